@@ -2,8 +2,10 @@ import {
   ExtensionContext,
   RelativePattern,
   Uri,
+  ViewColumn,
   WorkspaceFolder,
   WorkspaceFoldersChangeEvent,
+  window,
   workspace,
 } from "vscode";
 import {
@@ -12,6 +14,8 @@ import {
 } from "vscode-languageclient/browser";
 
 const extensionName = "wgsl-analyzer";
+const projectConfigFile = "wgsl-analyzer.toml";
+const formatConfigFile = ".wgslfmt.toml";
 
 const clients: Map<string, LanguageClient> = new Map();
 
@@ -58,6 +62,18 @@ async function startClient(folder: WorkspaceFolder, context: ExtensionContext) {
     ],
     diagnosticCollectionName: extensionName,
     workspaceFolder: folder,
+    synchronize: { configurationSection: extensionName },
+    middleware: {
+      // Commands executed by the server return a document to display
+      executeCommand: async (command, args, next) => {
+        const output = await next(command, args);
+        if (output?.content !== undefined) {
+          const document = await workspace.openTextDocument(output);
+          await window.showTextDocument(document, ViewColumn.Beside);
+        }
+        return output;
+      },
+    },
   };
 
   const client = new LanguageClient(
@@ -69,6 +85,41 @@ async function startClient(folder: WorkspaceFolder, context: ExtensionContext) {
 
   clients.set(folder.uri.toString(), client);
   await client.start();
+
+  // The server can not read files, so configuration files are sent whenever they change
+  const sendConfigFile = async (method: string, uri: Uri, deleted: boolean) => {
+    let text = null;
+    if (!deleted) {
+      try {
+        text = new TextDecoder().decode(await workspace.fs.readFile(uri));
+      } catch {
+        // The file does not exist
+      }
+    }
+    await client.sendNotification(method, { uri: uri.toString(), text });
+  };
+
+  const watchConfigFiles = (pattern: string, method: string) => {
+    const watcher = workspace.createFileSystemWatcher(
+      new RelativePattern(folder, pattern)
+    );
+    watcher.onDidCreate((uri) => sendConfigFile(method, uri, false));
+    watcher.onDidChange((uri) => sendConfigFile(method, uri, false));
+    watcher.onDidDelete((uri) => sendConfigFile(method, uri, true));
+    context.subscriptions.push(watcher);
+  };
+
+  const projectConfig = Uri.joinPath(folder.uri, projectConfigFile);
+  watchConfigFiles(projectConfigFile, "wgsl-analyzer/didChangeProjectConfig");
+  await sendConfigFile("wgsl-analyzer/didChangeProjectConfig", projectConfig, false);
+
+  watchConfigFiles(`**/${formatConfigFile}`, "wgsl-analyzer/didChangeFormatConfig");
+  const formatConfigs = await workspace.findFiles(
+    new RelativePattern(folder, `**/${formatConfigFile}`)
+  );
+  for (const uri of formatConfigs) {
+    await sendConfigFile("wgsl-analyzer/didChangeFormatConfig", uri, false);
+  }
 }
 
 async function stopClient(folder: string) {
@@ -35,10 +35,27 @@ connection.onInitialize(() => {
         change: TextDocumentSyncKind.Full,
       },
       completionProvider: {
-        triggerCharacters: ["."],
+        triggerCharacters: [".", "@"],
       },
       documentFormattingProvider: true,
+      documentRangeFormattingProvider: true,
+      documentOnTypeFormattingProvider: {
+        firstTriggerCharacter: "}",
+        moreTriggerCharacter: [";"],
+      },
+      hoverProvider: true,
       documentSymbolProvider: true,
+      workspaceSymbolProvider: true,
+      codeActionProvider: true,
+      codeLensProvider: { resolveProvider: false },
+      documentLinkProvider: { resolveProvider: false },
+      executeCommandProvider: {
+        commands: [
+          "wgsl-analyzer.generateRustStructs",
+          "wgsl-analyzer.reflectBindGroups",
+          "wgsl-analyzer.translate",
+        ],
+      },
       workspace: {
         workspaceFolders: { supported: true },
         fileOperations: {
@@ -58,7 +75,9 @@ addEventListener("message", loadWasmModule);
 function startServer({ WGSLLanguageServer }) {
   console.log("WGSL wasm module loaded... Starting server");
 
-  const wgsl_ls = new WGSLLanguageServer(connection.sendDiagnostics);
+  const wgsl_ls = new WGSLLanguageServer(connection.sendDiagnostics, (params) =>
+    connection.sendNotification("window/showMessage", params)
+  );
 
   connection.onNotification((...args) => wgsl_ls.onNotification(...args));
 
@@ -66,10 +85,28 @@ function startServer({ WGSLLanguageServer }) {
     JSON.parse(wgsl_ls.onCompletion(args[0]))
   );
 
+  connection.onHover((arg) => JSON.parse(wgsl_ls.onHover(arg)));
+
   connection.onDocumentSymbol((arg) =>
     JSON.parse(wgsl_ls.onDocumentSymbol(arg))
   );
 
+  connection.onWorkspaceSymbol((arg) =>
+    JSON.parse(wgsl_ls.onWorkspaceSymbol(arg))
+  );
+
+  connection.onCodeLens((arg) => JSON.parse(wgsl_ls.onCodeLens(arg)));
+
+  connection.onDocumentLinks((arg) =>
+    JSON.parse(wgsl_ls.onDocumentLinks(arg))
+  );
+
+  connection.onCodeAction((arg) => JSON.parse(wgsl_ls.onCodeAction(arg)));
+
+  connection.onExecuteCommand((arg) =>
+    JSON.parse(wgsl_ls.onExecuteCommand(arg))
+  );
+
   connection.onDocumentFormatting((arg) => {
     let res = wgsl_ls.onDocumentFormatting(JSON.stringify(arg));
     if (res == undefined) {
@@ -78,4 +115,22 @@ function startServer({ WGSLLanguageServer }) {
       return JSON.parse(res);
     }
   });
+
+  connection.onDocumentRangeFormatting((arg) => {
+    let res = wgsl_ls.onDocumentRangeFormatting(JSON.stringify(arg));
+    if (res == undefined) {
+      return res;
+    } else {
+      return JSON.parse(res);
+    }
+  });
+
+  connection.onDocumentOnTypeFormatting((arg) => {
+    let res = wgsl_ls.onDocumentOnTypeFormatting(JSON.stringify(arg));
+    if (res == undefined) {
+      return res;
+    } else {
+      return JSON.parse(res);
+    }
+  });
 }
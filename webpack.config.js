@@ -12,6 +12,10 @@ module.exports = {
     new WasmPackPlugin({
       crateDirectory: path.resolve(__dirname, "./wgsl-language-server"),
       outDir: path.resolve(__dirname, "./dist/pkg"),
+      // Optional cargo features, such as the translation backends
+      extraArgs: process.env.WGSL_ANALYZER_FEATURES
+        ? `-- --features ${process.env.WGSL_ANALYZER_FEATURES}`
+        : "",
     }),
   ],
   target: "webworker",
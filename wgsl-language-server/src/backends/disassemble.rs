@@ -0,0 +1,506 @@
+// A textual representation of a SPIR-V binary, in the style of `spirv-dis`.
+// Operands are decoded for the instructions emitted by naga, any others are printed as ids.
+
+use std::collections::{HashMap, HashSet};
+
+use spirv::{BuiltIn, GLOp, Op};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operand {
+    Id,
+    Literal,
+    LiteralString,
+    // A bitmask, such as `FunctionControl` or `MemoryAccess`
+    Mask,
+    Capability,
+    AddressingModel,
+    MemoryModel,
+    ExecutionModel,
+    ExecutionMode,
+    Decoration,
+    StorageClass,
+    Dim,
+    ImageFormat,
+    SourceLanguage,
+    GroupOperation,
+    ExtInstruction,
+}
+
+use Operand::*;
+
+// Whether an instruction has a result type and a result id
+fn result_layout(op: Op) -> (bool, bool) {
+    match op {
+        Op::Nop
+        | Op::SourceContinued
+        | Op::Source
+        | Op::SourceExtension
+        | Op::Name
+        | Op::MemberName
+        | Op::Line
+        | Op::NoLine
+        | Op::Extension
+        | Op::MemoryModel
+        | Op::EntryPoint
+        | Op::ExecutionMode
+        | Op::ExecutionModeId
+        | Op::Capability
+        | Op::Decorate
+        | Op::MemberDecorate
+        | Op::DecorateId
+        | Op::DecorateString
+        | Op::MemberDecorateString
+        | Op::GroupDecorate
+        | Op::GroupMemberDecorate
+        | Op::Store
+        | Op::CopyMemory
+        | Op::CopyMemorySized
+        | Op::FunctionEnd
+        | Op::ImageWrite
+        | Op::ControlBarrier
+        | Op::MemoryBarrier
+        | Op::AtomicStore
+        | Op::EmitVertex
+        | Op::EndPrimitive
+        | Op::LoopMerge
+        | Op::SelectionMerge
+        | Op::Branch
+        | Op::BranchConditional
+        | Op::Switch
+        | Op::Kill
+        | Op::Return
+        | Op::ReturnValue
+        | Op::Unreachable
+        | Op::LifetimeStart
+        | Op::LifetimeStop
+        | Op::ModuleProcessed
+        | Op::TerminateInvocation
+        | Op::DemoteToHelperInvocation
+        | Op::TypeForwardPointer
+        | Op::RayQueryInitializeKHR
+        | Op::RayQueryTerminateKHR
+        | Op::RayQueryGenerateIntersectionKHR
+        | Op::RayQueryConfirmIntersectionKHR
+        | Op::SetMeshOutputsEXT
+        | Op::EmitMeshTasksEXT => (false, false),
+        Op::ExtInstImport
+        | Op::String
+        | Op::DecorationGroup
+        | Op::Label
+        | Op::TypeVoid
+        | Op::TypeBool
+        | Op::TypeInt
+        | Op::TypeFloat
+        | Op::TypeVector
+        | Op::TypeMatrix
+        | Op::TypeImage
+        | Op::TypeSampler
+        | Op::TypeSampledImage
+        | Op::TypeArray
+        | Op::TypeRuntimeArray
+        | Op::TypeStruct
+        | Op::TypeOpaque
+        | Op::TypePointer
+        | Op::TypeFunction
+        | Op::TypeEvent
+        | Op::TypeDeviceEvent
+        | Op::TypeReserveId
+        | Op::TypeQueue
+        | Op::TypePipe
+        | Op::TypeAccelerationStructureKHR
+        | Op::TypeRayQueryKHR => (false, true),
+        _ => (true, true),
+    }
+}
+
+// The operands following the result type and id, and the operands repeated after those
+fn operand_layout(op: Op) -> (&'static [Operand], &'static [Operand]) {
+    match op {
+        Op::Capability => (&[Capability], &[]),
+        Op::Extension | Op::SourceExtension | Op::ModuleProcessed => (&[LiteralString], &[]),
+        Op::ExtInstImport | Op::String => (&[LiteralString], &[]),
+        Op::MemoryModel => (&[AddressingModel, MemoryModel], &[]),
+        Op::EntryPoint => (&[ExecutionModel, Id, LiteralString], &[Id]),
+        Op::ExecutionMode => (&[Id, ExecutionMode], &[Literal]),
+        Op::ExecutionModeId => (&[Id, ExecutionMode], &[Id]),
+        Op::Source => (&[SourceLanguage, Literal, Id, LiteralString], &[]),
+        Op::Name => (&[Id, LiteralString], &[]),
+        Op::MemberName => (&[Id, Literal, LiteralString], &[]),
+        Op::Decorate => (&[Id, Decoration], &[Literal]),
+        Op::MemberDecorate => (&[Id, Literal, Decoration], &[Literal]),
+        Op::DecorateString => (&[Id, Decoration], &[LiteralString]),
+        Op::MemberDecorateString => (&[Id, Literal, Decoration], &[LiteralString]),
+        Op::TypeInt => (&[Literal, Literal], &[]),
+        Op::TypeFloat => (&[Literal], &[]),
+        Op::TypeVector | Op::TypeMatrix => (&[Id, Literal], &[]),
+        Op::TypeImage => (
+            &[
+                Id,
+                Dim,
+                Literal,
+                Literal,
+                Literal,
+                Literal,
+                Literal,
+                ImageFormat,
+            ],
+            &[Literal],
+        ),
+        Op::TypePointer => (&[StorageClass, Id], &[]),
+        Op::TypeForwardPointer => (&[Id, StorageClass], &[]),
+        Op::Variable => (&[StorageClass], &[Id]),
+        Op::Constant | Op::SpecConstant => (&[], &[Literal]),
+        Op::Function => (&[Mask, Id], &[]),
+        Op::SelectionMerge => (&[Id, Mask], &[]),
+        Op::LoopMerge => (&[Id, Id, Mask], &[Literal]),
+        Op::Switch => (&[Id, Id], &[Literal, Id]),
+        Op::ExtInst => (&[Id, ExtInstruction], &[Id]),
+        Op::Load => (&[Id], &[Mask]),
+        Op::Store | Op::CopyMemory => (&[Id, Id], &[Mask]),
+        Op::CompositeExtract => (&[Id], &[Literal]),
+        Op::CompositeInsert | Op::VectorShuffle => (&[Id, Id], &[Literal]),
+        Op::ArrayLength => (&[Id, Literal], &[]),
+        Op::ImageSampleImplicitLod
+        | Op::ImageSampleExplicitLod
+        | Op::ImageFetch
+        | Op::ImageRead
+        | Op::ImageQuerySizeLod => (&[Id, Id, Mask], &[Id]),
+        Op::ImageSampleDrefImplicitLod
+        | Op::ImageSampleDrefExplicitLod
+        | Op::ImageGather
+        | Op::ImageDrefGather
+        | Op::ImageWrite => (&[Id, Id, Id, Mask], &[Id]),
+        Op::GroupNonUniformIAdd
+        | Op::GroupNonUniformFAdd
+        | Op::GroupNonUniformIMul
+        | Op::GroupNonUniformFMul
+        | Op::GroupNonUniformSMin
+        | Op::GroupNonUniformUMin
+        | Op::GroupNonUniformFMin
+        | Op::GroupNonUniformSMax
+        | Op::GroupNonUniformUMax
+        | Op::GroupNonUniformFMax
+        | Op::GroupNonUniformBitwiseAnd
+        | Op::GroupNonUniformBitwiseOr
+        | Op::GroupNonUniformBitwiseXor
+        | Op::GroupNonUniformLogicalAnd
+        | Op::GroupNonUniformLogicalOr
+        | Op::GroupNonUniformLogicalXor
+        | Op::GroupNonUniformBallotBitCount => (&[Id, GroupOperation], &[Id]),
+        _ => (&[], &[Id]),
+    }
+}
+
+// Decodes a nul terminated string, returning it along with the number of words it occupies
+fn decode_string(words: &[u32]) -> (String, usize) {
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    let len = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    let string = String::from_utf8_lossy(&bytes[..len]).into_owned();
+    (string, (len / 4 + 1).min(words.len()))
+}
+
+// Widens the bits of a half precision float, which `f32` represents exactly
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+fn enum_name<T: std::fmt::Debug>(value: Option<T>, word: u32) -> String {
+    value.map_or_else(|| word.to_string(), |value| format!("{value:?}"))
+}
+
+pub fn disassemble(words: &[u32]) -> Result<String, String> {
+    if words.len() < 5 || words[0] != spirv::MAGIC_NUMBER {
+        return Err("Not a SPIR-V module".to_owned());
+    }
+
+    let mut lines = vec![
+        "; SPIR-V".to_owned(),
+        format!(
+            "; Version: {}.{}",
+            (words[1] >> 16) & 0xff,
+            (words[1] >> 8) & 0xff
+        ),
+        format!("; Generator: {:#010x}", words[2]),
+        format!("; Bound: {}", words[3]),
+        format!("; Schema: {}", words[4]),
+    ];
+
+    // The width of each float type
+    let mut float_types = HashMap::new();
+    let mut glsl_std = HashSet::new();
+    let mut offset = 5;
+
+    while offset < words.len() {
+        let word_count = (words[offset] >> 16) as usize;
+        let opcode = words[offset] & 0xffff;
+
+        if word_count == 0 || offset + word_count > words.len() {
+            return Err(format!("Invalid instruction at word {offset}"));
+        }
+
+        let mut operands = &words[offset + 1..offset + word_count];
+        offset += word_count;
+
+        let Some(op) = Op::from_u32(opcode) else {
+            lines.push(format!("{:15}OpUnknown{opcode} {operands:?}", ""));
+            continue;
+        };
+
+        let (has_type, has_result) = result_layout(op);
+        let mut parts = vec![];
+
+        let mut result_type = None;
+        if has_type && let Some((ty, rest)) = operands.split_first() {
+            result_type = Some(*ty);
+            parts.push(format!("%{ty}"));
+            operands = rest;
+        }
+
+        let mut result = None;
+        if has_result && let Some((id, rest)) = operands.split_first() {
+            result = Some(*id);
+            operands = rest;
+        }
+
+        let literals = operands;
+        let (fixed, repeated) = operand_layout(op);
+        let float_width = match op {
+            Op::Constant | Op::SpecConstant => result_type.and_then(|ty| float_types.get(&ty)),
+            _ => None,
+        };
+        let mut builtin_decoration = false;
+        let mut ext_set = None;
+
+        let kinds = fixed.iter().chain(repeated.iter().cycle());
+        for kind in kinds {
+            let Some(&word) = operands.first() else {
+                break;
+            };
+
+            let mut consumed = 1;
+            let part = match kind {
+                Id => format!("%{word}"),
+                Literal if builtin_decoration => enum_name(BuiltIn::from_u32(word), word),
+                // Literals wider than a word are split into words, lowest order first
+                Literal if float_width == Some(&64) && operands.len() == 2 => {
+                    consumed = 2;
+                    let bits = u64::from(word) | (u64::from(operands[1]) << 32);
+                    format!("{:?}", f64::from_bits(bits))
+                }
+                Literal if float_width == Some(&32) => format!("{:?}", f32::from_bits(word)),
+                Literal if float_width == Some(&16) => format!("{:?}", f16_to_f32(word as u16)),
+                Literal => word.to_string(),
+                LiteralString => {
+                    let (string, words) = decode_string(operands);
+                    consumed = words;
+                    format!("{string:?}")
+                }
+                Mask if word == 0 => "None".to_owned(),
+                Mask => format!("{word:#x}"),
+                Capability => enum_name(spirv::Capability::from_u32(word), word),
+                AddressingModel => enum_name(spirv::AddressingModel::from_u32(word), word),
+                MemoryModel => enum_name(spirv::MemoryModel::from_u32(word), word),
+                ExecutionModel => enum_name(spirv::ExecutionModel::from_u32(word), word),
+                ExecutionMode => enum_name(spirv::ExecutionMode::from_u32(word), word),
+                Decoration => {
+                    builtin_decoration = word == spirv::Decoration::BuiltIn as u32;
+                    enum_name(spirv::Decoration::from_u32(word), word)
+                }
+                StorageClass => enum_name(spirv::StorageClass::from_u32(word), word),
+                Dim => enum_name(spirv::Dim::from_u32(word), word),
+                ImageFormat => enum_name(spirv::ImageFormat::from_u32(word), word),
+                SourceLanguage => enum_name(spirv::SourceLanguage::from_u32(word), word),
+                GroupOperation => enum_name(spirv::GroupOperation::from_u32(word), word),
+                ExtInstruction if ext_set.is_some_and(|set| glsl_std.contains(&set)) => {
+                    enum_name(GLOp::from_u32(word), word)
+                }
+                ExtInstruction => word.to_string(),
+            };
+
+            if op == Op::ExtInst && *kind == Id && ext_set.is_none() {
+                ext_set = Some(word);
+            }
+
+            parts.push(part);
+            operands = &operands[consumed.min(operands.len())..];
+        }
+
+        match (op, result) {
+            (Op::TypeFloat, Some(id)) => {
+                float_types.insert(id, literals.first().copied().unwrap_or(32));
+            }
+            (Op::ExtInstImport, Some(id))
+                if parts.first().is_some_and(|it| it.contains("GLSL")) =>
+            {
+                glsl_std.insert(id);
+            }
+            _ => {}
+        }
+
+        let instruction = std::iter::once(format!("Op{op:?}"))
+            .chain(parts)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        lines.push(match result {
+            Some(id) => format!("{:>12} = {instruction}", format!("%{id}")),
+            None => format!("{:15}{instruction}", ""),
+        });
+    }
+
+    Ok(lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn instructions_are_decoded() {
+        let module = naga::front::wgsl::parse_str(
+            "@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(sqrt(0.25)); }",
+        )
+        .unwrap();
+        let info = naga::valid::Validator::new(Default::default(), Default::default())
+            .validate(&module)
+            .unwrap();
+        let words = naga::back::spv::write_vec(&module, &info, &Default::default(), None).unwrap();
+
+        let text = disassemble(&words).unwrap();
+
+        assert!(text.starts_with("; SPIR-V\n; Version: 1."));
+        assert!(text.contains("OpCapability Shader"));
+        assert!(text.contains("OpMemoryModel Logical GLSL450"));
+        assert!(text.contains("OpEntryPoint Fragment %"));
+        assert!(text.contains("OpExecutionMode %"));
+        assert!(text.contains(" = OpTypeFloat 32"));
+        assert!(text.contains("OpDecorate %"));
+        assert!(text.contains("Location 0"));
+        assert!(text.contains("OpConstant %"));
+    }
+
+    fn module(instructions: &[(Op, &[u32])]) -> Vec<u32> {
+        let mut words = vec![spirv::MAGIC_NUMBER, 0x0001_0300, 0, 32, 0];
+        for (op, operands) in instructions {
+            words.push(((operands.len() as u32 + 1) << 16) | *op as u32);
+            words.extend_from_slice(operands);
+        }
+        words
+    }
+
+    // A nul terminated string, padded to a whole number of words
+    fn string(string: &str) -> Vec<u32> {
+        let mut bytes = string.as_bytes().to_vec();
+        bytes.resize(string.len() / 4 * 4 + 4, 0);
+        bytes
+            .chunks(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    fn lines(instructions: &[(Op, &[u32])]) -> Vec<String> {
+        disassemble(&module(instructions))
+            .unwrap()
+            .lines()
+            .skip(5)
+            .map(|line| line.trim().to_owned())
+            .collect()
+    }
+
+    #[test]
+    pub fn constants() {
+        let pi = std::f32::consts::PI.to_bits();
+        let [low, high] = [2.5f64.to_bits() as u32, (2.5f64.to_bits() >> 32) as u32];
+
+        assert_eq!(
+            lines(&[
+                (Op::TypeFloat, &[1, 16]),
+                (Op::Constant, &[1, 2, 0x3c00]),
+                (Op::Constant, &[1, 3, 0xc100]),
+                (Op::Constant, &[1, 4, 0x0001]),
+                (Op::TypeFloat, &[5, 32]),
+                (Op::Constant, &[5, 6, pi]),
+                (Op::TypeFloat, &[7, 64]),
+                (Op::Constant, &[7, 8, low, high]),
+                (Op::TypeInt, &[9, 32, 1]),
+                (Op::Constant, &[9, 10, 42]),
+            ]),
+            [
+                "%1 = OpTypeFloat 16",
+                "%2 = OpConstant %1 1.0",
+                "%3 = OpConstant %1 -2.5",
+                "%4 = OpConstant %1 5.9604645e-8",
+                "%5 = OpTypeFloat 32",
+                "%6 = OpConstant %5 3.1415927",
+                "%7 = OpTypeFloat 64",
+                "%8 = OpConstant %7 2.5",
+                "%9 = OpTypeInt 32 1",
+                "%10 = OpConstant %9 42",
+            ]
+        );
+    }
+
+    #[test]
+    pub fn decorations() {
+        let builtin = spirv::Decoration::BuiltIn as u32;
+        let location = spirv::Decoration::Location as u32;
+        let offset = spirv::Decoration::Offset as u32;
+        let block = spirv::Decoration::Block as u32;
+
+        assert_eq!(
+            lines(&[
+                (Op::Decorate, &[1, builtin, BuiltIn::FragCoord as u32]),
+                (Op::Decorate, &[2, location, 3]),
+                (Op::Decorate, &[4, block]),
+                (Op::MemberDecorate, &[4, 1, offset, 16]),
+            ]),
+            [
+                "OpDecorate %1 BuiltIn FragCoord",
+                "OpDecorate %2 Location 3",
+                "OpDecorate %4 Block",
+                "OpMemberDecorate %4 1 Offset 16",
+            ]
+        );
+    }
+
+    #[test]
+    pub fn strings() {
+        let fragment = spirv::ExecutionModel::Fragment as u32;
+        let name = [&[1][..], &string("main")].concat();
+        let member_name = [&[2, 0][..], &string("uv")].concat();
+        let entry_point = [&[fragment, 1][..], &string("fs_main"), &[3, 4]].concat();
+        let file = [&[5][..], &string("shader.wgsl")].concat();
+
+        assert_eq!(
+            lines(&[
+                (Op::Name, &name),
+                (Op::MemberName, &member_name),
+                (Op::EntryPoint, &entry_point),
+                (Op::String, &file),
+            ]),
+            [
+                "OpName %1 \"main\"",
+                "OpMemberName %2 0 \"uv\"",
+                "OpEntryPoint Fragment %1 \"fs_main\" %3 %4",
+                "%5 = OpString \"shader.wgsl\"",
+            ]
+        );
+    }
+
+    #[test]
+    pub fn invalid_binary() {
+        assert!(disassemble(&[1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,439 @@
+// Translation of modules through naga's backends. Each backend is behind a cargo feature,
+// so the default wasm build does not include any of them.
+
+#[cfg(feature = "spirv")]
+mod disassemble;
+mod portability;
+
+pub use portability::{PortabilityError, portability_errors};
+
+use naga::{
+    Module, Span,
+    valid::{Capabilities, ModuleInfo, ValidationFlags, Validator},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Msl,
+    Hlsl,
+    Glsl,
+    Spirv,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "msl" => Ok(Backend::Msl),
+            "hlsl" => Ok(Backend::Hlsl),
+            "glsl" => Ok(Backend::Glsl),
+            "spirv" => Ok(Backend::Spirv),
+            other => Err(format!(
+                "Unknown backend `{other}`, expected `msl`, `hlsl`, `glsl` or `spirv`"
+            )),
+        }
+    }
+}
+
+impl Backend {
+    pub const ALL: [Backend; 4] = [Backend::Msl, Backend::Hlsl, Backend::Glsl, Backend::Spirv];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Msl => "MSL",
+            Backend::Hlsl => "HLSL",
+            Backend::Glsl => "GLSL",
+            Backend::Spirv => "SPIR-V",
+        }
+    }
+
+    // Language of the translated output, as a VSCode language identifier
+    pub fn language(&self) -> &'static str {
+        match self {
+            Backend::Msl => "cpp",
+            Backend::Hlsl => "hlsl",
+            Backend::Glsl => "c",
+            Backend::Spirv => "plaintext",
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Backend::Msl => cfg!(feature = "msl"),
+            Backend::Hlsl => cfg!(feature = "hlsl"),
+            Backend::Glsl => cfg!(feature = "glsl"),
+            Backend::Spirv => cfg!(feature = "spirv"),
+        }
+    }
+
+    pub fn enabled() -> impl Iterator<Item = Backend> {
+        Backend::ALL.into_iter().filter(Backend::is_enabled)
+    }
+}
+
+/// Translates a validated module, or a single entry point of it, with the given backend.
+/// A single entry point is validated again with the `capabilities` the module was validated with.
+pub fn translate(
+    module: &Module,
+    info: &ModuleInfo,
+    backend: Backend,
+    entry_point: Option<&str>,
+    capabilities: Capabilities,
+) -> Result<String, String> {
+    if !backend.is_enabled() {
+        return Err(format!(
+            "The {} backend is not enabled in this build",
+            backend.name()
+        ));
+    }
+
+    let (module, info) = match entry_point {
+        Some(name) => single_entry_point(module, name, capabilities)?,
+        None => (module.clone(), info.clone()),
+    };
+
+    write(&module, &info, backend).map_err(|err| err.message)
+}
+
+/// An error of a backend, with the span of the code causing it when it can be found.
+struct WriteError {
+    message: String,
+    span: Option<Span>,
+}
+
+#[cfg(any(feature = "msl", feature = "hlsl", feature = "glsl", feature = "spirv"))]
+impl WriteError {
+    fn new(err: impl ToString, span: Option<Span>) -> Self {
+        Self {
+            message: err.to_string(),
+            span,
+        }
+    }
+}
+
+// The first type which is, or is made of, the scalar a backend does not support
+#[cfg(any(feature = "hlsl", feature = "glsl"))]
+fn scalar_type_span(module: &Module, unsupported: naga::Scalar) -> Option<Span> {
+    use naga::TypeInner;
+
+    module.types.iter().find_map(|(handle, ty)| match ty.inner {
+        TypeInner::Scalar(scalar)
+        | TypeInner::Vector { scalar, .. }
+        | TypeInner::Matrix { scalar, .. }
+            if scalar == unsupported =>
+        {
+            Some(module.types.get_span(handle))
+        }
+        _ => None,
+    })
+}
+
+// GLSL is always written as GLSL ES 3.0, since that is what WebGL2 supports. Previews and
+// portability warnings use the same version, so a preview fails where a warning is shown.
+#[cfg(feature = "glsl")]
+const GLSL_ES_VERSION: u16 = 300;
+
+#[cfg(any(feature = "msl", feature = "hlsl", feature = "glsl", feature = "spirv"))]
+fn write(module: &Module, info: &ModuleInfo, backend: Backend) -> Result<String, WriteError> {
+    // Backends require overrides to be replaced by their values
+    let (module, info) =
+        naga::back::pipeline_constants::process_overrides(module, info, &Default::default())
+            .map_err(|err| WriteError::new(format!("Failed to process overrides: {err}"), None))?;
+
+    match backend {
+        #[cfg(feature = "msl")]
+        Backend::Msl => {
+            use naga::back::msl::Error;
+
+            naga::back::msl::write_string(&module, &info, &Default::default(), &Default::default())
+                .map(|(source, _)| source)
+                .map_err(|err| {
+                    let span = match err {
+                        Error::UnsupportedCompose(ty) | Error::UnsupportedArrayOfType(ty) => {
+                            Some(module.types.get_span(ty))
+                        }
+                        _ => None,
+                    };
+                    WriteError::new(err, span)
+                })
+        }
+        #[cfg(feature = "hlsl")]
+        Backend::Hlsl => {
+            let mut source = String::new();
+            let options = Default::default();
+            naga::back::hlsl::Writer::new(&mut source, &options)
+                .write(&module, &info, None)
+                .map_err(|err| {
+                    let span = match err {
+                        naga::back::hlsl::Error::UnsupportedScalar(scalar) => {
+                            scalar_type_span(&module, scalar)
+                        }
+                        _ => None,
+                    };
+                    WriteError::new(err, span)
+                })?;
+            Ok(source)
+        }
+        #[cfg(feature = "glsl")]
+        Backend::Glsl => {
+            let options = naga::back::glsl::Options {
+                version: naga::back::glsl::Version::new_gles(GLSL_ES_VERSION),
+                ..Default::default()
+            };
+            glsl::translate(&module, &info, &options)
+        }
+        #[cfg(feature = "spirv")]
+        Backend::Spirv => naga::back::spv::write_vec(&module, &info, &Default::default(), None)
+            .map_err(|err| err.to_string())
+            .and_then(|words| disassemble::disassemble(&words))
+            .map_err(|err| WriteError::new(err, None)),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("The {} backend is not enabled", backend.name()),
+    }
+}
+
+#[cfg(not(any(feature = "msl", feature = "hlsl", feature = "glsl", feature = "spirv")))]
+fn write(_module: &Module, _info: &ModuleInfo, backend: Backend) -> Result<String, WriteError> {
+    unreachable!("The {} backend is not enabled", backend.name())
+}
+
+// Removes every other entry point from the module, so only the code it uses is translated
+fn single_entry_point(
+    module: &Module,
+    name: &str,
+    capabilities: Capabilities,
+) -> Result<(Module, ModuleInfo), String> {
+    let mut module = module.clone();
+    module
+        .entry_points
+        .retain(|entry_point| entry_point.name == name);
+
+    if module.entry_points.is_empty() {
+        return Err(format!("No entry point named `{name}`"));
+    }
+
+    naga::compact::compact(&mut module);
+
+    let info = Validator::new(ValidationFlags::all(), capabilities)
+        .validate(&module)
+        .map_err(|err| err.emit_to_string(""))?;
+
+    Ok((module, info))
+}
+
+#[cfg(feature = "glsl")]
+pub mod glsl {
+    use naga::{
+        AddressSpace, Expression, ImageClass, ImageDimension, ImageQuery, Module, Scalar,
+        ShaderStage, Span, TypeInner,
+        back::glsl::{Error, Features, Options, PipelineOptions, Writer},
+        valid::ModuleInfo,
+    };
+
+    use super::{WriteError, scalar_type_span};
+
+    /// GLSL is written one entry point at a time, so each entry point is written in turn.
+    pub(super) fn translate(
+        module: &Module,
+        info: &ModuleInfo,
+        options: &Options,
+    ) -> Result<String, WriteError> {
+        let mut output = vec![];
+
+        for (index, entry_point) in module.entry_points.iter().enumerate() {
+            let mut source = String::new();
+            let pipeline_options = PipelineOptions {
+                shader_stage: entry_point.stage,
+                entry_point: entry_point.name.clone(),
+                multiview: None,
+            };
+
+            Writer::new(
+                &mut source,
+                module,
+                info,
+                options,
+                &pipeline_options,
+                Default::default(),
+            )
+            .and_then(|mut writer| writer.write())
+            .map_err(|err| {
+                let span = match err {
+                    Error::MissingFeatures(features) => {
+                        missing_features_span(module, info, index, features)
+                    }
+                    Error::UnsupportedScalar(scalar) => scalar_type_span(module, scalar),
+                    _ => None,
+                };
+                let message = match module.entry_points.len() {
+                    1 => err.to_string(),
+                    _ => format!("{}: {err}", entry_point.name),
+                };
+                WriteError::new(message, span)
+            })?;
+
+            output.push(format!("// {}\n{source}", entry_point.name));
+        }
+
+        Ok(output.join("\n"))
+    }
+
+    // Naga only reports which features the GLSL version lacks, so they are traced back to the
+    // globals, expressions and types requiring them. A compute entry point is unsupported as a
+    // whole, so nothing in it is singled out.
+    fn missing_features_span(
+        module: &Module,
+        info: &ModuleInfo,
+        index: usize,
+        missing: Features,
+    ) -> Option<Span> {
+        let entry_point = &module.entry_points[index];
+        if entry_point.stage == ShaderStage::Compute {
+            return None;
+        }
+
+        let entry_point_info = info.get_entry_point(index);
+        let global = module.global_variables.iter().find(|(handle, global)| {
+            let required = match global.space {
+                AddressSpace::Storage { .. } => {
+                    Features::BUFFER_STORAGE | Features::DYNAMIC_ARRAY_SIZE
+                }
+                AddressSpace::WorkGroup => Features::COMPUTE_SHADER,
+                _ => image_features(&module.types[global.ty].inner),
+            };
+            !entry_point_info[*handle].is_empty() && missing.intersects(required)
+        });
+        if let Some((handle, _)) = global {
+            return Some(module.global_variables.get_span(handle));
+        }
+
+        let functions = module
+            .functions
+            .iter()
+            .map(|(_, function)| function)
+            .chain([&entry_point.function]);
+        for function in functions {
+            let query = function.expressions.iter().find(|(_, expression)| {
+                let required = match expression {
+                    Expression::ImageQuery {
+                        query: ImageQuery::NumLevels,
+                        ..
+                    } => Features::TEXTURE_LEVELS,
+                    Expression::ImageQuery {
+                        query: ImageQuery::NumSamples,
+                        ..
+                    } => Features::TEXTURE_SAMPLES,
+                    _ => Features::empty(),
+                };
+                missing.intersects(required)
+            });
+            if let Some((handle, _)) = query {
+                return Some(function.expressions.get_span(handle));
+            }
+        }
+
+        if missing.contains(Features::ARRAY_OF_ARRAYS) {
+            let nested = module.types.iter().find(|(_, ty)| {
+                matches!(ty.inner, TypeInner::Array { base, .. }
+                    if matches!(module.types[base].inner, TypeInner::Array { .. }))
+            });
+            if let Some((handle, _)) = nested {
+                return Some(module.types.get_span(handle));
+            }
+        }
+
+        missing
+            .contains(Features::DOUBLE_TYPE)
+            .then(|| scalar_type_span(module, Scalar::F64))
+            .flatten()
+    }
+
+    fn image_features(ty: &TypeInner) -> Features {
+        let TypeInner::Image {
+            dim,
+            arrayed,
+            class,
+        } = *ty
+        else {
+            return Features::empty();
+        };
+
+        let mut features = match class {
+            ImageClass::Sampled { multi: true, .. } | ImageClass::Depth { multi: true } => {
+                Features::MULTISAMPLED_TEXTURES | Features::MULTISAMPLED_TEXTURE_ARRAYS
+            }
+            ImageClass::Storage { .. } => Features::IMAGE_LOAD_STORE | Features::FULL_IMAGE_FORMATS,
+            _ => Features::empty(),
+        };
+        if arrayed && dim == ImageDimension::Cube {
+            features |= Features::CUBE_TEXTURES_ARRAY;
+        }
+        features
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn validate(src: &str) -> (Module, ModuleInfo) {
+        let module = naga::front::wgsl::parse_str(src).unwrap();
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .unwrap();
+        (module, info)
+    }
+
+    const SHADER: &str = "
+@vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }
+@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }
+";
+
+    #[test]
+    pub fn single_entry_point_is_kept() {
+        let (module, _) = validate(SHADER);
+        let (module, _) = single_entry_point(&module, "fs_main", Capabilities::all()).unwrap();
+
+        let names: Vec<_> = module.entry_points.iter().map(|it| &it.name).collect();
+        assert_eq!(names, vec!["fs_main"]);
+        assert!(single_entry_point(&module, "missing", Capabilities::all()).is_err());
+    }
+
+    #[test]
+    pub fn single_entry_point_keeps_capabilities() {
+        let (module, _) = validate(
+            "@fragment fn fs_main() -> @location(0) vec4<f32> { let x = 1.0lf; return vec4<f32>(f32(x)); }",
+        );
+        assert!(single_entry_point(&module, "fs_main", Capabilities::FLOAT64).is_ok());
+        assert!(single_entry_point(&module, "fs_main", Capabilities::empty()).is_err());
+    }
+
+    #[test]
+    pub fn every_enabled_backend_translates() {
+        let (module, info) = validate(SHADER);
+
+        for backend in Backend::enabled() {
+            let output = translate(
+                &module,
+                &info,
+                backend,
+                Some("fs_main"),
+                Capabilities::all(),
+            )
+            .unwrap();
+            assert!(!output.is_empty(), "{} output is empty", backend.name());
+        }
+    }
+
+    #[test]
+    pub fn disabled_backends_report_an_error() {
+        let (module, info) = validate(SHADER);
+
+        for backend in Backend::ALL.into_iter().filter(|it| !it.is_enabled()) {
+            assert!(translate(&module, &info, backend, None, Capabilities::all()).is_err());
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// Reports entry points which validate, but which a backend fails to translate. Naga only
+// validates against its own capabilities, so features unsupported by a target, like storage
+// buffers on WebGL2, would otherwise only be reported when creating a pipeline.
+
+use std::ops::Range;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+use naga::{Module, valid::Capabilities};
+
+use super::{Backend, single_entry_point, write};
+use crate::{
+    config::Lint, document_tracker::TrackedDocument, lexer::Keyword, parser::find_declaration,
+    range_tools::new_location,
+};
+
+/// An entry point which a target backend fails to translate
+#[derive(Debug)]
+pub struct PortabilityError {
+    // The code causing the error when the backend allows finding it, or the entry point name
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+fn target_name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Glsl => "GLSL ES 3.0",
+        other => other.name(),
+    }
+}
+
+/// Translates each entry point of a valid module on its own with the target backends.
+/// Backends which are not enabled in this build are skipped.
+pub fn portability_errors(
+    module: &Module,
+    source: &str,
+    targets: &[Backend],
+    capabilities: Capabilities,
+) -> Vec<PortabilityError> {
+    let targets: Vec<_> = targets.iter().filter(|it| it.is_enabled()).collect();
+    if targets.is_empty() {
+        return vec![];
+    }
+
+    let mut errors = vec![];
+
+    for entry_point in &module.entry_points {
+        let Some((_, name)) = find_declaration(source, Keyword::Fn, &entry_point.name) else {
+            continue;
+        };
+
+        // Each entry point is translated on its own, so errors can be attributed to it
+        let Ok((module, info)) = single_entry_point(module, &entry_point.name, capabilities) else {
+            continue;
+        };
+
+        for &&backend in &targets {
+            let Err(err) = write(&module, &info, backend) else {
+                continue;
+            };
+
+            errors.push(PortabilityError {
+                range: err
+                    .span
+                    .and_then(|span| span.to_range())
+                    .unwrap_or(name.clone()),
+                message: format!(
+                    "Entry point `{}` can not be translated to {}: {}",
+                    entry_point.name,
+                    target_name(backend),
+                    err.message
+                ),
+            });
+        }
+    }
+
+    errors
+}
+
+impl TrackedDocument {
+    /// Warnings for the entry points which can not be translated to the target backends,
+    /// found when the document was last compiled.
+    pub fn portability_diagnostics(&self) -> Vec<Diagnostic> {
+        self.portability_errors
+            .iter()
+            .map(|error| Diagnostic {
+                range: new_location(error.range.clone(), &self.content, self.uri.clone()).range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Lint::Portability.code(),
+                message: error.message.clone(),
+                source: Some("wgsl-language-support".to_owned()),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::TextDocumentItem;
+
+    use super::*;
+    use crate::document_tracker::DocumentTracker;
+
+    fn diagnostics(text: &str, targets: &[Backend]) -> Vec<Diagnostic> {
+        let uri: lsp_types::Uri = "file:///shader.wgsl".parse().unwrap();
+        let mut tracker = DocumentTracker::new();
+        tracker.set_settings(serde_json::json!({
+            "wgsl-analyzer": { "targetBackends": targets }
+        }));
+        tracker.insert(TextDocumentItem {
+            uri: uri.clone(),
+            language_id: "wgsl".to_owned(),
+            version: 0,
+            text: text.to_owned(),
+        });
+        tracker.get(&uri).unwrap().portability_diagnostics()
+    }
+
+    const SHADER: &str = "
+@group(0) @binding(0) var<storage, read_write> counts: array<u32>;
+
+@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }
+
+@compute @workgroup_size(64) fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    counts[id.x] += 1u;
+}
+";
+
+    #[test]
+    pub fn no_targets() {
+        assert!(diagnostics(SHADER, &[]).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "glsl")]
+    pub fn webgl2_compute_shader() {
+        let diagnostics = diagnostics(SHADER, &[Backend::Glsl]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("Entry point `cs_main` can not be translated to GLSL ES 3.0")
+        );
+        assert_eq!(diagnostics[0].range.start.line, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "glsl")]
+    pub fn webgl2_storage_buffer() {
+        let src = "
+@group(0) @binding(0) var<storage> colors: array<vec4<f32>>;
+
+@fragment fn fs_main() -> @location(0) vec4<f32> { return colors[0]; }
+";
+        let diagnostics = diagnostics(src, &[Backend::Glsl]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        assert_eq!(diagnostics[0].range.end.line, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "glsl")]
+    pub fn webgl2_texture_query() {
+        let src = "
+@group(0) @binding(0) var t: texture_2d<f32>;
+
+@fragment fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(f32(textureNumLevels(t)));
+}
+";
+        let diagnostics = diagnostics(src, &[Backend::Glsl]);
+
+        assert_eq!(diagnostics.len(), 1);
+        let range = diagnostics[0].range;
+        assert_eq!(range.start.line, 4);
+        assert_eq!(
+            &src.lines().nth(4).unwrap()
+                [range.start.character as usize..range.end.character as usize],
+            "textureNumLevels"
+        );
+    }
+}
@@ -0,0 +1,436 @@
+// `wgsl-analyzer check`, which reports the same diagnostics as the language server for a set of
+// files, so they can be checked in CI
+
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use codespan_reporting::{
+    diagnostic::{Diagnostic as CodespanDiagnostic, Label},
+    files::SimpleFiles,
+    term::{
+        self,
+        termcolor::{ColorChoice, StandardStream},
+    },
+};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, TextDocumentItem, Uri};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{config::ProjectConfig, document_tracker::DocumentTracker, range_tools::string_range};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Human,
+    Json,
+    Sarif,
+}
+
+pub struct CheckedFile {
+    pub path: PathBuf,
+    pub uri: Uri,
+    pub content: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    path: String,
+    diagnostics: &'a [Diagnostic],
+}
+
+pub fn run(
+    paths: &[PathBuf],
+    format: OutputFormat,
+    project_config: Option<ProjectConfig>,
+) -> Result<ExitCode, String> {
+    let files = check_files(&collect_files(paths)?, project_config)?;
+
+    match format {
+        OutputFormat::Human => emit_human(&files)?,
+        OutputFormat::Json => {
+            let output: Vec<_> = files
+                .iter()
+                .map(|file| JsonFile {
+                    path: display_path(&file.path),
+                    diagnostics: &file.diagnostics,
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        OutputFormat::Sarif => {
+            println!("{}", serde_json::to_string_pretty(&sarif(&files)).unwrap());
+        }
+    }
+
+    let has_errors = files
+        .iter()
+        .flat_map(|file| &file.diagnostics)
+        .any(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+
+    Ok(match has_errors {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    })
+}
+
+// Expands directories into the `.wgsl` files they contain, skipping hidden directories
+pub fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = vec![];
+
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut entries: Vec<_> = std::fs::read_dir(path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                let wgsl = path.extension().is_some_and(|ext| ext == "wgsl");
+                !hidden && (path.is_dir() || wgsl)
+            })
+            .collect();
+
+        entries.sort();
+        files.extend(collect_files(&entries)?);
+    }
+
+    Ok(files)
+}
+
+/// Runs the files through a `DocumentTracker`, as if they were all open in an editor.
+pub fn check_files(
+    paths: &[PathBuf],
+    project_config: Option<ProjectConfig>,
+) -> Result<Vec<CheckedFile>, String> {
+    let mut tracker = DocumentTracker::new();
+    tracker.set_project_config(project_config);
+    let mut uris = vec![];
+
+    for path in paths {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let uri = path_to_uri(path)?;
+
+        tracker.insert(TextDocumentItem {
+            uri: uri.clone(),
+            language_id: "wgsl".to_owned(),
+            version: 0,
+            text,
+        });
+        uris.push((uri, path));
+    }
+
+    let mut diagnostics = tracker.get_diagnostics();
+
+    Ok(uris
+        .into_iter()
+        .map(|(uri, path)| {
+            let diagnostics = diagnostics
+                .iter_mut()
+                .find(|it| it.uri == uri)
+                .map(|it| std::mem::take(&mut it.diagnostics))
+                .unwrap_or_default();
+
+            CheckedFile {
+                path: path.clone(),
+                content: tracker.get(&uri).unwrap().content.clone(),
+                uri,
+                diagnostics: diagnostics
+                    .into_iter()
+                    .map(|diagnostic| Diagnostic {
+                        // Parse and validation errors are published without a severity
+                        severity: diagnostic.severity.or(Some(DiagnosticSeverity::ERROR)),
+                        ..diagnostic
+                    })
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+pub fn path_to_uri(path: &Path) -> Result<Uri, String> {
+    let path = std::fs::canonicalize(path)
+        .map_err(|err| format!("Failed to resolve {}: {err}", path.display()))?;
+
+    let mut uri = "file://".to_owned();
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+
+    for byte in path.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri += &format!("%{byte:02X}"),
+        }
+    }
+
+    uri.parse()
+        .map_err(|err| format!("Invalid path {}: {err}", path))
+}
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn emit_human(files: &[CheckedFile]) -> Result<(), String> {
+    let mut codespan_files = SimpleFiles::new();
+    let ids: Vec<_> = files
+        .iter()
+        .map(|file| codespan_files.add(display_path(&file.path), file.content.as_str()))
+        .collect();
+
+    let color = match std::io::stdout().is_terminal() {
+        true => ColorChoice::Auto,
+        false => ColorChoice::Never,
+    };
+    let writer = StandardStream::stdout(color);
+    let config = term::Config::default();
+
+    let (mut errors, mut warnings) = (0, 0);
+
+    for (file, &id) in files.iter().zip(&ids) {
+        for diagnostic in &file.diagnostics {
+            match diagnostic.severity {
+                Some(DiagnosticSeverity::ERROR) => errors += 1,
+                Some(DiagnosticSeverity::WARNING) => warnings += 1,
+                _ => {}
+            }
+
+            let diagnostic = to_codespan(diagnostic, file, id, files, &ids);
+            term::emit(&mut writer.lock(), &config, &codespan_files, &diagnostic)
+                .map_err(|err| err.to_string())?;
+        }
+    }
+
+    eprintln!(
+        "Checked {}: {}, {}",
+        count(files.len(), "file"),
+        count(errors, "error"),
+        count(warnings, "warning")
+    );
+
+    Ok(())
+}
+
+pub(super) fn count(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {noun}"),
+        _ => format!("{count} {noun}s"),
+    }
+}
+
+// Codespan requires labels to end after they start, which ranges clamped to the file may not
+fn label_range(content: &str, range: lsp_types::Range) -> std::ops::Range<usize> {
+    let range = string_range(content, range);
+    range.start..range.end.max(range.start)
+}
+
+// Related information in the same place as the diagnostic becomes its label, or notes,
+// and related information elsewhere becomes secondary labels
+fn to_codespan(
+    diagnostic: &Diagnostic,
+    file: &CheckedFile,
+    id: usize,
+    files: &[CheckedFile],
+    ids: &[usize],
+) -> CodespanDiagnostic<usize> {
+    let severity = match diagnostic.severity {
+        Some(DiagnosticSeverity::WARNING) => codespan_reporting::diagnostic::Severity::Warning,
+        Some(DiagnosticSeverity::INFORMATION) => codespan_reporting::diagnostic::Severity::Note,
+        Some(DiagnosticSeverity::HINT) => codespan_reporting::diagnostic::Severity::Help,
+        _ => codespan_reporting::diagnostic::Severity::Error,
+    };
+
+    let mut primary = Label::primary(id, label_range(&file.content, diagnostic.range));
+    let mut labels = vec![];
+    let mut notes = vec![];
+
+    for related in diagnostic.related_information.iter().flatten() {
+        let index = files
+            .iter()
+            .position(|file| file.uri == related.location.uri);
+
+        match index {
+            Some(index) if ids[index] == id && related.location.range == diagnostic.range => {
+                if primary.message.is_empty() {
+                    primary.message = related.message.clone();
+                } else if related.message != primary.message {
+                    notes.push(related.message.clone());
+                }
+            }
+            Some(index) => labels.push(
+                Label::secondary(
+                    ids[index],
+                    label_range(&files[index].content, related.location.range),
+                )
+                .with_message(&related.message),
+            ),
+            None => notes.push(related.message.clone()),
+        }
+    }
+
+    labels.insert(0, primary);
+
+    let mut codespan_diagnostic = CodespanDiagnostic::new(severity)
+        .with_message(&diagnostic.message)
+        .with_labels(labels)
+        .with_notes(notes);
+
+    // Lints are identified by their code
+    if let Some(NumberOrString::String(code)) = &diagnostic.code {
+        codespan_diagnostic = codespan_diagnostic.with_code(code);
+    }
+
+    codespan_diagnostic
+}
+
+// SARIF 2.1.0, as consumed by code scanning tools. Lines and columns are 1-based.
+fn sarif(files: &[CheckedFile]) -> serde_json::Value {
+    let results: Vec<_> = files
+        .iter()
+        .flat_map(|file| {
+            file.diagnostics.iter().map(|diagnostic| {
+                let level = match diagnostic.severity {
+                    Some(DiagnosticSeverity::ERROR) => "error",
+                    Some(DiagnosticSeverity::WARNING) => "warning",
+                    _ => "note",
+                };
+                let range = diagnostic.range;
+
+                json!({
+                    "level": level,
+                    "message": { "text": diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": display_path(&file.path) },
+                            "region": {
+                                "startLine": range.start.line + 1,
+                                "startColumn": range.start.character + 1,
+                                "endLine": range.end.line + 1,
+                                "endColumn": range.end.character + 1,
+                            }
+                        }
+                    }]
+                })
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "wgsl-analyzer",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/unfinishedprogram/wgsl-analyzer",
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wgsl-analyzer-check-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (path, text) in files {
+            std::fs::write(dir.join(path), text).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    pub fn directories_are_checked() {
+        let dir = write_files(
+            "directories",
+            &[
+                ("valid.wgsl", "fn main() {}"),
+                ("invalid.wgsl", "fn main() { let x: u32 = 1.0; }"),
+                ("notes.txt", "not a shader"),
+            ],
+        );
+
+        let files = check_files(&collect_files(std::slice::from_ref(&dir)).unwrap(), None).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|it| it.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(names, vec!["invalid.wgsl", "valid.wgsl"]);
+        assert_eq!(
+            files[0].diagnostics[0].severity,
+            Some(DiagnosticSeverity::ERROR)
+        );
+        assert!(files[1].diagnostics.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn sarif_results() {
+        let dir = write_files("sarif", &[("shader.wgsl", "\nfn main() { let x = ; }")]);
+
+        let files = check_files(&[dir.join("shader.wgsl")], None).unwrap();
+        let sarif = sarif(&files);
+        let result = &sarif["runs"][0]["results"][0];
+
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn diagnostics_at_the_end_of_files() {
+        let dir = write_files(
+            "end",
+            &[
+                ("brace.wgsl", "fn main() {\n"),
+                ("comment.wgsl", "fn main() {}\n/* é"),
+            ],
+        );
+
+        let files = check_files(&collect_files(std::slice::from_ref(&dir)).unwrap(), None).unwrap();
+        let ids = [0, 1];
+
+        for (file, id) in files.iter().zip(ids) {
+            let diagnostic = to_codespan(&file.diagnostics[0], file, id, &files, &ids);
+            let range = diagnostic.labels[0].range.clone();
+
+            assert!(range.start > 0, "{}", file.path.display());
+            assert!(range.start <= range.end && range.end <= file.content.len());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn counts() {
+        assert_eq!(count(1, "file"), "1 file");
+        assert_eq!(count(0, "error"), "0 errors");
+        assert_eq!(count(2, "warning"), "2 warnings");
+    }
+}
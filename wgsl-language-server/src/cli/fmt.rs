@@ -0,0 +1,360 @@
+// `wgsl-analyzer fmt`, which formats files in place, or checks that they are already formatted
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use lsp_types::FormattingOptions;
+
+use super::check::{collect_files, count};
+use crate::{
+    config::FormatConfig,
+    fmt::{FormatStyle, format},
+    range_tools::position_at_byte_offset,
+};
+
+const FORMAT_CONFIG_FILE: &str = ".wgslfmt.toml";
+
+// The subset of `FormattingOptions` used by the formatter. These take precedence over the
+// closest `.wgslfmt.toml`, and the `format` section of the project configuration.
+#[derive(Debug, clap::Args)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level [default: 4]
+    #[arg(long)]
+    tab_size: Option<u32>,
+    /// Indent with tabs instead of spaces
+    #[arg(long)]
+    use_tabs: bool,
+    /// End the file with exactly one newline, after trimming any others
+    #[arg(long)]
+    insert_final_newline: bool,
+    /// Remove all newlines at the end of the file
+    #[arg(long)]
+    trim_final_newlines: bool,
+    /// Wrap lines longer than this where possible [default: 100]
+    #[arg(long)]
+    max_width: Option<usize>,
+}
+
+impl FormatOptions {
+    fn resolve(&self, config: &FormatConfig) -> (FormattingOptions, FormatStyle) {
+        let mut options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        };
+        config.apply(&mut options);
+
+        if let Some(tab_size) = self.tab_size {
+            options.tab_size = tab_size;
+        }
+        if self.use_tabs {
+            options.insert_spaces = false;
+        }
+        if self.insert_final_newline {
+            options.insert_final_newline = Some(true);
+        }
+        if self.trim_final_newlines {
+            options.trim_final_newlines = Some(true);
+        }
+
+        let mut style = config.style.clone();
+        if let Some(max_width) = self.max_width {
+            style.max_width = max_width;
+        }
+
+        (options, style)
+    }
+}
+
+/// Finds `.wgslfmt.toml` in `directory`, or the closest directory above it.
+fn find_format_config(directory: &Path) -> Result<Option<FormatConfig>, String> {
+    let directory = std::path::absolute(directory)
+        .map_err(|err| format!("Failed to resolve {}: {err}", directory.display()))?;
+
+    let Some(path) = directory
+        .ancestors()
+        .map(|dir| dir.join(FORMAT_CONFIG_FILE))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+
+    let text = std::fs::read_to_string(&path)
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+
+    FormatConfig::parse(&text).map(Some).map_err(|err| {
+        format!(
+            "Invalid configuration in {}: {}",
+            path.display(),
+            err.message
+        )
+    })
+}
+
+/// Formats `paths` in place, or standard input to standard output when no paths are given.
+/// With `check`, nothing is written and a diff is printed for every unformatted file.
+pub fn run(
+    paths: &[PathBuf],
+    check: bool,
+    options: &FormatOptions,
+    config: &FormatConfig,
+) -> Result<ExitCode, String> {
+    if paths.is_empty() || paths == [Path::new("-")] {
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|err| format!("Failed to read standard input: {err}"))?;
+
+        let formatted = format_source(&source, "<stdin>", Path::new("."), options, config)?;
+
+        if !check {
+            print!("{formatted}");
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        return Ok(match formatted == source {
+            true => ExitCode::SUCCESS,
+            false => {
+                print!("{}", unified_diff("<stdin>", &source, &formatted));
+                ExitCode::FAILURE
+            }
+        });
+    }
+
+    let mut unformatted = 0;
+
+    for path in collect_files(paths)? {
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        // The parent of a file name on its own is empty rather than `None`
+        let directory = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let name = path.display().to_string();
+        let formatted = format_source(&source, &name, directory, options, config)?;
+
+        if formatted == source {
+            continue;
+        }
+
+        unformatted += 1;
+
+        if check {
+            print!("{}", unified_diff(&name, &source, &formatted));
+        } else {
+            std::fs::write(&path, formatted)
+                .map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+        }
+    }
+
+    if check && unformatted > 0 {
+        let verb = match unformatted {
+            1 => "is",
+            _ => "are",
+        };
+        eprintln!("{} {verb} not formatted", count(unformatted, "file"));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn format_source(
+    source: &str,
+    name: &str,
+    directory: &Path,
+    options: &FormatOptions,
+    config: &FormatConfig,
+) -> Result<String, String> {
+    let format_config = find_format_config(directory)?;
+    let (options, style) = options.resolve(format_config.as_ref().unwrap_or(config));
+
+    let formatted =
+        format(source, &options, &style).ok_or_else(|| format!("Failed to format {name}"))?;
+
+    for range in &formatted.skipped {
+        let position = position_at_byte_offset(source, range.start);
+        eprintln!(
+            "warning: {name}:{}:{}: left {:?} unformatted, as it could not be lexed",
+            position.line + 1,
+            position.character + 1,
+            &source[range.clone()],
+        );
+    }
+
+    Ok(formatted.text)
+}
+
+enum Line<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+// Line diff from the longest common subsequence of the lines of both sources
+fn diff_lines<'a>(before: &[&'a str], after: &[&'a str]) -> Vec<Line<'a>> {
+    let mut lcs = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = match before[i] == after[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = vec![];
+
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            lines.push(Line::Equal(before[i]));
+            i += 1;
+            j += 1;
+        } else if i < before.len() && (j == after.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(Line::Delete(before[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Insert(after[j]));
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+/// A unified diff between two sources, with three lines of context around each change.
+pub fn unified_diff(name: &str, before: &str, after: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let before_lines: Vec<_> = before.split_inclusive('\n').collect();
+    let after_lines: Vec<_> = after.split_inclusive('\n').collect();
+    let lines = diff_lines(&before_lines, &after_lines);
+
+    let changed: Vec<_> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut output = format!("--- {name}\n+++ {name}\n");
+    let mut index = 0;
+
+    while index < changed.len() {
+        // Extend the hunk while the next change is within the context of the previous one
+        let start = changed[index].saturating_sub(CONTEXT);
+        let mut end = changed[index];
+        while index < changed.len() && changed[index] <= end + 2 * CONTEXT {
+            end = changed[index];
+            index += 1;
+        }
+        let end = (end + CONTEXT + 1).min(lines.len());
+
+        // Line numbers at the start of the hunk
+        let (mut before_line, mut after_line) = (1, 1);
+        for line in &lines[..start] {
+            match line {
+                Line::Equal(_) => {
+                    before_line += 1;
+                    after_line += 1;
+                }
+                Line::Delete(_) => before_line += 1,
+                Line::Insert(_) => after_line += 1,
+            }
+        }
+
+        let hunk = &lines[start..end];
+        let before_len = hunk
+            .iter()
+            .filter(|it| !matches!(it, Line::Insert(_)))
+            .count();
+        let after_len = hunk
+            .iter()
+            .filter(|it| !matches!(it, Line::Delete(_)))
+            .count();
+
+        output += &format!("@@ -{before_line},{before_len} +{after_line},{after_len} @@\n");
+
+        for line in hunk {
+            let (prefix, text) = match line {
+                Line::Equal(text) => (' ', text),
+                Line::Delete(text) => ('-', text),
+                Line::Insert(text) => ('+', text),
+            };
+
+            output.push(prefix);
+            output += text;
+            if !text.ends_with('\n') {
+                output += "\n\\ No newline at end of file\n";
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn paths_relative_to_the_current_directory() {
+        // Tests run in the directory of the package
+        let name = "wgsl-analyzer-fmt-relative.wgsl";
+        std::fs::write(name, "const a=1;").unwrap();
+
+        let options = FormatOptions {
+            tab_size: None,
+            use_tabs: false,
+            insert_final_newline: false,
+            trim_final_newlines: false,
+            max_width: None,
+        };
+        let result = run(&[PathBuf::from(name)], false, &options, &Default::default());
+        let formatted = std::fs::read_to_string(name).unwrap();
+        std::fs::remove_file(name).unwrap();
+
+        assert_eq!(result, Ok(ExitCode::SUCCESS));
+        assert_eq!(formatted, "const a = 1;");
+    }
+
+    #[test]
+    pub fn diff_hunks() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let after = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nL\n";
+
+        assert_eq!(
+            unified_diff("shader.wgsl", before, after),
+            "--- shader.wgsl
++++ shader.wgsl
+@@ -1,5 +1,5 @@
+ a
+-b
++B
+ c
+ d
+ e
+@@ -9,4 +9,4 @@
+ i
+ j
+ k
+-l
++L
+"
+        );
+    }
+
+    #[test]
+    pub fn identical_sources_have_no_hunks() {
+        assert_eq!(
+            unified_diff("shader.wgsl", "fn main() {}\n", "fn main() {}\n"),
+            "--- shader.wgsl\n+++ shader.wgsl\n"
+        );
+    }
+}
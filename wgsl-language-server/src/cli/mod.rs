@@ -0,0 +1,255 @@
+// Command line interface, exposing analyzer features outside of the editor
+
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+mod check;
+mod fmt;
+
+use clap::{Parser, Subcommand};
+
+use naga::{
+    Module,
+    valid::{ModuleInfo, ValidationFlags, Validator},
+};
+
+use crate::{
+    backends::{Backend, translate},
+    config::{Config, ProjectConfig},
+    preprocessor::preprocess,
+    range_tools::position_at_byte_offset,
+    reflection::reflect_bind_groups,
+    rust_structs::{RustStructStyle, generate_rust_structs},
+};
+
+#[derive(Parser)]
+#[command(name = "wgsl-analyzer", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate Rust definitions of the host-shareable structs in a WGSL file
+    RustStructs {
+        file: PathBuf,
+        /// Only generate this struct, and the structs it depends on
+        #[arg(long = "struct")]
+        struct_name: Option<String>,
+        /// Either `bytemuck` or `encase`
+        #[arg(long, default_value = "bytemuck")]
+        style: RustStructStyle,
+    },
+    /// Print the bind group layouts used by the entry points of a WGSL file as JSON
+    Reflect { file: PathBuf },
+    /// Report the diagnostics of WGSL files, or of every WGSL file in directories.
+    /// Exits with a failure status when any errors are found.
+    Check {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value = "human")]
+        format: check::OutputFormat,
+    },
+    /// Format WGSL files in place, or standard input when no paths are given
+    Fmt {
+        paths: Vec<PathBuf>,
+        /// Print a diff instead of writing, and exit with a failure status when unformatted
+        #[arg(long)]
+        check: bool,
+        #[command(flatten)]
+        options: fmt::FormatOptions,
+    },
+    /// Translate a WGSL file with one of the backends enabled in this build
+    Translate {
+        file: PathBuf,
+        /// One of `msl`, `hlsl`, `glsl` or `spirv`
+        #[arg(long)]
+        backend: Backend,
+        /// Only translate this entry point, and the code it uses
+        #[arg(long)]
+        entry_point: Option<String>,
+    },
+}
+
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Check { paths, format } => {
+            let result = find_project_config()
+                .and_then(|project_config| check::run(&paths, format, project_config));
+            return result.unwrap_or_else(|err| {
+                eprintln!("error: {err}");
+                ExitCode::FAILURE
+            });
+        }
+        Command::Fmt {
+            paths,
+            check,
+            options,
+        } => {
+            let result = current_config()
+                .and_then(|config| fmt::run(&paths, check, &options, &config.format));
+            return result.unwrap_or_else(|err| {
+                eprintln!("error: {err}");
+                ExitCode::FAILURE
+            });
+        }
+        Command::RustStructs {
+            file,
+            struct_name,
+            style,
+        } => current_config()
+            .and_then(|config| rust_structs(&file, struct_name.as_deref(), style, &config)),
+        Command::Reflect { file } => current_config().and_then(|config| reflect(&file, &config)),
+        Command::Translate {
+            file,
+            backend,
+            entry_point,
+        } => current_config().and_then(|config| {
+            let (module, info) = validate_file(&file, &config)?;
+            translate(
+                &module,
+                &info,
+                backend,
+                entry_point.as_deref(),
+                config.validator_capabilities()?,
+            )
+        }),
+    };
+
+    match result {
+        Ok(output) => {
+            print!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+const PROJECT_CONFIG_FILE: &str = "wgsl-analyzer.toml";
+
+/// Finds `wgsl-analyzer.toml` in the current directory, or the closest directory above it.
+fn find_project_config() -> Result<Option<ProjectConfig>, String> {
+    let current_dir = std::env::current_dir()
+        .map_err(|err| format!("Failed to read the current directory: {err}"))?;
+
+    let Some(path) = current_dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+
+    let text = std::fs::read_to_string(&path)
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    let project_config = ProjectConfig {
+        uri: check::path_to_uri(&path)?,
+        text,
+    };
+
+    // Report errors here, since the document tracker keeps the default configuration instead
+    load_config(Some(&project_config))?;
+
+    Ok(Some(project_config))
+}
+
+// The configuration of the project containing the current directory, as used by `check`
+fn current_config() -> Result<Config, String> {
+    find_project_config().and_then(|project_config| load_config(project_config.as_ref()))
+}
+
+fn load_config(project_config: Option<&ProjectConfig>) -> Result<Config, String> {
+    Config::load(&Default::default(), project_config).map_err(|err| {
+        let path = project_config.map_or(PROJECT_CONFIG_FILE.into(), |it| it.uri.to_string());
+        format!("Invalid configuration in {path}: {}", err.message)
+    })
+}
+
+// Files are preprocessed with the shader defs of the configuration, like in the editor
+fn parse_file(file: &Path, config: &Config) -> Result<(String, Module), String> {
+    let source = std::fs::read_to_string(file)
+        .map_err(|err| format!("Failed to read {}: {err}", file.display()))?;
+
+    let (source, errors) = preprocess(&source, &config.shader_defs);
+    if let Some(error) = errors.first() {
+        let position = position_at_byte_offset(&source, error.range.start);
+        return Err(format!(
+            "{}:{}:{}: {}",
+            file.display(),
+            position.line + 1,
+            position.character + 1,
+            error.message
+        ));
+    }
+
+    let module = naga::front::wgsl::parse_str(&source)
+        .map_err(|err| err.emit_to_string_with_path(&source, file))?;
+
+    Ok((source, module))
+}
+
+fn rust_structs(
+    file: &Path,
+    struct_name: Option<&str>,
+    style: RustStructStyle,
+    config: &Config,
+) -> Result<String, String> {
+    let (_, module) = parse_file(file, config)?;
+    generate_rust_structs(&module, struct_name, style)
+}
+
+fn validate_file(file: &Path, config: &Config) -> Result<(Module, ModuleInfo), String> {
+    let (source, module) = parse_file(file, config)?;
+
+    let info = Validator::new(ValidationFlags::all(), config.validator_capabilities()?)
+        .validate(&module)
+        .map_err(|err| err.emit_to_string_with_path(&source, &file.display().to_string()))?;
+
+    Ok((module, info))
+}
+
+fn reflect(file: &Path, config: &Config) -> Result<String, String> {
+    let (module, info) = validate_file(file, config)?;
+    let groups = reflect_bind_groups(&module, &info);
+    Ok(serde_json::to_string_pretty(&groups).unwrap() + "\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn files_are_validated_with_the_project_config() {
+        let file = std::env::temp_dir().join("wgsl-analyzer-cli-config.wgsl");
+        std::fs::write(
+            &file,
+            "#ifdef DOUBLE\nconst scale = 2.0lf;\n#else\nconst scale = 1.0;\n#endif\n",
+        )
+        .unwrap();
+
+        let project = |text: &str| ProjectConfig {
+            uri: "file:///project/wgsl-analyzer.toml".parse().unwrap(),
+            text: text.to_owned(),
+        };
+        let default = load_config(None).unwrap();
+        let double = load_config(Some(&project("[shaderDefs]\nDOUBLE = true"))).unwrap();
+        let no_float64 = load_config(Some(&project(
+            "capabilities = []\n[shaderDefs]\nDOUBLE = true",
+        )))
+        .unwrap();
+
+        assert!(validate_file(&file, &default).is_ok());
+        assert!(validate_file(&file, &double).is_ok());
+        assert!(validate_file(&file, &no_float64).is_err());
+
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+use lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, Command, Range};
+use naga::TypeInner;
+
+use serde::Serialize;
+
+use crate::{
+    backends::Backend,
+    commands::{
+        DocumentArgs, GENERATE_RUST_STRUCTS, GenerateRustStructsArgs, REFLECT_BIND_GROUPS,
+        TRANSLATE, TranslateArgs,
+    },
+    document_tracker::TrackedDocument,
+    functions::{FunctionItem, function_items},
+    range_tools::string_offset,
+    rust_structs::{RustStructStyle, is_host_shareable},
+};
+
+const STYLES: [(RustStructStyle, &str); 2] = [
+    (RustStructStyle::Bytemuck, "bytemuck"),
+    (RustStructStyle::Encase, "encase"),
+];
+
+pub trait CodeActionProvider {
+    fn get_code_actions(&self, range: &Range) -> Vec<CodeActionOrCommand>;
+}
+
+// A code action which runs a server command, producing a new document
+fn command_action(
+    title: String,
+    kind: CodeActionKind,
+    command: &str,
+    args: impl Serialize,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.clone(),
+        kind: Some(kind),
+        command: Some(Command {
+            title,
+            command: command.to_owned(),
+            arguments: Some(vec![serde_json::to_value(args).unwrap()]),
+        }),
+        ..Default::default()
+    })
+}
+
+impl TrackedDocument {
+    fn generate_rust_structs_action(
+        &self,
+        title: String,
+        kind: CodeActionKind,
+        struct_name: Option<String>,
+        style: RustStructStyle,
+    ) -> CodeActionOrCommand {
+        let args = GenerateRustStructsArgs {
+            uri: self.uri.clone(),
+            struct_name,
+            style,
+        };
+
+        command_action(title, kind, GENERATE_RUST_STRUCTS, args)
+    }
+
+    fn translate_action(
+        &self,
+        title: String,
+        backend: Backend,
+        entry_point: Option<String>,
+    ) -> CodeActionOrCommand {
+        let args = TranslateArgs {
+            uri: self.uri.clone(),
+            backend,
+            entry_point,
+        };
+
+        command_action(title, CodeActionKind::SOURCE, TRANSLATE, args)
+    }
+}
+
+impl CodeActionProvider for TrackedDocument {
+    fn get_code_actions(&self, range: &Range) -> Vec<CodeActionOrCommand> {
+        let Some(Ok((module, validation))) = &self.compilation_result else {
+            return vec![];
+        };
+
+        let offset = string_offset(&self.content, &range.start);
+        let mut actions = vec![];
+
+        let host_shareable_structs: Vec<_> = module
+            .types
+            .iter()
+            .filter(|(handle, ty)| {
+                matches!(ty.inner, TypeInner::Struct { .. }) && is_host_shareable(module, *handle)
+            })
+            .collect();
+
+        let selected = host_shareable_structs.iter().find(|(handle, _)| {
+            module
+                .types
+                .get_span(*handle)
+                .to_range()
+                .is_some_and(|span| span.contains(&offset))
+        });
+
+        if let Some((_, ty)) = selected {
+            let name = ty.name.clone().unwrap_or_default();
+            for (style, style_name) in STYLES {
+                actions.push(self.generate_rust_structs_action(
+                    format!("Generate Rust struct for `{name}` ({style_name})"),
+                    CodeActionKind::REFACTOR,
+                    Some(name.clone()),
+                    style,
+                ));
+            }
+        }
+
+        if !host_shareable_structs.is_empty() {
+            for (style, style_name) in STYLES {
+                actions.push(self.generate_rust_structs_action(
+                    format!("Generate Rust structs for all host-shareable structs ({style_name})"),
+                    CodeActionKind::SOURCE,
+                    None,
+                    style,
+                ));
+            }
+        }
+
+        let has_bindings = module
+            .global_variables
+            .iter()
+            .any(|(_, global)| global.binding.is_some());
+
+        if validation.is_ok() && has_bindings {
+            actions.push(command_action(
+                "Export bind group layouts as JSON".to_owned(),
+                CodeActionKind::SOURCE,
+                REFLECT_BIND_GROUPS,
+                DocumentArgs {
+                    uri: self.uri.clone(),
+                },
+            ));
+        }
+
+        if validation.is_ok() {
+            let entry_point = function_items(module).find_map(|item| match item {
+                FunctionItem::EntryPoint(_, entry_point) => item
+                    .range(module, &self.content)
+                    .filter(|range| range.contains(&offset))
+                    .map(|_| entry_point.name.clone()),
+                FunctionItem::Function(..) => None,
+            });
+
+            for backend in Backend::enabled() {
+                if let Some(name) = &entry_point {
+                    actions.push(self.translate_action(
+                        format!("Translate `{name}` to {}", backend.name()),
+                        backend,
+                        Some(name.clone()),
+                    ));
+                }
+
+                actions.push(self.translate_action(
+                    format!("Translate to {}", backend.name()),
+                    backend,
+                    None,
+                ));
+            }
+        }
+
+        actions
+    }
+}
@@ -0,0 +1,148 @@
+use lsp_types::{CodeLens, Command};
+use naga::{EntryPoint, Module, ShaderStage, valid::ModuleInfo};
+
+use crate::{
+    document_tracker::TrackedDocument,
+    functions::{FunctionItem, function_items},
+    interface::{
+        InterfaceVariable, entry_point_inputs, entry_point_outputs, entry_point_resources,
+        shader_stage_str,
+    },
+    range_tools::{range_to_span, span_to_lsp_range},
+};
+
+pub trait CodeLensProvider {
+    fn get_code_lenses(&self) -> Vec<CodeLens>;
+}
+
+// Summaries of an entry point's interface, each shown as a separate lens
+fn entry_point_summary(
+    module: &Module,
+    info: Option<&ModuleInfo>,
+    index: usize,
+    entry_point: &EntryPoint,
+) -> Vec<String> {
+    let labels = |variables: Vec<InterfaceVariable>| {
+        variables
+            .iter()
+            .map(|variable| variable.label(module))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut summary = vec![];
+
+    if entry_point.stage == ShaderStage::Compute {
+        let [x, y, z] = entry_point.workgroup_size;
+        summary.push(format!("compute @workgroup_size({x}, {y}, {z})"));
+    } else {
+        summary.push(shader_stage_str(entry_point.stage).to_owned());
+    }
+
+    let inputs = entry_point_inputs(module, entry_point);
+    if !inputs.is_empty() {
+        summary.push(format!("in: {}", labels(inputs)));
+    }
+
+    let outputs = entry_point_outputs(module, entry_point);
+    if !outputs.is_empty() {
+        summary.push(format!("out: {}", labels(outputs)));
+    }
+
+    // Resource usage is only known once the module has been validated
+    if let Some(info) = info {
+        let resources: Vec<_> = entry_point_resources(module, info.get_entry_point(index))
+            .into_iter()
+            .map(|handle| {
+                let global = &module.global_variables[handle];
+                let binding = global.binding.as_ref().unwrap();
+                format!(
+                    "@group({}) @binding({}) {}",
+                    binding.group,
+                    binding.binding,
+                    global.name.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+
+        if !resources.is_empty() {
+            summary.push(format!("uses: {}", resources.join(", ")));
+        }
+    }
+
+    summary
+}
+
+impl CodeLensProvider for TrackedDocument {
+    fn get_code_lenses(&self) -> Vec<CodeLens> {
+        let Some(Ok((module, validation))) = &self.compilation_result else {
+            return vec![];
+        };
+
+        let info = validation.as_ref().ok();
+
+        function_items(module)
+            .filter_map(|item| match item {
+                FunctionItem::EntryPoint(index, entry_point) => {
+                    Some((item.range(module, &self.content)?, index, entry_point))
+                }
+                FunctionItem::Function(..) => None,
+            })
+            .flat_map(|(range, index, entry_point)| {
+                let range =
+                    span_to_lsp_range(range_to_span(range.start..range.start), &self.content);
+
+                entry_point_summary(module, info, index, entry_point)
+                    .into_iter()
+                    .map(move |title| CodeLens {
+                        range,
+                        // Lenses without a command are displayed, but can not be clicked
+                        command: Some(Command {
+                            title,
+                            command: String::new(),
+                            arguments: None,
+                        }),
+                        data: None,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+    use super::*;
+
+    #[test]
+    pub fn entry_point_summaries() {
+        let module = naga::front::wgsl::parse_str(
+            "
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+@group(0) @binding(1) var<uniform> scale: f32;
+
+fn load(i: u32) -> f32 { return data[i] * scale; }
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    data[id.x] = load(id.x);
+}
+",
+        )
+        .unwrap();
+
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .unwrap();
+
+        assert_eq!(
+            entry_point_summary(&module, Some(&info), 0, &module.entry_points[0]),
+            vec![
+                "compute @workgroup_size(64, 1, 1)",
+                "in: @builtin(global_invocation_id) id: vec3<u32>",
+                "uses: @group(0) @binding(0) data, @group(0) @binding(1) scale",
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+// Custom commands executed through `workspace/executeCommand`
+
+use lsp_types::{ExecuteCommandParams, Uri};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    backends::{Backend, translate},
+    document_tracker::DocumentTracker,
+    reflection::reflect_bind_groups,
+    rust_structs::{RustStructStyle, generate_rust_structs},
+};
+
+pub const GENERATE_RUST_STRUCTS: &str = "wgsl-analyzer.generateRustStructs";
+pub const REFLECT_BIND_GROUPS: &str = "wgsl-analyzer.reflectBindGroups";
+pub const TRANSLATE: &str = "wgsl-analyzer.translate";
+
+/// Output of a command, which the client displays as a new document
+#[derive(Debug, Serialize)]
+pub struct CommandOutput {
+    pub language: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerateRustStructsArgs {
+    pub uri: Uri,
+    // Generate every host-shareable struct when not specified
+    #[serde(rename = "struct")]
+    pub struct_name: Option<String>,
+    pub style: RustStructStyle,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentArgs {
+    pub uri: Uri,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslateArgs {
+    pub uri: Uri,
+    pub backend: Backend,
+    // Translate the whole module when not specified
+    pub entry_point: Option<String>,
+}
+
+fn parse_args<T: DeserializeOwned>(params: ExecuteCommandParams) -> Result<T, String> {
+    let args = params
+        .arguments
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Missing arguments for command `{}`", params.command))?;
+
+    serde_json::from_value(args).map_err(|err| format!("Invalid arguments: {err}"))
+}
+
+impl DocumentTracker {
+    pub fn execute_command(&self, params: ExecuteCommandParams) -> Result<CommandOutput, String> {
+        match params.command.as_str() {
+            GENERATE_RUST_STRUCTS => {
+                let args: GenerateRustStructsArgs = parse_args(params)?;
+                let module = self
+                    .get(&args.uri)
+                    .and_then(|doc| doc.last_valid_module.as_ref())
+                    .ok_or("Document has not been successfully parsed")?;
+
+                Ok(CommandOutput {
+                    language: "rust".to_owned(),
+                    content: generate_rust_structs(
+                        module,
+                        args.struct_name.as_deref(),
+                        args.style,
+                    )?,
+                })
+            }
+            REFLECT_BIND_GROUPS => {
+                let args: DocumentArgs = parse_args(params)?;
+                let Some(Ok((module, Ok(info)))) = self
+                    .get(&args.uri)
+                    .and_then(|doc| doc.compilation_result.as_ref())
+                else {
+                    return Err("Document must be free of errors to reflect bind groups".to_owned());
+                };
+
+                Ok(CommandOutput {
+                    language: "json".to_owned(),
+                    content: serde_json::to_string_pretty(&reflect_bind_groups(module, info))
+                        .unwrap(),
+                })
+            }
+            TRANSLATE => {
+                let args: TranslateArgs = parse_args(params)?;
+                let Some(Ok((module, Ok(info)))) = self
+                    .get(&args.uri)
+                    .and_then(|doc| doc.compilation_result.as_ref())
+                else {
+                    return Err("Document must be free of errors to be translated".to_owned());
+                };
+
+                Ok(CommandOutput {
+                    language: args.backend.language().to_owned(),
+                    content: translate(
+                        module,
+                        info,
+                        args.backend,
+                        args.entry_point.as_deref(),
+                        self.capabilities(),
+                    )?,
+                })
+            }
+            other => Err(format!("Unknown command `{other}`")),
+        }
+    }
+}
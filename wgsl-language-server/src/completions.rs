@@ -1,10 +1,13 @@
+mod attributes;
 mod builtins;
 mod completion_provider;
+mod context;
 mod document_completions;
 mod keywords;
 mod property_access;
 mod swizzle;
 
+pub use attributes::AttributeCompletions;
 pub use builtins::BuiltinCompletions;
 pub use completion_provider::CompletionProvider;
 pub use keywords::KeywordCompletions;
@@ -0,0 +1,155 @@
+use lsp_types::{Command, CompletionItem, CompletionItemKind, InsertTextFormat};
+use naga::ShaderStage::{self, Compute, Fragment, Vertex};
+
+use super::{
+    CompletionProvider,
+    completion_provider::detailed_completion_item,
+    context::Direction::{self, Input, Output},
+};
+
+pub enum AttributeCompletions {
+    Names,
+    // Builtin values usable by entry points of `stage` in `direction`, or anywhere when unknown
+    Builtins {
+        stage: Option<ShaderStage>,
+        direction: Option<Direction>,
+    },
+    InterpolationTypes,
+    // Sampling modes, which depend on whether the interpolation type is `flat`
+    InterpolationSamplings {
+        flat: bool,
+    },
+}
+
+impl CompletionProvider for AttributeCompletions {
+    fn get_completions(&self, _position: &lsp_types::Position) -> Vec<CompletionItem> {
+        match self {
+            AttributeCompletions::Names => ATTRIBUTES
+                .iter()
+                .map(|(name, snippet)| attribute_item(name, snippet))
+                .collect(),
+            AttributeCompletions::Builtins { stage, direction } => BUILTINS
+                .iter()
+                .filter(|(_, uses)| {
+                    uses.iter().any(|(use_stage, use_direction)| {
+                        stage.is_none_or(|it| it == *use_stage)
+                            && direction.is_none_or(|it| it == *use_direction)
+                    })
+                })
+                .map(|(name, uses)| {
+                    let detail = uses
+                        .iter()
+                        .map(|(stage, direction)| format!("{stage:?} {direction:?}").to_lowercase())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    detailed_completion_item(*name, CompletionItemKind::ENUM_MEMBER, detail)
+                })
+                .collect(),
+            AttributeCompletions::InterpolationTypes => ["perspective", "linear", "flat"]
+                .into_iter()
+                .map(|name| detailed_completion_item(name, CompletionItemKind::ENUM_MEMBER, "type"))
+                .collect(),
+            AttributeCompletions::InterpolationSamplings { flat } => match flat {
+                true => &["first", "either"][..],
+                false => &["center", "centroid", "sample"][..],
+            }
+            .iter()
+            .map(|name| {
+                detailed_completion_item(*name, CompletionItemKind::ENUM_MEMBER, "sampling")
+            })
+            .collect(),
+        }
+    }
+}
+
+fn attribute_item(name: &str, snippet: &str) -> CompletionItem {
+    CompletionItem {
+        label: name.to_owned(),
+        kind: Some(CompletionItemKind::PROPERTY),
+        insert_text: Some(snippet.to_owned()),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        // The arguments of these are completed too
+        command: matches!(name, "builtin" | "interpolate").then(|| Command {
+            title: "Suggest".to_owned(),
+            command: "editor.action.triggerSuggest".to_owned(),
+            arguments: None,
+        }),
+        ..Default::default()
+    }
+}
+
+// https://www.w3.org/TR/WGSL/#attributes
+const ATTRIBUTES: &[(&str, &str)] = &[
+    ("align", "align(${1:16})"),
+    ("binding", "binding(${1:0})"),
+    ("blend_src", "blend_src(${1:0})"),
+    ("builtin", "builtin($1)"),
+    ("compute", "compute"),
+    ("const", "const"),
+    (
+        "diagnostic",
+        "diagnostic(${1:off}, ${2:derivative_uniformity})",
+    ),
+    ("fragment", "fragment"),
+    ("group", "group(${1:0})"),
+    ("id", "id(${1:0})"),
+    ("interpolate", "interpolate($1)"),
+    ("invariant", "invariant"),
+    ("location", "location(${1:0})"),
+    ("must_use", "must_use"),
+    ("size", "size(${1:16})"),
+    ("vertex", "vertex"),
+    ("workgroup_size", "workgroup_size(${1:64})"),
+];
+
+// https://www.w3.org/TR/WGSL/#builtin-values
+const BUILTINS: &[(&str, &[(ShaderStage, Direction)])] = &[
+    ("clip_distances", &[(Vertex, Output)]),
+    ("frag_depth", &[(Fragment, Output)]),
+    ("front_facing", &[(Fragment, Input)]),
+    ("global_invocation_id", &[(Compute, Input)]),
+    ("instance_index", &[(Vertex, Input)]),
+    ("local_invocation_id", &[(Compute, Input)]),
+    ("local_invocation_index", &[(Compute, Input)]),
+    ("num_workgroups", &[(Compute, Input)]),
+    ("position", &[(Vertex, Output), (Fragment, Input)]),
+    ("sample_index", &[(Fragment, Input)]),
+    ("sample_mask", &[(Fragment, Input), (Fragment, Output)]),
+    (
+        "subgroup_invocation_id",
+        &[(Compute, Input), (Fragment, Input)],
+    ),
+    ("subgroup_size", &[(Compute, Input), (Fragment, Input)]),
+    ("vertex_index", &[(Vertex, Input)]),
+    ("workgroup_id", &[(Compute, Input)]),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn builtins(stage: Option<ShaderStage>, direction: Option<Direction>) -> Vec<String> {
+        AttributeCompletions::Builtins { stage, direction }
+            .get_completions(&Default::default())
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+    }
+
+    #[test]
+    pub fn builtins_by_stage_and_direction() {
+        assert_eq!(
+            builtins(Some(Vertex), Some(Input)),
+            ["instance_index", "vertex_index"]
+        );
+        assert_eq!(
+            builtins(Some(Fragment), Some(Output)),
+            ["frag_depth", "sample_mask"]
+        );
+        assert_eq!(
+            builtins(None, Some(Output)),
+            ["clip_distances", "frag_depth", "position", "sample_mask"]
+        );
+        assert_eq!(builtins(None, None).len(), BUILTINS.len());
+    }
+}
@@ -1,27 +1,143 @@
 use lsp_types::CompletionItemKind;
 
-use super::{CompletionProvider, completion_provider::new_completion_item};
-pub struct BuiltinCompletions;
+use super::{
+    CompletionProvider,
+    completion_provider::{detailed_completion_item, new_completion_item},
+};
+pub enum BuiltinCompletions {
+    Functions,
+    Types,
+    AddressSpaces,
+    AccessModes,
+}
 
 impl CompletionProvider for BuiltinCompletions {
     fn get_completions(&self, _position: &lsp_types::Position) -> Vec<lsp_types::CompletionItem> {
-        [].iter()
-            .chain(VALUE_CONSTRUCTORS)
-            .chain(OTHER)
-            .chain(NUMERIC)
-            .chain(DERIVATIVE)
-            .chain(TEXTURE)
-            .chain(ATOMIC)
-            .chain(DATA_PACKING)
-            .chain(DATA_UNPACKING)
-            .chain(SYNCHRONIZATION)
-            .chain(SUBGROUP)
-            .chain(QUAD)
-            .map(|name| new_completion_item(*name, CompletionItemKind::FUNCTION))
-            .collect()
+        match self {
+            BuiltinCompletions::Functions => []
+                .iter()
+                .chain(VALUE_CONSTRUCTORS)
+                .chain(OTHER)
+                .chain(NUMERIC)
+                .chain(DERIVATIVE)
+                .chain(TEXTURE)
+                .chain(ATOMIC)
+                .chain(DATA_PACKING)
+                .chain(DATA_UNPACKING)
+                .chain(SYNCHRONIZATION)
+                .chain(SUBGROUP)
+                .chain(QUAD)
+                .map(|name| new_completion_item(*name, CompletionItemKind::FUNCTION))
+                .collect(),
+            BuiltinCompletions::Types => TYPES
+                .iter()
+                .map(|name| new_completion_item(*name, CompletionItemKind::CLASS))
+                .collect(),
+            BuiltinCompletions::AddressSpaces => ADDRESS_SPACES
+                .iter()
+                .map(|name| {
+                    detailed_completion_item(
+                        *name,
+                        CompletionItemKind::ENUM_MEMBER,
+                        "address space",
+                    )
+                })
+                .collect(),
+            BuiltinCompletions::AccessModes => ACCESS_MODES
+                .iter()
+                .map(|name| {
+                    detailed_completion_item(*name, CompletionItemKind::ENUM_MEMBER, "access mode")
+                })
+                .collect(),
+        }
     }
 }
 
+// Predeclared enumerants of address spaces and access modes
+const ADDRESS_SPACES: &[&str] = &[
+    "function",
+    "private",
+    "workgroup",
+    "uniform",
+    "storage",
+    "push_constant",
+];
+
+const ACCESS_MODES: &[&str] = &["read", "write", "read_write"];
+
+// Predeclared types and type generators, including the aliases of vectors and matrices
+const TYPES: &[&str] = &[
+    "bool",
+    "f16",
+    "f32",
+    "i32",
+    "u32",
+    "vec2",
+    "vec3",
+    "vec4",
+    "vec2f",
+    "vec3f",
+    "vec4f",
+    "vec2h",
+    "vec3h",
+    "vec4h",
+    "vec2i",
+    "vec3i",
+    "vec4i",
+    "vec2u",
+    "vec3u",
+    "vec4u",
+    "mat2x2",
+    "mat2x3",
+    "mat2x4",
+    "mat3x2",
+    "mat3x3",
+    "mat3x4",
+    "mat4x2",
+    "mat4x3",
+    "mat4x4",
+    "mat2x2f",
+    "mat2x3f",
+    "mat2x4f",
+    "mat3x2f",
+    "mat3x3f",
+    "mat3x4f",
+    "mat4x2f",
+    "mat4x3f",
+    "mat4x4f",
+    "mat2x2h",
+    "mat2x3h",
+    "mat2x4h",
+    "mat3x2h",
+    "mat3x3h",
+    "mat3x4h",
+    "mat4x2h",
+    "mat4x3h",
+    "mat4x4h",
+    "array",
+    "atomic",
+    "ptr",
+    "sampler",
+    "sampler_comparison",
+    "texture_1d",
+    "texture_2d",
+    "texture_2d_array",
+    "texture_3d",
+    "texture_cube",
+    "texture_cube_array",
+    "texture_multisampled_2d",
+    "texture_depth_2d",
+    "texture_depth_2d_array",
+    "texture_depth_cube",
+    "texture_depth_cube_array",
+    "texture_depth_multisampled_2d",
+    "texture_external",
+    "texture_storage_1d",
+    "texture_storage_2d",
+    "texture_storage_2d_array",
+    "texture_storage_3d",
+];
+
 const VALUE_CONSTRUCTORS: &[&str] = &[
     "array", "bool", "f16", "f32", "i32", "u32", "mat2x2", "mat2x3", "mat2x4", "mat3x2", "mat3x3",
     "mat3x4", "mat4x2", "mat4x3", "mat4x4", "vec2", "vec3", "vec4",
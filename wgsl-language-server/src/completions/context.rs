@@ -0,0 +1,479 @@
+use naga::ShaderStage;
+
+use crate::lexer::{Keyword, LexError, Token, lex_with_errors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+// What can be written at the cursor, decided by the tokens before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionContext {
+    // Comments, literals, the names of new declarations and the operators after an operand
+    None,
+    // After a `.`
+    Member,
+    // After a `@`
+    Attribute,
+    Type,
+    // The first argument of `var` and `ptr`
+    AddressSpace,
+    // The access mode of `var`, `ptr` and storage textures
+    AccessMode,
+    // The start of a module scope declaration
+    Item,
+    // The start of a statement in a function body
+    Statement,
+    Expression,
+    // The value of `@builtin`, with the stage and direction of the entry point it is used by
+    Builtin {
+        stage: Option<ShaderStage>,
+        direction: Option<Direction>,
+    },
+    // The first argument of `@interpolate`
+    InterpolationType,
+    // The second argument of `@interpolate`
+    InterpolationSampling {
+        flat: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope<'src> {
+    Block,
+    Struct,
+    Parameters,
+    // The arguments of an attribute, with its name
+    Attribute(Option<&'src str>),
+    For,
+    Parens,
+    Brackets,
+    // A template list, with the name it follows and the index of the argument being written.
+    // The template list of `var` holds an address space and access mode.
+    Template {
+        name: Option<&'src str>,
+        argument: usize,
+    },
+}
+
+fn is_word(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Ident(_)
+            | Token::Keyword(_)
+            | Token::Boolean(_)
+            | Token::Error(LexError::InvalidIdentifier(_))
+    )
+}
+
+// Types whose template list may not be closed yet, which template discovery can not find
+fn is_template_type(name: &str) -> bool {
+    matches!(name, "array" | "atomic" | "ptr" | "vec2" | "vec3" | "vec4")
+        || (name.starts_with("mat") && name.len() == 6)
+        || name.starts_with("texture_")
+}
+
+// What the argument at `index` of the template list following `name` holds
+fn template_argument(name: Option<&str>, index: usize) -> CompletionContext {
+    match (name, index) {
+        (Some("var" | "ptr"), 0) => CompletionContext::AddressSpace,
+        (Some("var"), 1) | (Some("ptr"), 2) => CompletionContext::AccessMode,
+        (Some("ptr"), 1) => CompletionContext::Type,
+        // The element count
+        (Some("array"), 1) => CompletionContext::Expression,
+        // Texel formats are not completed
+        (Some(name), 1) if name.starts_with("texture_storage_") => CompletionContext::AccessMode,
+        (Some(name), _) if name.starts_with("texture_storage_") => CompletionContext::None,
+        (Some(_), 0) | (None, _) => CompletionContext::Type,
+        _ => CompletionContext::None,
+    }
+}
+
+fn pop_to<'src>(
+    scopes: &mut Vec<Scope<'src>>,
+    is_opener: impl Fn(Scope) -> bool,
+) -> Option<Scope<'src>> {
+    while let Some(scope) = scopes.pop() {
+        if is_opener(scope) {
+            return Some(scope);
+        }
+    }
+    None
+}
+
+pub fn completion_context(source: &str, offset: usize) -> CompletionContext {
+    let Some(before) = source.get(..offset) else {
+        return CompletionContext::None;
+    };
+
+    let mut tokens = lex_with_errors(before);
+
+    // The word being typed does not change what can be written
+    match tokens.last() {
+        Some((token, span)) if span.end == offset && is_word(token) => {
+            tokens.pop();
+        }
+        Some((Token::Trivia(comment), span)) if span.end == offset && comment.starts_with("//") => {
+            return CompletionContext::None;
+        }
+        Some((Token::Integer(_) | Token::Float(_), span)) if span.end == offset => {
+            return CompletionContext::None;
+        }
+        Some((Token::Error(LexError::UnterminatedComment), _)) => return CompletionContext::None,
+        _ => {}
+    }
+
+    let tokens: Vec<Token> = tokens
+        .into_iter()
+        .map(|(token, _)| token)
+        .filter(|token| !matches!(token, Token::Trivia(_)))
+        .collect();
+
+    let mut scopes = vec![];
+    // Whether the last token ends an attribute, like the `)` of `@location(0)`
+    let mut after_attribute = false;
+    // The stage of the last entry point attribute, and of the function it applies to
+    let mut stage = None;
+    let mut function_stage = None;
+    // Whether the cursor is in the return type of a function
+    let mut return_type = false;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let prev = index.checked_sub(1).map(|index| &tokens[index]);
+        let prev2 = index.checked_sub(2).map(|index| &tokens[index]);
+        after_attribute = matches!(prev, Some(Token::Syntax("@"))) && is_word(token);
+
+        if after_attribute {
+            stage = match token {
+                Token::Ident("vertex") => Some(ShaderStage::Vertex),
+                Token::Ident("fragment") => Some(ShaderStage::Fragment),
+                Token::Ident("compute") => Some(ShaderStage::Compute),
+                _ => stage,
+            };
+        }
+
+        match token {
+            Token::Keyword(Keyword::Fn) => {
+                function_stage = stage.take();
+                return_type = false;
+            }
+            Token::Syntax("->") if scopes.is_empty() => return_type = true,
+            Token::Syntax("{" | ";") if scopes.is_empty() => return_type = false,
+            _ => {}
+        }
+
+        match token {
+            Token::Syntax("{") => match (prev2, prev) {
+                (Some(Token::Keyword(Keyword::Struct)), Some(Token::Ident(_))) => {
+                    scopes.push(Scope::Struct)
+                }
+                _ => scopes.push(Scope::Block),
+            },
+            Token::Syntax("(") => match (prev2, prev) {
+                (Some(Token::Keyword(Keyword::Fn)), Some(Token::Ident(_))) => {
+                    scopes.push(Scope::Parameters)
+                }
+                (Some(Token::Syntax("@")), Some(Token::Ident(name))) => {
+                    scopes.push(Scope::Attribute(Some(name)))
+                }
+                (Some(Token::Syntax("@")), Some(prev)) if is_word(prev) => {
+                    scopes.push(Scope::Attribute(None))
+                }
+                (_, Some(Token::Keyword(Keyword::For))) => scopes.push(Scope::For),
+                _ => scopes.push(Scope::Parens),
+            },
+            Token::Syntax("[") => scopes.push(Scope::Brackets),
+            Token::TemplateArgsStart | Token::Syntax("<") => match prev {
+                Some(Token::Keyword(Keyword::Var)) => scopes.push(Scope::Template {
+                    name: Some("var"),
+                    argument: 0,
+                }),
+                Some(Token::Ident(name)) if is_template_type(name) => {
+                    scopes.push(Scope::Template {
+                        name: Some(name),
+                        argument: 0,
+                    })
+                }
+                _ if *token == Token::TemplateArgsStart => scopes.push(Scope::Template {
+                    name: None,
+                    argument: 0,
+                }),
+                _ => {}
+            },
+            Token::Syntax(",") => {
+                if let Some(Scope::Template { argument, .. }) = scopes.last_mut() {
+                    *argument += 1;
+                }
+            }
+            Token::Syntax("}") => {
+                pop_to(&mut scopes, |scope| {
+                    matches!(scope, Scope::Block | Scope::Struct)
+                });
+            }
+            Token::Syntax(")") => {
+                let scope = pop_to(&mut scopes, |scope| {
+                    matches!(
+                        scope,
+                        Scope::Parameters | Scope::Attribute(_) | Scope::For | Scope::Parens
+                    )
+                });
+                after_attribute = matches!(scope, Some(Scope::Attribute(_)));
+            }
+            Token::Syntax("]") => {
+                pop_to(&mut scopes, |scope| scope == Scope::Brackets);
+            }
+            Token::TemplateArgsEnd | Token::Syntax(">")
+                if matches!(scopes.last(), Some(Scope::Template { .. })) =>
+            {
+                scopes.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let scope = scopes.last().copied();
+
+    // Attributes come before the declarations and statements they apply to
+    let start = match scope {
+        None => CompletionContext::Item,
+        Some(Scope::Block) => CompletionContext::Statement,
+        Some(Scope::For) => CompletionContext::Expression,
+        Some(Scope::Struct | Scope::Parameters) => CompletionContext::None,
+        Some(_) => CompletionContext::Expression,
+    };
+    if after_attribute {
+        return start;
+    }
+
+    let Some(prev) = tokens.last() else {
+        return CompletionContext::Item;
+    };
+
+    match (scope, prev) {
+        (Some(Scope::Attribute(Some("builtin"))), Token::Syntax("(")) => {
+            let direction = match scopes.iter().rev().nth(1) {
+                Some(Scope::Parameters) => Some(Direction::Input),
+                None if return_type => Some(Direction::Output),
+                _ => None,
+            };
+            let stage = match direction {
+                Some(_) => function_stage,
+                None => None,
+            };
+            return CompletionContext::Builtin { stage, direction };
+        }
+        (Some(Scope::Attribute(Some("interpolate"))), Token::Syntax("(")) => {
+            return CompletionContext::InterpolationType;
+        }
+        (Some(Scope::Attribute(Some("interpolate"))), Token::Syntax(",")) => {
+            let flat = tokens.iter().rev().nth(1) == Some(&Token::Ident("flat"));
+            return CompletionContext::InterpolationSampling { flat };
+        }
+        (
+            Some(Scope::Template { name, argument }),
+            Token::TemplateArgsStart | Token::Syntax("<" | ","),
+        ) => return template_argument(name, argument),
+        (Some(Scope::Attribute(Some("builtin" | "interpolate"))), _) => {
+            return CompletionContext::None;
+        }
+        _ => {}
+    }
+
+    match prev {
+        Token::Syntax(".") => CompletionContext::Member,
+        Token::Syntax("@") => CompletionContext::Attribute,
+        Token::Syntax(":" | "->") => CompletionContext::Type,
+        Token::Syntax("{" | ",") if scope == Some(Scope::Struct) => CompletionContext::None,
+        Token::Syntax("(" | ",") if scope == Some(Scope::Parameters) => CompletionContext::None,
+        Token::Syntax("(") if scope == Some(Scope::For) => CompletionContext::Statement,
+        Token::Syntax("{" | "}" | ";") => start,
+        // Names of new declarations
+        Token::Keyword(
+            Keyword::Alias
+            | Keyword::Const
+            | Keyword::Fn
+            | Keyword::Let
+            | Keyword::Override
+            | Keyword::Struct
+            | Keyword::Var,
+        ) => CompletionContext::None,
+        Token::Keyword(Keyword::Else) => CompletionContext::Statement,
+        // Followed by a block, `:` or `;`, or the name of an extension
+        Token::Keyword(
+            Keyword::Continue
+            | Keyword::Continuing
+            | Keyword::Default
+            | Keyword::Diagnostic
+            | Keyword::Discard
+            | Keyword::Enable
+            | Keyword::Loop
+            | Keyword::Requires,
+        ) => CompletionContext::None,
+        // Only an operator can follow an operand
+        Token::Ident(_)
+        | Token::Boolean(_)
+        | Token::Integer(_)
+        | Token::Float(_)
+        | Token::TemplateArgsEnd
+        | Token::Syntax(")" | "]" | ">") => CompletionContext::None,
+        _ => CompletionContext::Expression,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The context at the `$` in `source`
+    fn context(source: &str) -> CompletionContext {
+        let offset = source.find('$').unwrap();
+        completion_context(&source.replace('$', ""), offset)
+    }
+
+    #[test]
+    pub fn types() {
+        assert_eq!(context("struct A { a: $"), CompletionContext::Type);
+        assert_eq!(context("struct A { a: f3$"), CompletionContext::Type);
+        assert_eq!(context("fn a(b: f32) -> $"), CompletionContext::Type);
+        assert_eq!(context("var<private> a: array<$"), CompletionContext::Type);
+        assert_eq!(
+            context("fn a() { let b = vec3<$>(); }"),
+            CompletionContext::Type
+        );
+        assert_eq!(context("fn a(b: ptr<function, $"), CompletionContext::Type);
+    }
+
+    #[test]
+    pub fn template_arguments() {
+        assert_eq!(
+            context("var<private> a: array<f32, $"),
+            CompletionContext::Expression
+        );
+        assert_eq!(
+            context("var<private> a: array<vec2<f32>, N$"),
+            CompletionContext::Expression
+        );
+        assert_eq!(context("var<$"), CompletionContext::AddressSpace);
+        assert_eq!(context("fn a(b: ptr<$"), CompletionContext::AddressSpace);
+        assert_eq!(context("var<storage, $"), CompletionContext::AccessMode);
+        assert_eq!(
+            context("fn a(b: ptr<storage, array<u32>, $"),
+            CompletionContext::AccessMode
+        );
+        assert_eq!(
+            context("var t: texture_storage_2d<rgba8unorm, $"),
+            CompletionContext::AccessMode
+        );
+        assert_eq!(
+            context("var t: texture_storage_2d<$"),
+            CompletionContext::None
+        );
+    }
+
+    #[test]
+    pub fn attributes() {
+        assert_eq!(context("@$"), CompletionContext::Attribute);
+        assert_eq!(context("@ver$\nfn main() {}"), CompletionContext::Attribute);
+        assert_eq!(
+            context("struct A { @location(0) a: f32, @$ }"),
+            CompletionContext::Attribute
+        );
+        assert_eq!(context("@vertex $"), CompletionContext::Item);
+        assert_eq!(context("@group(0) @binding(1) $"), CompletionContext::Item);
+    }
+
+    #[test]
+    pub fn statements_and_items() {
+        assert_eq!(context("$"), CompletionContext::Item);
+        assert_eq!(context("const a = 1;\n$"), CompletionContext::Item);
+        assert_eq!(context("fn a() {}\nfn b() {}\n$"), CompletionContext::Item);
+        assert_eq!(context("fn a() {\n    $\n}"), CompletionContext::Statement);
+        assert_eq!(
+            context("fn a() {\n    let b = 1;\n    re$"),
+            CompletionContext::Statement
+        );
+        assert_eq!(
+            context("fn a() { if true {} $"),
+            CompletionContext::Statement
+        );
+        assert_eq!(context("fn a() { for ($"), CompletionContext::Statement);
+    }
+
+    #[test]
+    pub fn expressions() {
+        assert_eq!(context("fn a() { let b = $"), CompletionContext::Expression);
+        assert_eq!(
+            context("fn a() { b += c * $"),
+            CompletionContext::Expression
+        );
+        assert_eq!(context("fn a() { return $"), CompletionContext::Expression);
+        assert_eq!(context("fn a() { b(c, $"), CompletionContext::Expression);
+        assert_eq!(
+            context("fn a() { for (var i = 0; $"),
+            CompletionContext::Expression
+        );
+        assert_eq!(context("@workgroup_size($"), CompletionContext::Expression);
+    }
+
+    #[test]
+    pub fn nothing() {
+        assert_eq!(context("// a comment $"), CompletionContext::None);
+        assert_eq!(context("/* a comment $"), CompletionContext::None);
+        assert_eq!(context("fn $"), CompletionContext::None);
+        assert_eq!(context("fn ma$"), CompletionContext::None);
+        assert_eq!(context("fn main($"), CompletionContext::None);
+        assert_eq!(context("struct A { $"), CompletionContext::None);
+        assert_eq!(context("fn a() { let b = c $"), CompletionContext::None);
+        assert_eq!(context("fn a() { let b = 1$"), CompletionContext::None);
+    }
+
+    #[test]
+    pub fn members() {
+        assert_eq!(context("fn a() { b.$"), CompletionContext::Member);
+        assert_eq!(context("fn a() { b.c$"), CompletionContext::Member);
+    }
+
+    #[test]
+    pub fn attribute_arguments() {
+        use Direction::{Input, Output};
+        use ShaderStage::{Compute, Fragment, Vertex};
+
+        let builtin = |stage, direction| CompletionContext::Builtin { stage, direction };
+
+        assert_eq!(
+            context("@vertex\nfn main(@builtin($"),
+            builtin(Some(Vertex), Some(Input))
+        );
+        assert_eq!(
+            context("@fragment\nfn main() -> @builtin($"),
+            builtin(Some(Fragment), Some(Output))
+        );
+        assert_eq!(
+            context(
+                "@compute @workgroup_size(64)\nfn main(@builtin(local_invocation_id) a: vec3u, @builtin(gl$"
+            ),
+            builtin(Some(Compute), Some(Input))
+        );
+        assert_eq!(context("fn a(@builtin($"), builtin(None, Some(Input)));
+        assert_eq!(context("struct A { @builtin($"), builtin(None, None));
+        assert_eq!(
+            context("fn a(@builtin(position) $"),
+            CompletionContext::None
+        );
+
+        assert_eq!(
+            context("@interpolate($"),
+            CompletionContext::InterpolationType
+        );
+        assert_eq!(
+            context("@interpolate(flat, $"),
+            CompletionContext::InterpolationSampling { flat: true }
+        );
+        assert_eq!(
+            context("@interpolate(linear, cen$"),
+            CompletionContext::InterpolationSampling { flat: false }
+        );
+    }
+}
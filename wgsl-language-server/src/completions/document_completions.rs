@@ -1,53 +1,28 @@
 use lsp_types::{CompletionItem, CompletionItemKind, Position};
-use naga::{Function, Span};
 
 use super::{
-    CompletionProvider,
+    AttributeCompletions, BuiltinCompletions, CompletionProvider, KeywordCompletions,
     completion_provider::{detailed_completion_item, new_completion_item},
+    context::{CompletionContext, completion_context},
 };
 
 use crate::{
     completions::swizzle::SWIZZLES,
     document_tracker::TrackedDocument,
-    parser::matching_bracket_index,
+    functions::{FunctionItem, function_items},
     pretty_error::error_context::{ModuleContext, type_print::TypePrintable},
-    range_tools::{RangeTools, source_location_to_range, span_to_lsp_range, string_offset},
+    range_tools::{RangeTools, range_to_span, span_to_lsp_range, string_offset},
 };
 
 impl TrackedDocument {
-    fn get_containing_function(&self, position: &Position) -> Option<&Function> {
-        let Some(module) = &self.last_valid_module else {
-            return None;
-        };
-
-        for (handle, function) in module.functions.iter() {
-            let mut location = module.functions.get_span(handle).location(&self.content);
-
-            if let Some(close) = matching_bracket_index(&self.content, location.offset as usize) {
-                location.length = close as u32 - location.offset;
-            }
-
-            let range = source_location_to_range(Some(location), &self.content).unwrap();
-
-            if range.contains_line(position) {
-                return Some(function);
-            }
-        }
-
-        for entry_point in &module.entry_points {
-            let entry_point_location =
-                Span::total_span(entry_point.function.body.span_iter().map(|(_, span)| *span))
-                    .location(&self.content);
-
-            let range =
-                source_location_to_range(Some(entry_point_location), &self.content).unwrap();
-
-            if range.contains_line(position) {
-                return Some(&entry_point.function);
-            }
-        }
-
-        None
+    fn get_containing_function(&self, position: &Position) -> Option<FunctionItem<'_>> {
+        let module = self.last_valid_module.as_ref()?;
+
+        function_items(module).find(|item| {
+            item.range(module, &self.content).is_some_and(|range| {
+                span_to_lsp_range(range_to_span(range), &self.content).contains_line(position)
+            })
+        })
     }
 
     fn get_functions(&self, _position: &Position) -> Vec<CompletionItem> {
@@ -93,14 +68,14 @@ impl TrackedDocument {
                 .to_range()
                 .unwrap_or_default();
 
-            if range.start < *position {
-                if let Some(name) = &variable.name {
-                    res.push(detailed_completion_item(
-                        name.to_owned(),
-                        CompletionItemKind::VARIABLE,
-                        &self.content[span],
-                    ))
-                }
+            if range.start < *position
+                && let Some(name) = &variable.name
+            {
+                res.push(detailed_completion_item(
+                    name.to_owned(),
+                    CompletionItemKind::VARIABLE,
+                    &self.content[span],
+                ))
             }
         }
 
@@ -173,7 +148,8 @@ impl TrackedDocument {
             return vec![];
         };
 
-        let Some(expr_type) = ctx.get_proceeding_popery_access_type(position, function) else {
+        let Some(expr_type) = ctx.get_proceeding_popery_access_type(position, function.function())
+        else {
             return vec![];
         };
 
@@ -214,22 +190,53 @@ impl TrackedDocument {
 impl CompletionProvider for &TrackedDocument {
     fn get_completions(&self, position: &Position) -> Vec<CompletionItem> {
         let text_offset = string_offset(&self.content, position);
-        let last_char = &self.content[text_offset - 1..text_offset];
-        let is_access_completion = last_char.starts_with('.');
+        let context = completion_context(&self.content, text_offset);
 
         let mut completions = vec![];
 
-        if is_access_completion {
-            completions.extend(self.get_property_access(position));
-        } else {
-            if let Some(current_function) = self.get_containing_function(position) {
-                completions.extend(self.get_locals(position, current_function));
+        match context {
+            CompletionContext::None => {}
+            CompletionContext::Member => completions.extend(self.get_property_access(position)),
+            CompletionContext::Attribute => {
+                completions.extend(AttributeCompletions::Names.get_completions(position))
+            }
+            CompletionContext::Builtin { stage, direction } => completions.extend(
+                AttributeCompletions::Builtins { stage, direction }.get_completions(position),
+            ),
+            CompletionContext::InterpolationType => completions
+                .extend(AttributeCompletions::InterpolationTypes.get_completions(position)),
+            CompletionContext::InterpolationSampling { flat } => completions.extend(
+                AttributeCompletions::InterpolationSamplings { flat }.get_completions(position),
+            ),
+            CompletionContext::Type => {
+                completions.extend(self.get_types(position));
+                completions.extend(BuiltinCompletions::Types.get_completions(position));
+            }
+            CompletionContext::AddressSpace => {
+                completions.extend(BuiltinCompletions::AddressSpaces.get_completions(position))
+            }
+            CompletionContext::AccessMode => {
+                completions.extend(BuiltinCompletions::AccessModes.get_completions(position))
+            }
+            CompletionContext::Item => {
+                completions.extend(KeywordCompletions::Item.get_completions(position))
+            }
+            CompletionContext::Statement | CompletionContext::Expression => {
+                if let Some(current_function) = self.get_containing_function(position) {
+                    completions.extend(self.get_locals(position, current_function.function()));
+                }
+                completions.extend(self.get_functions(position));
+                completions.extend(self.get_globals(position));
+                completions.extend(BuiltinCompletions::Functions.get_completions(position));
+
+                if context == CompletionContext::Statement {
+                    completions.extend(KeywordCompletions::Statement.get_completions(position));
+                } else {
+                    // Struct constructors
+                    completions.extend(self.get_types(position));
+                    completions.extend(KeywordCompletions::Expression.get_completions(position));
+                }
             }
-            completions.extend(self.get_functions(position));
-            completions.extend(self.get_types(position));
-            completions.extend(self.get_globals(position));
-            completions.extend(crate::completions::KeywordCompletions.get_completions(position));
-            completions.extend(crate::completions::BuiltinCompletions.get_completions(position));
         }
 
         completions
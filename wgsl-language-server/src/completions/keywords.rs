@@ -2,40 +2,58 @@ use lsp_types::CompletionItemKind;
 
 use super::{CompletionProvider, completion_provider::new_completion_item};
 
-pub struct KeywordCompletions;
+pub enum KeywordCompletions {
+    // Keywords starting module scope declarations
+    Item,
+    // Keywords starting statements
+    Statement,
+    Expression,
+}
 
 impl CompletionProvider for KeywordCompletions {
     fn get_completions(&self, _position: &lsp_types::Position) -> Vec<lsp_types::CompletionItem> {
-        [
-            "alias",
-            "break",
-            "case",
-            "const",
-            "const_assert",
-            "continue",
-            "continuing",
-            "default",
-            "diagnostic",
-            "discard",
-            "else",
-            "enable",
-            "false",
-            "fn",
-            "for",
-            "if",
-            "let",
-            "loop",
-            "override",
-            "requires",
-            "return",
-            "struct",
-            "switch",
-            "true",
-            "var",
-            "while",
-        ]
-        .into_iter()
-        .map(|it| new_completion_item(it, CompletionItemKind::KEYWORD))
+        match self {
+            KeywordCompletions::Item => ITEM,
+            KeywordCompletions::Statement => STATEMENT,
+            KeywordCompletions::Expression => EXPRESSION,
+        }
+        .iter()
+        .map(|it| new_completion_item(*it, CompletionItemKind::KEYWORD))
         .collect()
     }
 }
+
+const ITEM: &[&str] = &[
+    "alias",
+    "const",
+    "const_assert",
+    "diagnostic",
+    "enable",
+    "fn",
+    "override",
+    "requires",
+    "struct",
+    "var",
+];
+
+const STATEMENT: &[&str] = &[
+    "break",
+    "case",
+    "const",
+    "const_assert",
+    "continue",
+    "continuing",
+    "default",
+    "discard",
+    "else",
+    "for",
+    "if",
+    "let",
+    "loop",
+    "return",
+    "switch",
+    "var",
+    "while",
+];
+
+const EXPRESSION: &[&str] = &["false", "true"];
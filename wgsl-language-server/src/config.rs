@@ -0,0 +1,494 @@
+// Settings sent by the client through `workspace/didChangeConfiguration`, and the project
+// configuration read from `wgsl-analyzer.toml` at the root of the workspace. Both use the same
+// keys, and the project configuration takes precedence.
+
+use std::collections::HashMap;
+
+use lsp_types::{FormattingOptions, NumberOrString, Uri};
+use naga::valid::Capabilities;
+use serde::Deserialize;
+
+use crate::{backends::Backend, fmt::FormatStyle};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct Config {
+    pub interface_checking: InterfaceCheckingConfig,
+    // Backends every entry point is checked to translate with, when enabled in this build
+    pub target_backends: Vec<Backend>,
+    // Names of the validator capabilities, like `float64`. All are enabled when not specified
+    pub capabilities: Option<Vec<String>>,
+    pub shader_defs: HashMap<String, ShaderDef>,
+    // Directories `#import` paths are resolved against, relative to the project root
+    pub import_roots: Vec<String>,
+    pub lints: HashMap<String, LintLevel>,
+    pub format: FormatConfig,
+    pub features: FeaturesConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct InterfaceCheckingConfig {
+    pub pairing: InterfacePairing,
+    // Explicit pairs take precedence over `pairing` for the fragment entry points they name
+    pub pairs: Vec<EntryPointPair>,
+}
+
+/// Which vertex entry points each fragment entry point is checked against
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterfacePairing {
+    // Vertex entry points declared in the same file
+    #[default]
+    Document,
+    // Vertex entry points declared in any open file
+    Workspace,
+    // Only check explicit pairs
+    Off,
+}
+
+/// Entry points are referenced by name, optionally prefixed by the path of their file,
+/// for example `shaders/mesh.wgsl:vs_main`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EntryPointPair {
+    pub vertex: String,
+    pub fragment: String,
+}
+
+/// The value of a shader def used by `#if` directives
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ShaderDef {
+    Bool(bool),
+    Int(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// Diagnostics which are not parse or validation errors, and can be configured through `lints`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    InterfaceMismatch,
+    Portability,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 2] = [Lint::InterfaceMismatch, Lint::Portability];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::InterfaceMismatch => "interface-mismatch",
+            Lint::Portability => "portability",
+        }
+    }
+
+    // Diagnostics of a lint use its name as their code
+    pub fn code(&self) -> Option<NumberOrString> {
+        Some(NumberOrString::String(self.name().to_owned()))
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+
+    fn default_level(&self) -> LintLevel {
+        match self {
+            Lint::InterfaceMismatch => LintLevel::Deny,
+            Lint::Portability => LintLevel::Warn,
+        }
+    }
+}
+
+/// Overrides for the formatting options sent by the editor, and the style of the formatter.
+/// A `.wgslfmt.toml` file uses the same keys, and takes precedence for the files below it.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormatConfig {
+    pub tab_size: Option<u32>,
+    pub insert_spaces: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+    pub trim_final_newlines: Option<bool>,
+    #[serde(flatten)]
+    pub style: FormatStyle,
+    // Keys which are neither options nor style, as `deny_unknown_fields` does not work with
+    // `flatten`
+    #[serde(flatten)]
+    unknown: HashMap<String, serde_json::Value>,
+}
+
+impl FormatConfig {
+    /// Parses the contents of a `.wgslfmt.toml` file.
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let config: FormatConfig = toml::from_str(text).map_err(toml_error)?;
+
+        config.check().map_err(|message| ConfigError {
+            message,
+            range: None,
+            from_settings: false,
+        })?;
+
+        Ok(config)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match self.unknown.keys().min() {
+            Some(key) => Err(format!("unknown format option `{key}`")),
+            None => Ok(()),
+        }
+    }
+
+    pub fn apply(&self, options: &mut FormattingOptions) {
+        if let Some(tab_size) = self.tab_size {
+            options.tab_size = tab_size;
+        }
+        if let Some(insert_spaces) = self.insert_spaces {
+            options.insert_spaces = insert_spaces;
+        }
+        if let Some(insert_final_newline) = self.insert_final_newline {
+            options.insert_final_newline = Some(insert_final_newline);
+        }
+        if let Some(trim_final_newlines) = self.trim_final_newlines {
+            options.trim_final_newlines = Some(trim_final_newlines);
+        }
+    }
+}
+
+/// Language server features which can be turned off
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct FeaturesConfig {
+    pub completion: bool,
+    pub hover: bool,
+    pub code_lens: bool,
+    pub code_actions: bool,
+    pub formatting: bool,
+    pub document_links: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            completion: true,
+            hover: true,
+            code_lens: true,
+            code_actions: true,
+            formatting: true,
+            document_links: true,
+        }
+    }
+}
+
+/// The contents of `wgsl-analyzer.toml`, as sent by the client
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectConfig {
+    pub uri: Uri,
+    pub text: String,
+}
+
+impl ProjectConfig {
+    /// The directory containing the configuration file, with a trailing `/`
+    pub fn root(&self) -> &str {
+        let uri = self.uri.as_str();
+        &uri[..uri.rfind('/').map_or(uri.len(), |index| index + 1)]
+    }
+}
+
+/// A `.wgslfmt.toml` file as sent by the client, which applies to the files in its directory
+#[derive(Debug)]
+pub struct FormatConfigFile {
+    pub uri: Uri,
+    pub text: String,
+    pub config: Result<FormatConfig, ConfigError>,
+}
+
+impl FormatConfigFile {
+    pub fn new(uri: Uri, text: String) -> Self {
+        let config = FormatConfig::parse(&text);
+        Self { uri, text, config }
+    }
+
+    /// The directory containing the file, with a trailing `/`
+    pub fn directory(&self) -> &str {
+        let uri = self.uri.as_str();
+        &uri[..uri.rfind('/').map_or(uri.len(), |index| index + 1)]
+    }
+}
+
+/// An error in the configuration, with the byte range of the project configuration it is in
+#[derive(Debug)]
+pub struct ConfigError {
+    pub message: String,
+    pub range: Option<std::ops::Range<usize>>,
+    // Whether the error is in the client's settings rather than in a configuration file
+    pub from_settings: bool,
+}
+
+impl Config {
+    /// Combines the `wgsl-analyzer` section of the client's settings with the project
+    /// configuration, which takes precedence.
+    pub fn load(
+        settings: &serde_json::Value,
+        project: Option<&ProjectConfig>,
+    ) -> Result<Self, ConfigError> {
+        let mut value = settings_section(settings);
+
+        let Some(project) = project else {
+            return Config::from_value(value).map_err(|message| ConfigError {
+                message: format!("Invalid `wgsl-analyzer` settings: {message}"),
+                range: None,
+                from_settings: true,
+            });
+        };
+
+        // Errors in the settings are reported as such, rather than in the project configuration
+        Config::load(settings, None)?;
+
+        // The project configuration is checked on its own first, so errors have the range of
+        // the key or value causing them
+        let config: Config = toml::from_str(&project.text).map_err(toml_error)?;
+        let project_error = |message| ConfigError {
+            message,
+            range: None,
+            from_settings: false,
+        };
+        config.check().map_err(project_error)?;
+
+        merge(&mut value, parse_toml(&project.text)?);
+        Config::from_value(value).map_err(project_error)
+    }
+
+    fn from_value(value: serde_json::Value) -> Result<Self, String> {
+        let config: Config = serde_json::from_value(value).map_err(|err| err.to_string())?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        self.validator_capabilities().map(|_| ())?;
+
+        if let Some(name) = self.lints.keys().find(|it| Lint::from_name(it).is_none()) {
+            return Err(format!("Unknown lint `{name}`"));
+        }
+
+        self.format.check()
+    }
+
+    pub fn validator_capabilities(&self) -> Result<Capabilities, String> {
+        let Some(names) = &self.capabilities else {
+            return Ok(Capabilities::all());
+        };
+
+        names
+            .iter()
+            .try_fold(Capabilities::empty(), |capabilities, name| {
+                Capabilities::from_name(&name.to_uppercase())
+                    .map(|capability| capabilities | capability)
+                    .ok_or_else(|| format!("Unknown capability `{name}`"))
+            })
+    }
+
+    /// The capabilities to validate with. A configuration is only loaded when they are valid.
+    pub fn capabilities(&self) -> Capabilities {
+        self.validator_capabilities().unwrap_or(Capabilities::all())
+    }
+
+    pub fn lint_level(&self, lint: Lint) -> LintLevel {
+        self.lints
+            .get(lint.name())
+            .copied()
+            .unwrap_or(lint.default_level())
+    }
+}
+
+fn toml_error(err: toml::de::Error) -> ConfigError {
+    ConfigError {
+        message: err.message().to_owned(),
+        range: err.span(),
+        from_settings: false,
+    }
+}
+
+fn parse_toml(text: &str) -> Result<serde_json::Value, ConfigError> {
+    let table: toml::Table = toml::from_str(text).map_err(toml_error)?;
+
+    Ok(serde_json::to_value(table).unwrap())
+}
+
+fn settings_section(settings: &serde_json::Value) -> serde_json::Value {
+    settings
+        .get("wgsl-analyzer")
+        .cloned()
+        .unwrap_or(serde_json::Value::Object(Default::default()))
+}
+
+// Recursively merges the tables of `overrides` into `value`
+fn merge(value: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (value, overrides) {
+        (serde_json::Value::Object(value), serde_json::Value::Object(overrides)) => {
+            for (key, override_value) in overrides {
+                match value.get_mut(&key) {
+                    Some(existing) => merge(existing, override_value),
+                    None => {
+                        value.insert(key, override_value);
+                    }
+                }
+            }
+        }
+        (value, overrides) => *value = overrides,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn project(text: &str) -> ProjectConfig {
+        ProjectConfig {
+            uri: "file:///project/wgsl-analyzer.toml".parse().unwrap(),
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    pub fn missing_settings_use_defaults() {
+        let config = Config::load(&serde_json::json!({}), None).unwrap();
+        assert_eq!(
+            config.interface_checking.pairing,
+            InterfacePairing::Document
+        );
+        assert!(config.interface_checking.pairs.is_empty());
+        assert!(config.target_backends.is_empty());
+        assert!(config.features.completion);
+        assert_eq!(config.validator_capabilities(), Ok(Capabilities::all()));
+    }
+
+    #[test]
+    pub fn interface_checking_settings() {
+        let settings = serde_json::json!({
+            "wgsl-analyzer": {
+                "interfaceChecking": {
+                    "pairing": "workspace",
+                    "pairs": [{ "vertex": "mesh.wgsl:vs_main", "fragment": "fs_main" }]
+                }
+            }
+        });
+        let config = Config::load(&settings, None).unwrap();
+
+        assert_eq!(
+            config.interface_checking.pairing,
+            InterfacePairing::Workspace
+        );
+        assert_eq!(
+            config.interface_checking.pairs[0].vertex,
+            "mesh.wgsl:vs_main"
+        );
+    }
+
+    #[test]
+    pub fn project_config_takes_precedence() {
+        let settings = serde_json::json!({
+            "wgsl-analyzer": {
+                "interfaceChecking": { "pairing": "workspace" },
+                "format": { "tabSize": 2, "maxWidth": 80 }
+            }
+        });
+        let project = project(
+            r#"
+capabilities = ["float64", "PUSH_CONSTANT"]
+importRoots = ["shaders"]
+
+[interfaceChecking]
+pairing = "off"
+
+[shaderDefs]
+SHADOWS = true
+MAX_LIGHTS = 4
+
+[lints]
+portability = "deny"
+
+[features]
+codeLens = false
+"#,
+        );
+
+        let config = Config::load(&settings, Some(&project)).unwrap();
+
+        assert_eq!(config.interface_checking.pairing, InterfacePairing::Off);
+        assert_eq!(config.format.tab_size, Some(2));
+        assert_eq!(config.format.style.max_width, 80);
+        assert_eq!(
+            config.validator_capabilities(),
+            Ok(Capabilities::FLOAT64 | Capabilities::PUSH_CONSTANT)
+        );
+        assert_eq!(config.shader_defs["MAX_LIGHTS"], ShaderDef::Int(4));
+        assert_eq!(config.lint_level(Lint::Portability), LintLevel::Deny);
+        assert_eq!(config.lint_level(Lint::InterfaceMismatch), LintLevel::Deny);
+        assert!(!config.features.code_lens);
+        assert_eq!(project.root(), "file:///project/");
+    }
+
+    #[test]
+    pub fn format_config() {
+        let config = FormatConfig::parse(
+            "tabSize = 2\nmaxWidth = 80\nbraceStyle = \"nextLine\"\ntrailingCommas = \"vertical\"",
+        )
+        .unwrap();
+
+        assert_eq!(config.tab_size, Some(2));
+        assert_eq!(config.style.max_width, 80);
+        assert_eq!(config.style.brace_style, crate::fmt::BraceStyle::NextLine);
+        assert_eq!(config.style.blank_lines_between_items, 1);
+
+        let err = FormatConfig::parse("braceStyle = \"allman\"").unwrap_err();
+        assert!(err.message.starts_with("unknown variant `allman`"));
+    }
+
+    #[test]
+    pub fn invalid_project_config() {
+        let settings = serde_json::json!({});
+
+        let err = Config::load(&settings, Some(&project("lints = ["))).unwrap_err();
+        assert!(err.range.is_some());
+
+        let err = Config::load(&settings, Some(&project("capabilities = [\"warp\"]"))).unwrap_err();
+        assert_eq!(err.message, "Unknown capability `warp`");
+
+        let err =
+            Config::load(&settings, Some(&project("lints = { unused = \"allow\" }"))).unwrap_err();
+        assert_eq!(err.message, "Unknown lint `unused`");
+    }
+
+    #[test]
+    pub fn unknown_keys() {
+        let text = "importRoots = [\"shaders\"]\ntargetBackend = [\"glsl\"]\n";
+        let err = Config::load(&serde_json::json!({}), Some(&project(text))).unwrap_err();
+        assert!(err.message.starts_with("unknown field `targetBackend`"));
+        assert_eq!(&text[err.range.unwrap()], "targetBackend");
+        assert!(!err.from_settings);
+
+        let text = "[format]\ntabsize = 2\n";
+        let err = Config::load(&serde_json::json!({}), Some(&project(text))).unwrap_err();
+        assert_eq!(err.message, "unknown format option `tabsize`");
+
+        let err = FormatConfig::parse("maxWidth = 80\nbraceStlye = \"nextLine\"").unwrap_err();
+        assert_eq!(err.message, "unknown format option `braceStlye`");
+
+        let settings = serde_json::json!({
+            "wgsl-analyzer": { "interfaceChecking": { "paring": "off" } }
+        });
+        let err = Config::load(&settings, Some(&project(""))).unwrap_err();
+        assert!(
+            err.message
+                .starts_with("Invalid `wgsl-analyzer` settings: unknown field `paring`")
+        );
+        assert!(err.from_settings);
+    }
+}
@@ -0,0 +1,163 @@
+// Links for the paths of `#import` directives. Quoted paths are relative to the importing file,
+// and module paths like `lighting::shadows` are resolved against the import roots of the project.
+
+use lsp_types::{DocumentLink, Uri};
+
+use crate::{document_tracker::DocumentTracker, range_tools::new_location};
+
+// The directory of a uri, with a trailing `/`
+fn parent(uri: &str) -> &str {
+    &uri[..uri.rfind('/').map_or(uri.len(), |index| index + 1)]
+}
+
+fn join(directory: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        directory.trim_end_matches('/'),
+        path.trim_start_matches("./").trim_start_matches('/')
+    )
+}
+
+// The offset of the imported path within an `#import` line, the path, and whether it is quoted
+fn import_path(line: &str) -> Option<(usize, &str, bool)> {
+    let start = line.find("#import")? + "#import".len();
+    let rest = &line[start..];
+    let offset = start + rest.len() - rest.trim_start().len();
+    let rest = rest.trim_start();
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        return Some((offset + 1, &quoted[..end], true));
+    }
+
+    let end = rest
+        .find(|char: char| !(char.is_alphanumeric() || char == '_' || char == ':'))
+        .unwrap_or(rest.len());
+
+    Some((offset, rest[..end].trim_end_matches(':'), false))
+}
+
+impl DocumentTracker {
+    pub fn get_document_links(&self, uri: &Uri) -> Vec<DocumentLink> {
+        let Some(document) = self.get(uri) else {
+            return vec![];
+        };
+
+        if !self.config().features.document_links {
+            return vec![];
+        }
+
+        let mut links = vec![];
+        let mut offset = 0;
+
+        for line in document.content.split_inclusive('\n') {
+            let line_offset = offset;
+            offset += line.len();
+
+            if !line.trim_start().starts_with("#import") {
+                continue;
+            }
+
+            let Some((start, path, quoted)) = import_path(line) else {
+                continue;
+            };
+
+            if path.is_empty() {
+                continue;
+            }
+
+            let target = match quoted {
+                true => Some(join(parent(uri.as_str()), path)),
+                false => self.resolve_module(path),
+            };
+
+            let Some(target) = target.and_then(|it| it.parse().ok()) else {
+                continue;
+            };
+
+            let range = line_offset + start..line_offset + start + path.len();
+            links.push(DocumentLink {
+                range: new_location(range, &document.content, uri.clone()).range,
+                target: Some(target),
+                tooltip: None,
+                data: None,
+            });
+        }
+
+        links
+    }
+
+    // Imports may name an item of a module, like `lighting::shadows::sample_shadow`, so every
+    // prefix of the path is tried against the open documents, before falling back to the first
+    // import root
+    fn resolve_module(&self, path: &str) -> Option<String> {
+        let project_config = self.project_config()?;
+        let import_roots = &self.config().import_roots;
+        let segments: Vec<_> = path.split("::").collect();
+
+        let candidates = (1..=segments.len()).rev().flat_map(|len| {
+            let file = format!("{}.wgsl", segments[..len].join("/"));
+            import_roots
+                .iter()
+                .map(move |root| join(&join(project_config.root(), root), &file))
+        });
+
+        candidates
+            .clone()
+            .find(|candidate| self.iter().any(|doc| doc.uri.as_str() == candidate))
+            .or_else(|| candidates.into_iter().next())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::TextDocumentItem;
+
+    use super::*;
+    use crate::config::ProjectConfig;
+
+    fn insert(tracker: &mut DocumentTracker, uri: &str, text: &str) {
+        tracker.insert(TextDocumentItem {
+            uri: uri.parse().unwrap(),
+            language_id: "wgsl".to_owned(),
+            version: 0,
+            text: text.to_owned(),
+        });
+    }
+
+    #[test]
+    pub fn import_links() {
+        let mut tracker = DocumentTracker::new();
+        tracker.set_project_config(Some(ProjectConfig {
+            uri: "file:///project/wgsl-analyzer.toml".parse().unwrap(),
+            text: "importRoots = [\"assets\", \"shaders\"]".to_owned(),
+        }));
+
+        insert(
+            &mut tracker,
+            "file:///project/shaders/lighting/shadows.wgsl",
+            "fn sample_shadow() {}",
+        );
+        insert(
+            &mut tracker,
+            "file:///project/shaders/main.wgsl",
+            "#import lighting::shadows::sample_shadow\n#import \"./common.wgsl\"\n#import pbr\n",
+        );
+
+        let uri = "file:///project/shaders/main.wgsl".parse().unwrap();
+        let targets: Vec<_> = tracker
+            .get_document_links(&uri)
+            .into_iter()
+            .map(|link| link.target.unwrap().to_string())
+            .collect();
+
+        assert_eq!(
+            targets,
+            vec![
+                "file:///project/shaders/lighting/shadows.wgsl",
+                "file:///project/shaders/common.wgsl",
+                "file:///project/assets/pbr.wgsl",
+            ]
+        );
+    }
+}
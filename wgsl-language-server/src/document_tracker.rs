@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 
 use lsp_types::{
-    CompletionItem, DidChangeTextDocumentParams, DocumentFormattingParams, DocumentSymbol,
-    Position, PublishDiagnosticsParams, Range, TextDocumentItem, TextEdit, Uri,
+    CodeActionOrCommand, CodeLens, CompletionItem, Diagnostic, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DocumentFormattingParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, DocumentSymbol, Hover, MessageType, NumberOrString, Position,
+    PublishDiagnosticsParams, Range, ShowMessageParams, TextDocumentItem, TextEdit, Uri,
+    WorkspaceSymbol,
 };
 use naga::{
     Module,
@@ -11,10 +14,18 @@ use naga::{
 };
 
 use crate::{
+    backends::{PortabilityError, portability_errors},
+    code_actions::CodeActionProvider,
+    code_lens::CodeLensProvider,
     completions::CompletionProvider,
+    config::{Config, ConfigError, FormatConfig, FormatConfigFile, Lint, LintLevel, ProjectConfig},
     fmt,
+    hover::HoverProvider,
+    lexer::{LexDiagnostic, lex_diagnostics},
+    log,
+    preprocessor::{PreprocessError, preprocess},
     pretty_error::error_context::ModuleContext,
-    range_tools::string_range,
+    range_tools::{new_location, position_at_byte_offset, string_offset, string_range},
     symbol_provider::SymbolProvider,
     wgsl_error::{parse_error_to_lsp_diagnostic, validation_error_to_lsp_diagnostic},
 };
@@ -26,15 +37,26 @@ pub struct TrackedDocument {
     pub version: i32,
     pub compilation_result: Option<CompilationResult>,
     pub last_valid_module: Option<Module>,
+    pub preprocess_errors: Vec<PreprocessError>,
+    pub lex_diagnostics: Vec<LexDiagnostic>,
+    pub portability_errors: Vec<PortabilityError>,
 }
 
 type CompilationResult =
     Result<(Module, Result<ModuleInfo, naga::WithSpan<ValidationError>>), ParseError>;
 
 impl TrackedDocument {
-    pub fn compile_module(&mut self, validator: &mut Validator) -> &CompilationResult {
+    pub fn compile_module(
+        &mut self,
+        validator: &mut Validator,
+        config: &Config,
+    ) -> &CompilationResult {
         validator.reset();
-        let result = match naga::front::wgsl::parse_str(&self.content) {
+        let (source, preprocess_errors) = preprocess(&self.content, &config.shader_defs);
+        self.preprocess_errors = preprocess_errors;
+        self.lex_diagnostics = lex_diagnostics(&source);
+
+        let result = match naga::front::wgsl::parse_str(&source) {
             Err(parse_error) => Err(parse_error),
             Ok(module) => {
                 self.last_valid_module = Some(module.clone());
@@ -43,6 +65,17 @@ impl TrackedDocument {
             }
         };
 
+        // Translating every entry point is slow, so it is only done when the document changes
+        self.portability_errors = match &result {
+            Ok((module, Ok(_))) => portability_errors(
+                module,
+                &source,
+                &config.target_backends,
+                config.capabilities(),
+            ),
+            _ => vec![],
+        };
+
         self.compilation_result.insert(result)
     }
 
@@ -51,9 +84,37 @@ impl TrackedDocument {
             return vec![];
         };
 
-        match compilation_result {
+        let to_diagnostic = |range: &std::ops::Range<usize>, message: &String| Diagnostic {
+            range: new_location(range.clone(), &self.content, self.uri.clone()).range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: message.clone(),
+            source: Some("wgsl-language-support".to_owned()),
+            ..Default::default()
+        };
+
+        let preprocess_diagnostics = self
+            .preprocess_errors
+            .iter()
+            .map(|error| to_diagnostic(&error.range, &error.message));
+        let lex_diagnostics: Vec<_> = self
+            .lex_diagnostics
+            .iter()
+            .map(|error| to_diagnostic(&error.range, &error.message))
+            .collect();
+
+        let compilation_diagnostics = match compilation_result {
+            // Parse errors caused by a token which failed to lex are already reported, with a
+            // more precise explanation
             Err(parse_error) => {
                 parse_error_to_lsp_diagnostic(parse_error, &self.content, &self.uri)
+                    .into_iter()
+                    .filter(|diagnostic| {
+                        !lex_diagnostics.iter().any(|it| {
+                            it.range.start <= diagnostic.range.end
+                                && diagnostic.range.start <= it.range.end
+                        })
+                    })
+                    .collect()
             }
             Ok((module, Err(validation_error))) => validation_error_to_lsp_diagnostic(
                 validation_error,
@@ -62,10 +123,15 @@ impl TrackedDocument {
                 module,
             ),
             _ => vec![],
-        }
+        };
+
+        preprocess_diagnostics
+            .chain(lex_diagnostics)
+            .chain(compilation_diagnostics)
+            .collect()
     }
 
-    pub fn module_context(&self) -> Option<ModuleContext> {
+    pub fn module_context(&self) -> Option<ModuleContext<'_>> {
         self.last_valid_module.as_ref().map(|module| ModuleContext {
             module,
             code: &self.content,
@@ -76,6 +142,12 @@ impl TrackedDocument {
 pub struct DocumentTracker {
     validator: Validator,
     documents: HashMap<Uri, TrackedDocument>,
+    config: Config,
+    // The sources of `config`, kept to rebuild it when either changes
+    settings: serde_json::Value,
+    project_config: Option<ProjectConfig>,
+    config_error: Option<ConfigError>,
+    format_configs: Vec<FormatConfigFile>,
 }
 
 impl DocumentTracker {
@@ -83,6 +155,83 @@ impl DocumentTracker {
         Self {
             validator: naga::valid::Validator::new(ValidationFlags::all(), Capabilities::all()),
             documents: Default::default(),
+            config: Default::default(),
+            settings: Default::default(),
+            project_config: None,
+            config_error: None,
+            format_configs: vec![],
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The capabilities documents are validated with.
+    pub fn capabilities(&self) -> Capabilities {
+        self.config.capabilities()
+    }
+
+    pub fn project_config(&self) -> Option<&ProjectConfig> {
+        self.project_config.as_ref()
+    }
+
+    /// Settings sent through `workspace/didChangeConfiguration`. Errors in them have no file to
+    /// be reported in, so they are returned as a message to show instead.
+    pub fn set_settings(&mut self, settings: serde_json::Value) -> Option<ShowMessageParams> {
+        self.settings = settings;
+        self.reload_config();
+
+        let err = self.config_error.as_ref().filter(|err| err.from_settings)?;
+        Some(ShowMessageParams {
+            typ: MessageType::ERROR,
+            message: err.message.clone(),
+        })
+    }
+
+    /// The contents of `wgsl-analyzer.toml`, or `None` when it does not exist
+    pub fn set_project_config(&mut self, project_config: Option<ProjectConfig>) {
+        self.project_config = project_config;
+        self.reload_config();
+    }
+
+    /// The contents of a `.wgslfmt.toml` file, or `None` when it was deleted
+    pub fn set_format_config(&mut self, uri: Uri, text: Option<String>) {
+        self.format_configs.retain(|file| file.uri != uri);
+
+        if let Some(text) = text {
+            self.format_configs.push(FormatConfigFile::new(uri, text));
+        }
+    }
+
+    // The closest valid `.wgslfmt.toml` above the document, or the `format` section of the
+    // configuration when there is none
+    fn format_config(&self, uri: &Uri) -> &FormatConfig {
+        self.format_configs
+            .iter()
+            .filter(|file| uri.as_str().starts_with(file.directory()))
+            .filter_map(|file| Some((file.directory().len(), file.config.as_ref().ok()?)))
+            .max_by_key(|(len, _)| *len)
+            .map_or(&self.config.format, |(_, config)| config)
+    }
+
+    // An invalid configuration is reported, and the previous one is kept until it is fixed
+    fn reload_config(&mut self) {
+        let config = match Config::load(&self.settings, self.project_config.as_ref()) {
+            Ok(config) => config,
+            Err(err) => {
+                log!("Invalid configuration: {}", err.message);
+                self.config_error = Some(err);
+                return;
+            }
+        };
+
+        self.validator = Validator::new(ValidationFlags::all(), config.capabilities());
+        self.config = config;
+        self.config_error = None;
+
+        for document in self.documents.values_mut() {
+            document.compile_module(&mut self.validator, &self.config);
         }
     }
 
@@ -93,9 +242,12 @@ impl DocumentTracker {
             version: doc.version,
             compilation_result: None,
             last_valid_module: None,
+            preprocess_errors: vec![],
+            lex_diagnostics: vec![],
+            portability_errors: vec![],
         };
 
-        document.compile_module(&mut self.validator);
+        document.compile_module(&mut self.validator, &self.config);
 
         self.documents.insert(doc.uri, document);
     }
@@ -110,7 +262,7 @@ impl DocumentTracker {
                     doc.content = change.text;
                 }
             }
-            doc.compile_module(&mut self.validator);
+            doc.compile_module(&mut self.validator, &self.config);
         }
     }
 
@@ -118,11 +270,25 @@ impl DocumentTracker {
         self.documents.remove(uri);
     }
 
+    pub fn get(&self, uri: &Uri) -> Option<&TrackedDocument> {
+        self.documents.get(uri)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TrackedDocument> {
+        self.documents.values()
+    }
+
     pub fn get_diagnostics(&self) -> Vec<PublishDiagnosticsParams> {
         let mut diagnostics = vec![];
 
         for (url, document) in &self.documents {
-            let lsp_diagnostics: Vec<_> = document.get_lsp_diagnostics().into_iter().collect();
+            let mut lsp_diagnostics = document.get_lsp_diagnostics();
+            lsp_diagnostics.extend(
+                self.stage_interface_diagnostics(document, &self.config.interface_checking)
+                    .into_iter()
+                    .chain(document.portability_diagnostics())
+                    .filter_map(|diagnostic| self.apply_lint_level(diagnostic)),
+            );
 
             diagnostics.push(PublishDiagnosticsParams {
                 uri: url.clone(),
@@ -131,12 +297,77 @@ impl DocumentTracker {
             })
         }
 
+        if let Some(project_config) = &self.project_config {
+            let errors = self.config_error.iter().filter(|err| !err.from_settings);
+            let errors = errors.map(|err| Diagnostic {
+                range: new_location(
+                    err.range.clone().unwrap_or_default(),
+                    &project_config.text,
+                    project_config.uri.clone(),
+                )
+                .range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: err.message.clone(),
+                source: Some("wgsl-language-support".to_owned()),
+                ..Default::default()
+            });
+
+            diagnostics.push(PublishDiagnosticsParams {
+                uri: project_config.uri.clone(),
+                diagnostics: errors.collect(),
+                version: None,
+            });
+        }
+
+        for file in &self.format_configs {
+            let errors = file.config.as_ref().err().map(|err| Diagnostic {
+                range: new_location(
+                    err.range.clone().unwrap_or_default(),
+                    &file.text,
+                    file.uri.clone(),
+                )
+                .range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: err.message.clone(),
+                source: Some("wgsl-language-support".to_owned()),
+                ..Default::default()
+            });
+
+            diagnostics.push(PublishDiagnosticsParams {
+                uri: file.uri.clone(),
+                diagnostics: errors.into_iter().collect(),
+                version: None,
+            });
+        }
+
         diagnostics
     }
 
+    // Drops or changes the severity of lint diagnostics, as configured by `lints`
+    fn apply_lint_level(&self, mut diagnostic: Diagnostic) -> Option<Diagnostic> {
+        let lint = match &diagnostic.code {
+            Some(NumberOrString::String(code)) => Lint::from_name(code),
+            _ => None,
+        };
+
+        if let Some(lint) = lint {
+            diagnostic.severity = match self.config.lint_level(lint) {
+                LintLevel::Allow => return None,
+                LintLevel::Warn => Some(DiagnosticSeverity::WARNING),
+                LintLevel::Deny => Some(DiagnosticSeverity::ERROR),
+            };
+        }
+
+        Some(diagnostic)
+    }
+
     pub fn get_completion(&self, url: &Uri, position: &Position) -> Vec<CompletionItem> {
         let mut completions = vec![];
 
+        if !self.config.features.completion {
+            return completions;
+        }
+
         if let Some(doc) = self.documents.get(url) {
             completions.extend(doc.get_completions(position))
         }
@@ -144,20 +375,326 @@ impl DocumentTracker {
         completions
     }
 
-    pub fn get_symbols(&self) -> Vec<DocumentSymbol> {
+    pub fn get_hover(&self, uri: &Uri, position: &Position) -> Option<Hover> {
+        if !self.config.features.hover {
+            return None;
+        }
+
+        self.documents.get(uri)?.get_hover(position)
+    }
+
+    pub fn get_code_actions(&self, uri: &Uri, range: &Range) -> Vec<CodeActionOrCommand> {
+        if !self.config.features.code_actions {
+            return vec![];
+        }
+
+        self.documents
+            .get(uri)
+            .map(|doc| doc.get_code_actions(range))
+            .unwrap_or_default()
+    }
+
+    pub fn get_code_lenses(&self, uri: &Uri) -> Vec<CodeLens> {
+        if !self.config.features.code_lens {
+            return vec![];
+        }
+
+        self.documents
+            .get(uri)
+            .map(|doc| doc.get_code_lenses())
+            .unwrap_or_default()
+    }
+
+    pub fn get_document_symbols(&self, uri: &Uri) -> Vec<DocumentSymbol> {
         self.documents
+            .get(uri)
+            .map(|doc| doc.get_symbols())
+            .unwrap_or_default()
+    }
+
+    pub fn get_workspace_symbols(&self, query: &str) -> Vec<WorkspaceSymbol> {
+        let mut symbols: Vec<_> = self
+            .documents
             .values()
-            .flat_map(|doc| doc.get_symbols())
-            .collect()
+            .flat_map(|doc| doc.get_workspace_symbols(query))
+            .collect();
+
+        symbols.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+
+        symbols.into_iter().map(|(_, symbol)| symbol).collect()
     }
 
-    pub fn format_document(&self, params: DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+    /// The edits formatting a document, and a message for the user naming the lines left
+    /// unformatted, as they could not be lexed.
+    pub fn format_document(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> Option<(Vec<TextEdit>, Option<ShowMessageParams>)> {
+        if !self.config.features.formatting {
+            return None;
+        }
+
+        let document = self.documents.get(&params.text_document.uri)?;
+        let format = self.format_config(&document.uri);
+        let mut options = params.options;
+        format.apply(&mut options);
+
+        let fmt::Formatted {
+            text: result,
+            skipped,
+        } = fmt::format(&document.content, &options, &format.style)?;
+
+        let message = skipped_message(&document.content, &skipped);
+
+        // Only the whitespace which changed is replaced, keeping the cursor and folding state of
+        // the editor. The whole document is replaced when the tokens can not be paired up.
+        let edits = match fmt::whitespace_edits(&document.content, &result) {
+            Some(edits) => to_text_edits(&document.content, edits),
+            None => vec![TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(u32::MAX, u32::MAX)),
+                result,
+            )],
+        };
+
+        Some((edits, message))
+    }
+
+    pub fn format_range(&self, params: DocumentRangeFormattingParams) -> Option<Vec<TextEdit>> {
+        if !self.config.features.formatting {
+            return None;
+        }
+
         let document = self.documents.get(&params.text_document.uri)?;
-        let result = fmt::pretty_print_ast(&document.content, &params.options)?;
+        let format = self.format_config(&document.uri);
+        let mut options = params.options;
+        format.apply(&mut options);
+
+        let range = string_range(&document.content, params.range);
+        let edits = fmt::format_range(&document.content, &options, &format.style, range)?;
+
+        Some(to_text_edits(&document.content, edits))
+    }
+
+    pub fn format_on_type(&self, params: DocumentOnTypeFormattingParams) -> Option<Vec<TextEdit>> {
+        if !self.config.features.formatting {
+            return None;
+        }
+
+        let position = params.text_document_position;
+        let document = self.documents.get(&position.text_document.uri)?;
+        let format = self.format_config(&document.uri);
+        let mut options = params.options;
+        format.apply(&mut options);
+
+        let offset = string_offset(&document.content, &position.position);
+        let ch = params.ch.chars().next()?;
+        let edits = fmt::format_on_type(&document.content, &options, &format.style, offset, ch)?;
+
+        Some(to_text_edits(&document.content, edits))
+    }
+}
+
+fn skipped_message(source: &str, skipped: &[std::ops::Range<usize>]) -> Option<ShowMessageParams> {
+    let mut lines: Vec<_> = skipped
+        .iter()
+        .map(|range| position_at_byte_offset(source, range.start).line + 1)
+        .collect();
+    lines.dedup();
+
+    let lines = match lines.as_slice() {
+        [] => return None,
+        [line] => format!("line {line}"),
+        lines => {
+            let lines: Vec<_> = lines.iter().map(u32::to_string).collect();
+            format!("lines {}", lines.join(", "))
+        }
+    };
+
+    Some(ShowMessageParams {
+        typ: MessageType::INFO,
+        message: format!("Formatting left text on {lines} unchanged, as it could not be lexed"),
+    })
+}
+
+// Edits are sorted, so positions are found by continuing from the end of the previous edit
+// rather than from the start of the source, which would be quadratic for large documents
+fn to_text_edits(source: &str, edits: Vec<fmt::Edit>) -> Vec<TextEdit> {
+    let mut offset = 0;
+    let mut position = Position::new(0, 0);
+
+    let mut advance = |target: usize| {
+        let delta = position_at_byte_offset(&source[offset..target], target - offset);
+        position = match delta.line {
+            0 => Position::new(position.line, position.character + delta.character),
+            _ => Position::new(position.line + delta.line, delta.character),
+        };
+        offset = target;
+        position
+    };
+
+    edits
+        .into_iter()
+        .map(|edit| {
+            let start = advance(edit.range.start);
+            let end = advance(edit.range.end);
+            TextEdit::new(Range::new(start, end), edit.text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{
+        FormattingOptions, TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams,
+    };
+
+    use super::*;
+
+    fn tracker(text: &str) -> (DocumentTracker, Uri) {
+        let uri: Uri = "file:///shader.wgsl".parse().unwrap();
+        let mut tracker = DocumentTracker::new();
+        tracker.insert(TextDocumentItem {
+            uri: uri.clone(),
+            language_id: "wgsl".to_owned(),
+            version: 0,
+            text: text.to_owned(),
+        });
+        (tracker, uri)
+    }
+
+    fn options() -> FormattingOptions {
+        FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        }
+    }
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_owned();
+        for edit in edits.iter().rev() {
+            result.replace_range(string_range(source, edit.range), &edit.new_text);
+        }
+        result
+    }
+
+    fn format_range(source: &str, range: Range) -> String {
+        let (tracker, uri) = tracker(source);
+        let edits = tracker
+            .format_range(DocumentRangeFormattingParams {
+                text_document: TextDocumentIdentifier { uri },
+                range,
+                options: options(),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+        apply(source, &edits)
+    }
+
+    fn format_on_type(source: &str, position: Position, ch: &str) -> Option<String> {
+        let (tracker, uri) = tracker(source);
+        let edits = tracker.format_on_type(DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            ch: ch.to_owned(),
+            options: options(),
+        })?;
+        Some(apply(source, &edits))
+    }
+
+    #[test]
+    pub fn range_formatting_with_utf16_positions() {
+        let source = "fn a() {\n/* é é */ let y=2;\n}\n";
+        let formatted = "fn a() {\n    /* é é */ let y = 2;\n}\n";
+
+        // The end is inside of the second `é` when counted in bytes
+        let range = Range::new(Position::new(1, 0), Position::new(1, 4));
+        assert_eq!(format_range(source, range), formatted);
+
+        let range = Range::new(Position::new(1, 0), Position::new(9, 0));
+        assert_eq!(format_range(source, range), formatted);
+    }
+
+    #[test]
+    pub fn on_type_formatting_with_utf16_positions() {
+        let source = "fn a() {\nlet x=1;\n// é é\n}";
+
+        assert_eq!(
+            format_on_type(source, Position::new(3, 1), "}").unwrap(),
+            "fn a() {\n    let x = 1;\n    // é é\n}"
+        );
+        assert_eq!(format_on_type(source, Position::new(2, 4), "}"), None);
+    }
+
+    #[test]
+    pub fn edit_positions_count_utf16_code_units() {
+        let (tracker, uri) = tracker("fn a() {\n    /* é */ let t=2;\n}\n");
+        let (edits, message) = tracker
+            .format_document(DocumentFormattingParams {
+                text_document: TextDocumentIdentifier { uri },
+                options: options(),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+
+        assert_eq!(message, None);
+
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit::new(
+                    Range::new(Position::new(1, 17), Position::new(1, 17)),
+                    " ".to_owned()
+                ),
+                TextEdit::new(
+                    Range::new(Position::new(1, 18), Position::new(1, 18)),
+                    " ".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn skipped_text_is_reported() {
+        let source = "fn f() {\nlet class=1;\nlet b=a  $$ 2; $\n}\n";
+        let (tracker, uri) = tracker(source);
+        let (_, message) = tracker
+            .format_document(DocumentFormattingParams {
+                text_document: TextDocumentIdentifier { uri },
+                options: options(),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            message.unwrap().message,
+            "Formatting left text on lines 2, 3 unchanged, as it could not be lexed"
+        );
+    }
+
+    #[test]
+    pub fn settings_errors_are_shown() {
+        let mut tracker = DocumentTracker::new();
+        tracker.set_project_config(Some(ProjectConfig {
+            uri: "file:///wgsl-analyzer.toml".parse().unwrap(),
+            text: String::new(),
+        }));
+
+        let message = tracker
+            .set_settings(serde_json::json!({ "wgsl-analyzer": { "lint": {} } }))
+            .unwrap();
+        assert_eq!(message.typ, MessageType::ERROR);
+        assert!(message.message.contains("unknown field `lint`"));
+
+        // They are not reported in the project configuration
+        let diagnostics = tracker.get_diagnostics();
+        assert!(diagnostics.iter().all(|it| it.diagnostics.is_empty()));
 
-        Some(vec![TextEdit::new(
-            Range::new(Position::new(0, 0), Position::new(u32::MAX, u32::MAX)),
-            result,
-        )])
+        let message = tracker.set_settings(serde_json::json!({ "wgsl-analyzer": { "lints": {} } }));
+        assert!(message.is_none());
     }
 }
@@ -0,0 +1,420 @@
+// Builds a `Doc` from the tokens and delimiters chosen by `pretty_print_ast`. Tokens are nested
+// into a tree of blocks, brackets, attribute lists and binary expressions, which become the
+// groups the layout breaks when a line is too long.
+
+use super::{
+    Delimiter,
+    doc::{Doc, Layout},
+    ends_operand,
+    style::{FormatStyle, TrailingCommas},
+};
+use crate::lexer::{Keyword, Token};
+
+#[derive(PartialEq, Eq)]
+enum Kind {
+    Root,
+    Block { struct_body: bool },
+    // Call arguments and parameter lists, which are wrapped. Other parentheses are not.
+    Paren { call: bool },
+    Bracket,
+    Template,
+    Attributes,
+    Binary,
+}
+
+struct Frame {
+    kind: Kind,
+    docs: Vec<Doc>,
+    // Where the current operand starts, that is after the last delimiter
+    operand_start: usize,
+    // Binary expressions keep their operators, and where each operand following one starts
+    operators: Vec<(u8, String)>,
+    operand_starts: Vec<usize>,
+    // Where the last token or nested frame ends, and whether it is a comma, ignoring comments
+    content_end: usize,
+    trailing_comma: bool,
+    // The spaces following the `:` of struct members, and the width of the member before them
+    member_types: Vec<(usize, usize)>,
+}
+
+impl Frame {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            docs: vec![],
+            operand_start: 0,
+            operators: vec![],
+            operand_starts: vec![],
+            content_end: 0,
+            trailing_comma: false,
+            member_types: vec![],
+        }
+    }
+
+    // Struct members are always on their own line, while argument lists only need a trailing
+    // comma when they are broken
+    fn set_trailing_comma(&mut self, style: &FormatStyle, comma: Doc) {
+        if self.content_end == 0 || style.trailing_commas == TrailingCommas::Preserve {
+            return;
+        }
+
+        if self.trailing_comma {
+            self.docs.remove(self.content_end - 1);
+            self.content_end -= 1;
+        }
+
+        if style.trailing_commas == TrailingCommas::Vertical {
+            self.docs.insert(self.content_end, comma);
+        }
+    }
+
+    fn align_member_types(&mut self) {
+        let width = self.member_types.iter().map(|(_, width)| *width).max();
+
+        for (index, member_width) in std::mem::take(&mut self.member_types) {
+            let padding = width.unwrap_or_default() - member_width + 1;
+            self.docs[index] = Doc::text(" ".repeat(padding));
+        }
+    }
+
+    fn into_doc(mut self, style: &FormatStyle) -> Option<Doc> {
+        match self.kind {
+            Kind::Root | Kind::Bracket | Kind::Template => Some(Doc::Concat(self.docs)),
+            Kind::Block { struct_body } => {
+                if struct_body {
+                    self.align_member_types();
+                    self.set_trailing_comma(style, Doc::text(","));
+                }
+                Some(Doc::nest(Doc::Concat(self.docs)))
+            }
+            Kind::Paren { .. } if self.docs.is_empty() => None,
+            Kind::Paren { call: false } => Some(Doc::Concat(self.docs)),
+            Kind::Paren { call: true } => {
+                self.set_trailing_comma(style, Doc::IfBreak(",".to_owned()));
+                self.docs.insert(0, Doc::SoftLine);
+                Some(Doc::group(Doc::Concat(vec![
+                    Doc::nest(Doc::Concat(self.docs)),
+                    Doc::SoftLine,
+                ])))
+            }
+            Kind::Attributes => Some(Doc::group(Doc::Concat(self.docs))),
+            Kind::Binary => {
+                let mut operands = vec![];
+                for start in self.operand_starts.into_iter().rev() {
+                    operands.push(Doc::Concat(self.docs.split_off(start)));
+                }
+                operands.push(Doc::Concat(self.docs));
+                operands.reverse();
+
+                Some(binary_tree(operands, self.operators))
+            }
+        }
+    }
+}
+
+// Operators of the lowest precedence are broken first, each starting a new line, while operands
+// with higher precedence operators are grouped and only broken when they do not fit on their own
+fn binary_tree(operands: Vec<Doc>, operators: Vec<(u8, String)>) -> Doc {
+    let Some(lowest) = operators.iter().map(|(precedence, _)| *precedence).min() else {
+        return operands.into_iter().next().unwrap_or(Doc::Concat(vec![]));
+    };
+
+    let mut operands = operands.into_iter();
+    let mut segments = vec![(None, vec![operands.next().unwrap()], vec![])];
+
+    for ((precedence, operator), operand) in operators.into_iter().zip(operands) {
+        if precedence == lowest {
+            segments.push((Some(operator), vec![operand], vec![]));
+        } else {
+            let (_, segment_operands, segment_operators) = segments.last_mut().unwrap();
+            segment_operators.push((precedence, operator));
+            segment_operands.push(operand);
+        }
+    }
+
+    let mut segments = segments.into_iter();
+    let (_, first_operands, first_operators) = segments.next().unwrap();
+    let first = binary_tree(first_operands, first_operators);
+
+    let rest = segments
+        .flat_map(|(operator, operands, operators)| {
+            [
+                Doc::Line,
+                Doc::text(operator.unwrap_or_default()),
+                Doc::text(" "),
+                binary_tree(operands, operators),
+            ]
+        })
+        .collect();
+
+    Doc::group(Doc::Concat(vec![first, Doc::nest(Doc::Concat(rest))]))
+}
+
+// The lines of multi-line comments are indented along with the code around them
+fn comment(text: &str) -> Doc {
+    let mut docs = vec![];
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            docs.push(Doc::HardLine);
+        }
+        docs.push(Doc::text(line));
+    }
+    Doc::Concat(docs)
+}
+
+fn precedence(operator: &str) -> Option<u8> {
+    match operator {
+        "||" => Some(0),
+        "&&" => Some(1),
+        "|" => Some(2),
+        "^" => Some(3),
+        "&" => Some(4),
+        "==" | "!=" | "<" | ">" | "<=" | ">=" => Some(5),
+        "<<" | ">>" => Some(6),
+        "+" | "-" => Some(7),
+        "*" | "/" | "%" => Some(8),
+        _ => None,
+    }
+}
+
+// The precedence of `token` when it is used as a binary operator, which is when it follows the
+// end of an operand, rather than being a unary operator like `-x` or `&x`
+pub fn binary_operator(prev: &Token, token: &Token) -> Option<u8> {
+    match token {
+        Token::Syntax(operator) if ends_operand(prev) => precedence(operator),
+        _ => None,
+    }
+}
+
+// Tokens which can continue a binary expression, any other token ends it
+fn continues_expression(token: &Token) -> bool {
+    match token {
+        Token::Ident(_)
+        | Token::Error(_)
+        | Token::Integer(_)
+        | Token::Float(_)
+        | Token::Boolean(_)
+        | Token::Trivia(_)
+        | Token::TemplateArgsStart
+        | Token::TemplateArgsEnd => true,
+        Token::Syntax(syntax) => {
+            matches!(*syntax, "." | "(" | ")" | "[" | "]" | "!" | "~")
+                || precedence(syntax).is_some()
+        }
+        _ => false,
+    }
+}
+
+pub struct DocBuilder<'a> {
+    style: &'a FormatStyle,
+    frames: Vec<Frame>,
+    // Set by `struct`, until the block of its members is opened
+    struct_pending: bool,
+}
+
+impl<'a> DocBuilder<'a> {
+    pub fn new(style: &'a FormatStyle) -> Self {
+        Self {
+            style,
+            frames: vec![Frame::new(Kind::Root)],
+            struct_pending: false,
+        }
+    }
+
+    fn top(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn push(&mut self, doc: Doc) {
+        self.top().docs.push(doc);
+    }
+
+    fn push_delimiter(&mut self, doc: Doc) {
+        let top = self.top();
+        top.docs.push(doc);
+        top.operand_start = top.docs.len();
+    }
+
+    fn open(&mut self, kind: Kind) {
+        self.frames.push(Frame::new(kind));
+    }
+
+    fn close_top(&mut self) {
+        let frame = self.frames.pop().unwrap();
+        if let Some(doc) = frame.into_doc(self.style) {
+            self.push(doc);
+            let top = self.top();
+            top.content_end = top.docs.len();
+            top.trailing_comma = false;
+        }
+    }
+
+    // Closes the innermost frame matching `kind` and the frames inside of it. Frames outside of
+    // the current block are never closed, so unbalanced brackets stay within their block.
+    fn close(&mut self, kind: impl Fn(&Kind) -> bool) {
+        let Some(index) = self.frames.iter().rposition(|frame| {
+            kind(&frame.kind) || matches!(frame.kind, Kind::Root | Kind::Block { .. })
+        }) else {
+            return;
+        };
+
+        if kind(&self.frames[index].kind) {
+            while self.frames.len() > index {
+                self.close_top();
+            }
+        }
+    }
+
+    fn close_if(&mut self, kind: Kind) {
+        if self.top().kind == kind {
+            self.close_top();
+        }
+    }
+
+    fn operator(&mut self, precedence: u8, operator: &str) {
+        if self.top().kind != Kind::Binary {
+            // The first operand was pushed before knowing it is part of a binary expression
+            let top = self.top();
+            let start = top.operand_start.min(top.docs.len());
+            let operand = top.docs.split_off(start);
+
+            self.open(Kind::Binary);
+            self.top().docs = operand;
+        }
+
+        let top = self.top();
+        top.operators.push((precedence, operator.to_owned()));
+        top.operand_starts.push(top.docs.len());
+    }
+
+    pub fn token(&mut self, prev: &Token, token: &Token, text: &str) {
+        match token {
+            Token::Syntax(")") => self.close(|kind| matches!(kind, Kind::Paren { .. })),
+            Token::Syntax("]") => self.close(|kind| *kind == Kind::Bracket),
+            Token::TemplateArgsEnd => self.close(|kind| *kind == Kind::Template),
+            Token::Syntax("@") if self.top().kind != Kind::Attributes => {
+                self.close_if(Kind::Binary);
+                self.open(Kind::Attributes);
+            }
+            _ if !continues_expression(token) => self.close_if(Kind::Binary),
+            _ => {}
+        }
+
+        match binary_operator(prev, token) {
+            Some(precedence) => self.operator(precedence, text),
+            None if matches!(token, Token::Trivia(_)) => self.push(comment(text)),
+            None => self.push(Doc::text(text)),
+        }
+
+        if !matches!(token, Token::Trivia(_)) {
+            let top = self.top();
+            top.content_end = top.docs.len();
+            top.trailing_comma = matches!(token, Token::Syntax(","));
+        }
+
+        match token {
+            Token::Keyword(Keyword::Struct) => self.struct_pending = true,
+            Token::Syntax("(") => self.open(Kind::Paren {
+                call: matches!(prev, Token::Ident(_) | Token::TemplateArgsEnd),
+            }),
+            Token::Syntax("[") => self.open(Kind::Bracket),
+            Token::TemplateArgsStart => self.open(Kind::Template),
+            _ => {}
+        }
+    }
+
+    pub fn open_block(&mut self) {
+        let struct_body = std::mem::take(&mut self.struct_pending);
+        self.open(Kind::Block { struct_body });
+    }
+
+    pub fn close_block(&mut self) {
+        self.close(|kind| matches!(kind, Kind::Block { .. }));
+    }
+
+    // Whitespace next to text which could not be lexed is kept as it is
+    pub fn verbatim(&mut self, whitespace: &str) {
+        self.push_delimiter(Doc::text(whitespace));
+    }
+
+    pub fn delimiter(
+        &mut self,
+        delimiter: &Delimiter,
+        explicit_newline: bool,
+        token: &Token,
+        next_token: &Token,
+    ) {
+        let attribute_ended = self.top().kind == Kind::Attributes
+            && !matches!(delimiter, Delimiter::None)
+            && !matches!(token, Token::Syntax("@"));
+
+        match delimiter {
+            Delimiter::None => {}
+            Delimiter::Space if attribute_ended => {
+                self.push_delimiter(Doc::Line);
+                if !matches!(next_token, Token::Syntax("@")) {
+                    self.close_top();
+                }
+            }
+            Delimiter::Space => {
+                let align_struct_members = self.style.align_struct_members;
+                let top = self.top();
+                let after_operator =
+                    top.kind == Kind::Binary && top.operand_starts.last() == Some(&top.docs.len());
+
+                // Binary operators add their own line breaks and spaces
+                if binary_operator(token, next_token).is_some() || after_operator {
+                    return;
+                }
+
+                match (&top.kind, token) {
+                    // Trailing comments stay after the comma, and break the list
+                    (_, Token::Syntax(",")) if matches!(next_token, Token::Trivia(_)) => {
+                        self.push_delimiter(Doc::text(" "))
+                    }
+                    (Kind::Paren { call: true }, Token::Syntax(",")) => {
+                        self.push_delimiter(Doc::Line)
+                    }
+                    (Kind::Block { struct_body: true }, Token::Syntax(":"))
+                        if align_struct_members =>
+                    {
+                        // The member starts after the last line break
+                        let start = top
+                            .docs
+                            .iter()
+                            .rposition(|doc| matches!(doc, Doc::HardLine));
+                        let member = &top.docs[start.map_or(0, |index| index + 1)..];
+                        let width = member.iter().map(Doc::flat_width).sum();
+
+                        top.member_types.push((top.docs.len(), width));
+                        self.push_delimiter(Doc::text(" "));
+                    }
+                    _ => self.push_delimiter(Doc::text(" ")),
+                }
+            }
+            Delimiter::Newline | Delimiter::DoubleNewline => {
+                if attribute_ended {
+                    self.close_top();
+                }
+
+                let blank_lines = match delimiter {
+                    Delimiter::DoubleNewline => self.style.blank_lines_between_items,
+                    _ => explicit_newline as usize,
+                };
+
+                for _ in 0..=blank_lines {
+                    self.push_delimiter(Doc::HardLine);
+                }
+            }
+        }
+    }
+
+    pub fn render(mut self, layout: &Layout) -> String {
+        while self.frames.len() > 1 {
+            self.close_top();
+        }
+
+        let root = self.frames.pop().unwrap();
+        layout.render(&Doc::Concat(root.docs))
+    }
+}
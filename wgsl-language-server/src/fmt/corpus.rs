@@ -0,0 +1,102 @@
+// Formats real-world shaders, checking that formatting is idempotent and only changes whitespace
+use lsp_types::FormattingOptions;
+
+use super::{FormatStyle, pretty_print_ast};
+use crate::lexer::{Token, lex_with_errors};
+
+const CORPUS: [(&str, &str); 3] = [
+    ("compute.wgsl", include_str!("corpus/compute.wgsl")),
+    ("pbr.wgsl", include_str!("corpus/pbr.wgsl")),
+    (
+        "post_process.wgsl",
+        include_str!("corpus/post_process.wgsl"),
+    ),
+];
+
+fn styles() -> Vec<FormatStyle> {
+    vec![
+        FormatStyle::default(),
+        // Narrow enough to wrap argument lists and binary expressions
+        FormatStyle {
+            max_width: 40,
+            ..Default::default()
+        },
+    ]
+}
+
+fn format(source: &str, style: &FormatStyle) -> String {
+    let options = FormattingOptions {
+        tab_size: 4,
+        insert_spaces: true,
+        ..Default::default()
+    };
+    pretty_print_ast(source, &options, style).unwrap()
+}
+
+fn tokens(source: &str) -> Vec<Token<'_>> {
+    let tokens: Vec<_> = lex_with_errors(source)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect();
+    assert!(!tokens.iter().any(|token| matches!(token, Token::Error(_))));
+    tokens
+}
+
+// The debug output of a module, without the spans of statements, which are the only part of it
+// expected to change. Arenas leave out their spans already.
+fn module(name: &str, source: &str) -> String {
+    let module = match naga::front::wgsl::parse_str(source) {
+        Ok(module) => format!("{module:#?}"),
+        Err(err) => panic!("{name}: {}\n{source}", err.emit_to_string(source)),
+    };
+
+    let mut output = String::new();
+    let mut spans_indent = None;
+    for line in module.lines() {
+        let indent = line.len() - line.trim_start().len();
+        match spans_indent {
+            Some(start) if indent == start && line.trim() == "]," => spans_indent = None,
+            Some(_) => {}
+            None if line.trim() == "span_info: [" => spans_indent = Some(indent),
+            None => {
+                output += line;
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+#[test]
+pub fn formatting_is_idempotent() {
+    for style in styles() {
+        for (name, source) in CORPUS {
+            let formatted = format(source, &style);
+            assert_eq!(
+                format(&formatted, &style),
+                formatted,
+                "{name} changed when formatted twice"
+            );
+        }
+    }
+}
+
+#[test]
+pub fn formatting_keeps_tokens() {
+    for style in styles() {
+        for (name, source) in CORPUS {
+            let formatted = format(source, &style);
+            assert_eq!(tokens(&formatted), tokens(source), "{name}");
+        }
+    }
+}
+
+#[test]
+pub fn formatting_keeps_the_module() {
+    for style in styles() {
+        for (name, source) in CORPUS {
+            let formatted = format(source, &style);
+            assert_eq!(module(name, &formatted), module(name, source), "{name}");
+        }
+    }
+}
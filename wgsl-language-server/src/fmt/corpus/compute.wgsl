@@ -0,0 +1,63 @@
+// Parallel prefix sum over a storage buffer, followed by a histogram pass
+
+struct Params {
+    count: u32,
+    bins: u32,
+    scale: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> values: array<f32>;
+@group(0) @binding(2) var<storage, read_write> histogram: array<atomic<u32>, 64>;
+
+const WORKGROUP_SIZE: u32 = 64u;
+
+var<workgroup> scratch: array<f32, WORKGROUP_SIZE>;
+
+fn add_to(total: ptr<function, f32>, value: f32) {
+    *total = *total + value;
+}
+
+fn bin_of(value: f32) -> u32 {
+    let clamped = clamp(value * params.scale, 0.0, f32(params.bins - 1u));
+    return u32(clamped);
+}
+
+@compute @workgroup_size(64)
+fn prefix_sum(@builtin(global_invocation_id) global_id: vec3<u32>, @builtin(local_invocation_index) local_index: u32) {
+    let index = global_id.x;
+    if index < params.count {
+        scratch[local_index] = values[index];
+    } else {
+        scratch[local_index] = 0.0;
+    }
+    workgroupBarrier();
+
+    /* Hillis-Steele scan: each step adds the value `offset` invocations back,
+       doubling the offset until it covers the workgroup */
+    for (var offset = 1u; offset < WORKGROUP_SIZE; offset = offset << 1u) {
+        var sum = scratch[local_index];
+        if local_index >= offset {
+            add_to(&sum, scratch[local_index - offset]);
+        }
+        workgroupBarrier();
+        scratch[local_index] = sum;
+        workgroupBarrier();
+    }
+
+    if index < params.count {
+        values[index] = scratch[local_index];
+    }
+}
+
+@compute @workgroup_size(64)
+fn build_histogram(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if index >= params.count || params.bins == 0u { // Out of bounds
+        return;
+    }
+
+    let value = -values[index];
+    let bin = bin_of(abs(value)) & 63u;
+    atomicAdd(&histogram[bin], 1u);
+}
@@ -0,0 +1,97 @@
+// Physically based shading with a single directional light and image based ambient light
+
+const PI: f32 = 3.14159265359;
+
+struct Camera {
+    view_projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+struct Light {
+    direction: vec3<f32>,
+    color: vec3<f32>,
+    intensity: f32,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(0) @binding(1) var<uniform> light: Light;
+@group(1) @binding(0) var base_color_texture: texture_2d<f32>;
+@group(1) @binding(1) var metallic_roughness_texture: texture_2d<f32>;
+@group(1) @binding(2) var material_sampler: sampler;
+@group(2) @binding(0) var environment: texture_cube<f32>;
+@group(2) @binding(1) var environment_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.world_position = in.position;
+    out.clip_position = camera.view_projection * vec4<f32>(in.position, 1.0);
+    out.normal = normalize(in.normal);
+    out.uv = in.uv;
+    return out;
+}
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denominator * denominator);
+}
+
+fn geometry_schlick_ggx(n_dot_v: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    return n_dot_v / (n_dot_v * (1.0 - k) + k);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (vec3<f32>(1.0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base_color = textureSample(base_color_texture, material_sampler, in.uv);
+    let metallic_roughness = textureSample(metallic_roughness_texture, material_sampler, in.uv);
+    let metallic = metallic_roughness.b;
+    let roughness = max(metallic_roughness.g, 0.04); // Avoids a division by zero
+
+    let n = normalize(in.normal);
+    let v = normalize(camera.position - in.world_position);
+    let l = normalize(-light.direction);
+    let h = normalize(v + l);
+
+    let n_dot_v = max(dot(n, v), /* epsilon */ 0.0001);
+    let n_dot_l = max(dot(n, l), 0.0);
+    let n_dot_h = max(dot(n, h), 0.0);
+
+    let f0 = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+    let f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+    let d = distribution_ggx(n_dot_h, roughness);
+    let g = geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+
+    let specular = d * g * f / (4.0 * n_dot_v * n_dot_l + 0.0001);
+    let diffuse = (vec3<f32>(1.0) - f) * (1.0 - metallic) * base_color.rgb / PI;
+    let direct = (diffuse + specular) * light.color * light.intensity * n_dot_l;
+
+    let reflection = reflect(-v, n);
+    let ambient = textureSampleLevel(environment, environment_sampler, reflection, roughness * 8.0).rgb;
+
+    var color = direct + ambient * base_color.rgb * 0.03;
+    // Reinhard tone mapping, then gamma correction
+    color = color / (color + vec3<f32>(1.0));
+    color = pow(color, vec3<f32>(1.0 / 2.2));
+
+    return vec4<f32>(color, base_color.a);
+}
@@ -0,0 +1,85 @@
+// Full screen post processing: bloom threshold, separable blur, vignette and film grain
+
+struct Settings {
+    threshold: f32,
+    vignette: f32,
+    grain: f32,
+    time: f32,
+    direction: vec2<f32>,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: Settings; // Updated every frame
+
+const WEIGHTS = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+struct FullscreenOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn fullscreen(@builtin(vertex_index) vertex_index: u32) -> FullscreenOutput {
+    var out: FullscreenOutput;
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    var p3 = fract(vec3<f32>(p.xyx) * 0.1031);
+    p3 += dot(p3, p3.yzx + 33.33);
+    return fract((p3.x + p3.y) * p3.z);
+}
+
+@fragment
+fn threshold(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, source_sampler, in.uv).rgb;
+    let brightness = luminance(color);
+    if brightness > settings.threshold {
+        return vec4<f32>(color, 1.0);
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn blur(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    var result = textureSample(source, source_sampler, in.uv).rgb * WEIGHTS[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = settings.direction * texel * f32(i);
+        result += textureSample(source, source_sampler, in.uv + offset).rgb * WEIGHTS[i];
+        result += textureSample(source, source_sampler, in.uv - offset).rgb * WEIGHTS[i];
+    }
+    return vec4<f32>(result, 1.0);
+}
+
+@fragment
+fn composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(source, source_sampler, in.uv).rgb;
+
+    let centered = in.uv - vec2<f32>(0.5);
+    let vignette = 1.0 - dot(centered, centered) * settings.vignette;
+    color *= clamp(vignette, 0.0, 1.0);
+
+    let noise = hash(in.uv * 1000.0 + vec2<f32>(settings.time)) - 0.5;
+    color += vec3<f32>(noise * settings.grain);
+
+    switch i32(settings.time) % 3 {
+        case 0: { // Swizzled
+            color = color.bgr;
+        }
+        case 1, 2: {
+            color = vec3<f32>(luminance(color));
+        }
+        default: {}
+    }
+
+    return vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
@@ -0,0 +1,201 @@
+// A document algebra in the style of Wadler's "A prettier printer". Groups are laid out on a
+// single line when they fit in the remaining width, otherwise every line break directly inside
+// them is taken.
+
+pub enum Doc {
+    Text(String),
+    // A space, or a line break when the enclosing group does not fit
+    Line,
+    // Nothing, or a line break when the enclosing group does not fit
+    SoftLine,
+    // Always a line break, which forces enclosing groups to break
+    HardLine,
+    // Text only written when the enclosing group is broken, like trailing commas
+    IfBreak(String),
+    // Indents line breaks by one more level
+    Nest(Box<Doc>),
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Doc {
+        Doc::Text(text.into())
+    }
+
+    pub fn nest(doc: Doc) -> Doc {
+        Doc::Nest(Box::new(doc))
+    }
+
+    pub fn group(doc: Doc) -> Doc {
+        Doc::Group(Box::new(doc))
+    }
+
+    /// The width of the document laid out on a single line.
+    pub fn flat_width(&self) -> usize {
+        match self {
+            Doc::Text(text) => text_width(text),
+            Doc::Line => 1,
+            Doc::SoftLine | Doc::HardLine | Doc::IfBreak(_) => 0,
+            Doc::Nest(doc) | Doc::Group(doc) => doc.flat_width(),
+            Doc::Concat(docs) => docs.iter().map(Doc::flat_width).sum(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+pub struct Layout<'a> {
+    pub max_width: usize,
+    pub indent_str: &'a str,
+    // Columns taken by one level of indentation, which for tabs is the tab size
+    pub indent_width: usize,
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+impl Layout<'_> {
+    pub fn render(&self, doc: &Doc) -> String {
+        let mut output = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, doc)];
+
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(text) => {
+                    output += text;
+                    column = match text.rfind('\n') {
+                        Some(index) => text_width(&text[index + 1..]),
+                        None => column + text_width(text),
+                    };
+                }
+                Doc::IfBreak(text) => {
+                    if mode == Mode::Break {
+                        output += text;
+                        column += text_width(text);
+                    }
+                }
+                Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                    if matches!(doc, Doc::Line) {
+                        output.push(' ');
+                        column += 1;
+                    }
+                }
+                Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                    // Blank lines and lines ending before a break have no trailing whitespace
+                    output.truncate(output.trim_end_matches([' ', '\t']).len());
+                    output.push('\n');
+                    output += &self.indent_str.repeat(indent);
+                    column = indent * self.indent_width;
+                }
+                Doc::Nest(doc) => stack.push((indent + 1, mode, doc)),
+                Doc::Group(doc) => {
+                    let mode = match mode == Mode::Flat
+                        || self.fits(column, (indent, Mode::Flat, doc), &stack)
+                    {
+                        true => Mode::Flat,
+                        false => Mode::Break,
+                    };
+                    stack.push((indent, mode, doc));
+                }
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            }
+        }
+
+        output
+    }
+
+    // Whether `next` fits on the current line in flat mode, along with what follows it up to the
+    // next possible line break
+    fn fits(&self, column: usize, next: (usize, Mode, &Doc), rest: &[(usize, Mode, &Doc)]) -> bool {
+        let mut remaining = self.max_width as isize - column as isize;
+        let mut stack = vec![(next.1, next.2)];
+        let mut rest = rest.iter().rev();
+
+        while remaining >= 0 {
+            let Some((mode, doc)) = stack
+                .pop()
+                .or_else(|| rest.next().map(|(_, mode, doc)| (*mode, *doc)))
+            else {
+                return true;
+            };
+
+            match doc {
+                Doc::Text(text) if text.contains('\n') => return mode == Mode::Break,
+                Doc::Text(text) => remaining -= text_width(text) as isize,
+                Doc::IfBreak(text) if mode == Mode::Break => remaining -= text_width(text) as isize,
+                Doc::IfBreak(_) => {}
+                Doc::Line | Doc::SoftLine | Doc::HardLine if mode == Mode::Break => return true,
+                Doc::HardLine => return false,
+                Doc::Line => remaining -= 1,
+                Doc::SoftLine => {}
+                Doc::Nest(doc) | Doc::Group(doc) => stack.push((mode, doc)),
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn call(name: &str, args: &[&str]) -> Doc {
+        let mut docs = vec![Doc::SoftLine];
+        for (index, arg) in args.iter().enumerate() {
+            if index > 0 {
+                docs.push(Doc::text(","));
+                docs.push(Doc::Line);
+            }
+            docs.push(Doc::text(*arg));
+        }
+
+        Doc::Concat(vec![
+            Doc::text(format!("{name}(")),
+            Doc::group(Doc::Concat(vec![
+                Doc::nest(Doc::Concat(docs)),
+                Doc::SoftLine,
+            ])),
+            Doc::text(");"),
+        ])
+    }
+
+    fn render(doc: &Doc, max_width: usize) -> String {
+        Layout {
+            max_width,
+            indent_str: "  ",
+            indent_width: 2,
+        }
+        .render(doc)
+    }
+
+    #[test]
+    pub fn groups_break_when_too_long() {
+        let doc = call("f", &["aaa", "bbb"]);
+
+        assert_eq!(render(&doc, 12), "f(aaa, bbb);");
+        // The `);` following the group counts towards its width
+        assert_eq!(render(&doc, 11), "f(\n  aaa,\n  bbb\n);");
+    }
+
+    #[test]
+    pub fn hard_lines_break_groups() {
+        let doc = Doc::group(Doc::Concat(vec![
+            Doc::text("a"),
+            Doc::Line,
+            Doc::text("// b"),
+            Doc::HardLine,
+            Doc::text("c"),
+        ]));
+
+        assert_eq!(render(&doc, 80), "a\n// b\nc");
+    }
+}
@@ -0,0 +1,305 @@
+// Formatting as a set of small edits. The formatter only changes the whitespace between tokens,
+// the indentation inside block comments, and trailing commas, so the tokens of the source and of
+// the formatted output can be paired up, and each differing gap between them becomes an edit.
+// Range and on-type formatting keep the edits in a range.
+
+use std::ops::Range;
+
+use lsp_types::FormattingOptions;
+
+use super::{FormatStyle, pretty_print_ast};
+use crate::lexer::lex_with_errors;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+// The ranges of tokens, excluding trailing whitespace which some tokens include, like the
+// newline of line comments
+fn token_ranges(source: &str) -> Vec<Range<usize>> {
+    lex_with_errors(source)
+        .into_iter()
+        .map(|(_, span)| span.start..span.start + source[span].trim_end().len())
+        .collect()
+}
+
+// Shrinks a replacement of `range` by `text` to the part which differs
+fn minimal_edit(source: &str, range: Range<usize>, text: &str) -> Edit {
+    let original = &source[range.clone()];
+
+    let prefix: usize = original
+        .chars()
+        .zip(text.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    let suffix: usize = original[prefix..]
+        .chars()
+        .rev()
+        .zip(text[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    Edit {
+        range: range.start + prefix..range.end - suffix,
+        text: text[prefix..text.len() - suffix].to_owned(),
+    }
+}
+
+// Whether two block comments only differ in the whitespace of their lines
+fn reindented_comment(a: &str, b: &str) -> bool {
+    a.starts_with("/*") && a.split_whitespace().eq(b.split_whitespace())
+}
+
+/// The edits turning `source` into `formatted`, or `None` when their tokens differ.
+pub fn whitespace_edits(source: &str, formatted: &str) -> Option<Vec<Edit>> {
+    let source_tokens = token_ranges(source);
+    let formatted_tokens = token_ranges(formatted);
+
+    let mut edits = vec![];
+    let (mut i, mut j) = (0, 0);
+    let (mut source_end, mut formatted_end) = (0, 0);
+
+    loop {
+        // Commas added or removed by the formatter become part of the gap
+        loop {
+            let a = source_tokens.get(i).map(|range| &source[range.clone()]);
+            let b = formatted_tokens
+                .get(j)
+                .map(|range| &formatted[range.clone()]);
+
+            match (a, b) {
+                (Some(a), Some(b)) if a == b || reindented_comment(a, b) => break,
+                (None, None) => break,
+                (_, Some(",")) => j += 1,
+                (Some(","), _) => i += 1,
+                _ => return None,
+            }
+        }
+
+        let source_gap = source_end..source_tokens.get(i).map_or(source.len(), |it| it.start);
+        let formatted_gap = formatted_end
+            ..formatted_tokens
+                .get(j)
+                .map_or(formatted.len(), |it| it.start);
+
+        if source[source_gap.clone()] != formatted[formatted_gap.clone()] {
+            edits.push(minimal_edit(source, source_gap, &formatted[formatted_gap]));
+        }
+
+        let (Some(a), Some(b)) = (source_tokens.get(i), formatted_tokens.get(j)) else {
+            return Some(edits);
+        };
+
+        if source[a.clone()] != formatted[b.clone()] {
+            edits.push(minimal_edit(source, a.clone(), &formatted[b.clone()]));
+        }
+
+        source_end = a.end;
+        formatted_end = b.end;
+        i += 1;
+        j += 1;
+    }
+}
+
+// Clamps an offset to the source, moving it back to the start of the character it is in
+fn char_boundary(source: &str, offset: usize) -> usize {
+    (0..=offset.min(source.len()))
+        .rev()
+        .find(|&index| source.is_char_boundary(index))
+        .unwrap_or_default()
+}
+
+// Extends a range to the start and end of the lines it covers
+fn whole_lines(source: &str, range: Range<usize>) -> Range<usize> {
+    let range_start = char_boundary(source, range.start);
+    let range_end = char_boundary(source, range.end).max(range_start);
+
+    let start = source[..range_start]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let end = source[range_end..]
+        .find('\n')
+        .map_or(source.len(), |index| range_end + index);
+
+    start..end
+}
+
+/// Formats the lines covered by `range`, leaving the rest of the source untouched.
+pub fn format_range(
+    source: &str,
+    options: &FormattingOptions,
+    style: &FormatStyle,
+    range: Range<usize>,
+) -> Option<Vec<Edit>> {
+    let formatted = pretty_print_ast(source, options, style)?;
+    let lines = whole_lines(source, range);
+
+    let edits = whitespace_edits(source, &formatted)?
+        .into_iter()
+        .filter(|edit| edit.range.start >= lines.start && edit.range.end <= lines.end)
+        .collect();
+
+    Some(edits)
+}
+
+// The `{` of the innermost block containing `offset`
+fn enclosing_block_start(source: &str, offset: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, char) in source[..offset].char_indices().rev() {
+        match char {
+            '}' => depth += 1,
+            '{' if depth == 0 => return Some(index),
+            '{' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Formats the block closed by a `}`, or the block containing a `;`, just typed before `offset`.
+/// Top level statements, which are not in a block, only have their own line formatted.
+pub fn format_on_type(
+    source: &str,
+    options: &FormattingOptions,
+    style: &FormatStyle,
+    offset: usize,
+    ch: char,
+) -> Option<Vec<Edit>> {
+    let typed = source[..char_boundary(source, offset)].rfind(ch)?;
+
+    let range = match ch {
+        '}' => enclosing_block_start(source, typed)?..typed + 1,
+        ';' => match enclosing_block_start(source, typed) {
+            Some(start) => start..typed + 1,
+            None => typed..typed + 1,
+        },
+        _ => return None,
+    };
+
+    format_range(source, options, style, range)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options() -> FormattingOptions {
+        FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        }
+    }
+
+    fn apply(source: &str, edits: &[Edit]) -> String {
+        let mut result = source.to_owned();
+        for edit in edits.iter().rev() {
+            result.replace_range(edit.range.clone(), &edit.text);
+        }
+        result
+    }
+
+    #[test]
+    pub fn edits_only_change_whitespace() {
+        let source = "fn main() {\n  let a = 1;\n    let b = 2;\n}\n";
+        let formatted = pretty_print_ast(source, &options(), &FormatStyle::default()).unwrap();
+        let edits = whitespace_edits(source, &formatted).unwrap();
+
+        assert_eq!(
+            edits,
+            vec![Edit {
+                range: 14..14,
+                text: "  ".to_owned()
+            }]
+        );
+        assert_eq!(apply(source, &edits), formatted);
+    }
+
+    #[test]
+    pub fn separate_edits_around_comments() {
+        let source = "// comment\nstruct A {\nx:f32,\n  /* y */ y : u32\n}\n";
+        let formatted = pretty_print_ast(source, &options(), &FormatStyle::default()).unwrap();
+        let edits = whitespace_edits(source, &formatted).unwrap();
+
+        assert!(edits.len() > 1);
+        assert!(
+            edits
+                .iter()
+                .all(|edit| edit.range.start >= "// comment\n".len())
+        );
+        assert_eq!(apply(source, &edits), formatted);
+        assert_eq!(whitespace_edits(&formatted, &formatted), Some(vec![]));
+    }
+
+    #[test]
+    pub fn different_tokens_have_no_edits() {
+        assert_eq!(whitespace_edits("let a = 1;", "let b = 1;"), None);
+    }
+
+    #[test]
+    pub fn trailing_commas_are_part_of_edits() {
+        let source = "struct A {\n    a: f32\n}\nfn f(a: u32,) {}";
+        let formatted = "struct A {\n    a: f32,\n}\nfn f(a: u32) {}";
+        let edits = whitespace_edits(source, formatted).unwrap();
+
+        assert_eq!(edits.len(), 2);
+        assert_eq!(apply(source, &edits), formatted);
+    }
+
+    #[test]
+    pub fn range_formatting_keeps_other_lines() {
+        let source = "fn a() {\nlet x=1;\nlet y=2;\n}\n";
+        let start = source.find("let y").unwrap();
+        let edits =
+            format_range(source, &options(), &FormatStyle::default(), start..start).unwrap();
+
+        assert_eq!(
+            apply(source, &edits),
+            "fn a() {\nlet x=1;\n    let y = 2;\n}\n"
+        );
+    }
+
+    #[test]
+    pub fn on_type_formatting_reindents_the_block() {
+        let source = "const c=1;\nfn a() {\nif true {\nlet x=1;\n}\n}\n";
+        let offset = source.rfind('}').unwrap() + 1;
+        let edits =
+            format_on_type(source, &options(), &FormatStyle::default(), offset, '}').unwrap();
+
+        assert_eq!(
+            apply(source, &edits),
+            "const c=1;\nfn a() {\n    if true {\n        let x = 1;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    pub fn offsets_inside_characters() {
+        let source = "fn a() {\nlet x=1; // é é}\n}";
+        let inside = source.find("é}").unwrap() + 1;
+
+        assert_eq!(whole_lines(source, inside..inside), 9..source.len() - 2);
+        assert_eq!(whole_lines(source, inside..1000), 9..source.len());
+        assert!(format_on_type(source, &options(), &FormatStyle::default(), inside, '}').is_none());
+    }
+
+    #[test]
+    pub fn block_comments_are_reindented_in_place() {
+        let source = "fn a() {\n        /* one\n           two */\n    let x = 1; // x\n}\n";
+        let formatted = pretty_print_ast(source, &options(), &FormatStyle::default()).unwrap();
+        let edits = whitespace_edits(source, &formatted).unwrap();
+
+        assert_eq!(
+            formatted,
+            "fn a() {\n    /* one\n       two */\n    let x = 1; // x\n}\n"
+        );
+        assert_eq!(edits.len(), 2);
+        assert_eq!(apply(source, &edits), formatted);
+    }
+}
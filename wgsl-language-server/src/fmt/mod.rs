@@ -0,0 +1,609 @@
+mod builder;
+#[cfg(test)]
+mod corpus;
+mod doc;
+mod edits;
+mod style;
+
+pub use edits::{Edit, format_on_type, format_range, whitespace_edits};
+pub use style::{AttributePlacement, BraceStyle, FormatStyle};
+
+use builder::{DocBuilder, binary_operator};
+use doc::Layout;
+use lsp_types::FormattingOptions;
+
+use std::{borrow::Cow, ops::Range};
+
+use crate::lexer::{Keyword, Token, lex_with_errors};
+
+pub enum Delimiter {
+    DoubleNewline,
+    Newline,
+    Space,
+    None,
+}
+
+pub struct Formatted {
+    pub text: String,
+    // Ranges of the source which could not be lexed, and were kept as they are
+    pub skipped: Vec<Range<usize>>,
+}
+
+pub fn pretty_print_ast(
+    code: &str,
+    options: &FormattingOptions,
+    style: &FormatStyle,
+) -> Option<String> {
+    format(code, options, style).map(|formatted| formatted.text)
+}
+
+/// Formats `code`, leaving the text which could not be lexed and the whitespace around it as it
+/// is. Everything around those ranges is still formatted.
+pub fn format(code: &str, options: &FormattingOptions, style: &FormatStyle) -> Option<Formatted> {
+    let tokens = lex_with_errors(code);
+    let skipped: Vec<_> = tokens
+        .iter()
+        .filter(|(token, _)| matches!(token, Token::Error(_)))
+        .map(|(_, span)| span.clone())
+        .collect();
+
+    let mut ctx = ASTContext::new(options);
+    let mut builder = DocBuilder::new(style);
+
+    for window in tokens.windows(2) {
+        let (token, span) = &window[0];
+        let (next_token, next_span) = &window[1];
+        let src_content = token_text(code, token, span);
+
+        // Whitespace will be anything skipped by the lexer, and the newline of line comments
+        let whitespace = &code[content_end(code, span)..next_span.start];
+        let whitespace_lines = whitespace.chars().filter(|it| *it == '\n').count();
+        let has_explicit_newline = whitespace_lines > 1;
+
+        use Delimiter as D;
+        use Token as T;
+
+        ctx.handle_brackets((token, next_token));
+        let indent_level = ctx.indent_level;
+
+        let delimiter = match (token, next_token) {
+            // Skip the delimiter for property access
+            (_, T::Syntax(".")) | (T::Syntax("."), _) => D::None,
+
+            (T::TemplateArgsStart, _) | (_, T::TemplateArgsEnd) => match style.template_spacing {
+                true => D::Space,
+                false => D::None,
+            },
+
+            (T::Keyword(_) | T::Ident(_), T::TemplateArgsStart) => D::None,
+
+            (T::Syntax("("), _) => D::None,
+            (_, T::Syntax(")")) => D::None,
+
+            (T::Syntax("["), _) => D::None,
+            (_, T::Syntax("]")) => D::None,
+
+            (T::Ident(_) | T::Syntax("]"), T::Syntax("[")) => D::None,
+            (T::Syntax("{"), T::Syntax("}")) => D::Space,
+
+            (_, T::Syntax("}")) => {
+                ctx.dedent();
+                D::Newline
+            }
+            (T::Syntax("{"), _) => {
+                ctx.indent();
+                D::Newline
+            }
+            (_, T::Syntax("{")) if style.brace_style == BraceStyle::NextLine => D::Newline,
+            (T::Syntax(";"), _) => {
+                if ctx.paren_level > 0 {
+                    D::Space
+                } else {
+                    D::Newline
+                }
+            }
+            (T::Syntax("}"), _) => {
+                if ctx.indent_level == 0 {
+                    D::DoubleNewline
+                } else {
+                    D::Newline
+                }
+            }
+
+            // Unary Operators
+            (T::Syntax("!" | "~"), _) => D::None,
+
+            // Negation, dereference and address-of
+            (T::Syntax("-" | "*" | "&"), _) if !ends_operand(ctx.prev_token) => D::None,
+
+            // Newlines for struct declaration properties
+            (T::Syntax(","), _) => {
+                if ctx.brace_level == 1
+                    && ctx.template_level == 0
+                    && ctx.paren_level == 0
+                    && ctx.bracket_level == 0
+                {
+                    D::Newline
+                } else {
+                    D::Space
+                }
+            }
+
+            (_, T::Keyword(Keyword::Fn | Keyword::Var | Keyword::Const))
+                if (ctx.template_level == 0 && ctx.paren_level == 0 && ctx.bracket_level == 0) =>
+            {
+                match ctx.line_has_attributes
+                    && style.attribute_placement == AttributePlacement::SameLine
+                {
+                    true => D::Space,
+                    false => D::Newline,
+                }
+            }
+
+            (T::Syntax("@"), _) => D::None,
+            (_, T::Syntax(";")) => D::None,
+            (_, T::Syntax(",")) => D::None,
+            (T::Ident(_) | T::Syntax("]"), T::Syntax("++" | "--")) => D::None,
+            (T::Ident(_), T::Syntax(":")) => D::None,
+            (T::Ident(_) | T::TemplateArgsEnd, T::Syntax("(")) => D::None,
+            (T::Trivia(_), _) => D::Newline,
+            (
+                T::Keyword(_)
+                | T::Boolean(_)
+                | T::Ident(_)
+                | T::Integer(_)
+                | T::Float(_)
+                | T::Syntax(_)
+                | T::TemplateArgsEnd
+                | T::Error(_),
+                _,
+            ) => D::Space,
+        };
+
+        let delimiter = match (token, next_token) {
+            // Comments following code on the same line stay on that line. The line break they
+            // displace is written after them.
+            (_, T::Trivia(comment)) if !whitespace.contains('\n') => {
+                if matches!(delimiter, D::Newline | D::DoubleNewline) {
+                    ctx.displaced_delimiter = Some(delimiter);
+                }
+                comment_spacing(comment, whitespace)
+            }
+            // Block comments followed by code on the same line. Binary operators add their own
+            // space.
+            (T::Trivia(comment), _) if comment.starts_with("/*") && !whitespace.contains('\n') => {
+                ctx.displaced_delimiter = None;
+                match binary_operator(ctx.prev_token, next_token) {
+                    Some(_) => D::None,
+                    None => comment_spacing(comment, whitespace),
+                }
+            }
+            // Anything after a comment ending its line starts a new line
+            (T::Trivia(_), _) => match (ctx.displaced_delimiter.take(), delimiter) {
+                (Some(D::DoubleNewline), _) | (_, D::DoubleNewline) => D::DoubleNewline,
+                _ => D::Newline,
+            },
+            _ => delimiter,
+        };
+
+        match (token, &delimiter) {
+            (T::Syntax("@"), _) => ctx.line_has_attributes = true,
+            (_, D::Newline | D::DoubleNewline) => ctx.line_has_attributes = false,
+            _ => {}
+        }
+
+        builder.token(ctx.prev_token, token, &src_content);
+
+        match ctx.indent_level.cmp(&indent_level) {
+            std::cmp::Ordering::Greater => builder.open_block(),
+            std::cmp::Ordering::Less => builder.close_block(),
+            std::cmp::Ordering::Equal => {}
+        }
+
+        if matches!(token, T::Error(_)) || matches!(next_token, T::Error(_)) {
+            builder.verbatim(whitespace);
+        } else {
+            builder.delimiter(&delimiter, has_explicit_newline, token, next_token);
+        }
+
+        if !matches!(token, T::Trivia(_)) {
+            ctx.prev_token = token;
+        }
+    }
+
+    let (last_token, last_span) = tokens.last()?;
+    let last_content = token_text(code, last_token, last_span);
+    builder.token(ctx.prev_token, last_token, &last_content);
+
+    let layout = Layout {
+        max_width: style.max_width.max(1),
+        indent_str: &ctx.indent_str,
+        indent_width: options.tab_size as usize,
+    };
+    let mut formatted = builder.render(&layout);
+
+    // Handle trailing whitespace
+    let trailing_whitespace = &code[content_end(code, last_span)..];
+    let mut trailing_lines = trailing_whitespace.chars().filter(|it| *it == '\n').count();
+
+    if matches!(options.trim_final_newlines, Some(true)) {
+        trailing_lines = 0;
+    }
+
+    // Applied after trimming, so the file ends with exactly one newline when both are set
+    if matches!(options.insert_final_newline, Some(true)) {
+        trailing_lines = 1;
+    }
+
+    for _ in 0..trailing_lines {
+        formatted.push('\n');
+    }
+
+    Some(Formatted {
+        text: formatted,
+        skipped,
+    })
+}
+
+// The end of a token without its trailing whitespace
+fn content_end(code: &str, span: &Range<usize>) -> usize {
+    span.start + code[span.clone()].trim_end().len()
+}
+
+// The trimmed text of a token, with the continuation lines of block comments keeping their
+// indentation relative to the line the comment starts on, rather than to the start of the line
+fn token_text<'a>(code: &'a str, token: &Token, span: &Range<usize>) -> Cow<'a, str> {
+    let text = code[span.clone()].trim();
+    if !matches!(token, Token::Trivia(_)) || !text.contains('\n') {
+        return Cow::Borrowed(text);
+    }
+
+    let line_start = code[..span.start].rfind('\n').map_or(0, |index| index + 1);
+    let indent = code[line_start..span.start]
+        .chars()
+        .take_while(|char| char.is_whitespace())
+        .count();
+
+    let mut lines = text.lines();
+    let mut result = lines.next().unwrap_or_default().to_owned();
+    for line in lines {
+        let mut line = line;
+        for _ in 0..indent {
+            match line.strip_prefix(char::is_whitespace) {
+                Some(rest) => line = rest,
+                None => break,
+            }
+        }
+        result.push('\n');
+        result += line;
+    }
+    Cow::Owned(result)
+}
+
+// The space between a comment and code on the same line. Line comments are always separated by a
+// space, and block comments by a space when they had one.
+fn comment_spacing(comment: &str, whitespace: &str) -> Delimiter {
+    match whitespace.is_empty() && comment.starts_with("/*") {
+        true => Delimiter::None,
+        false => Delimiter::Space,
+    }
+}
+
+// Whether an operator following `token` is a binary operator, rather than a unary one like `-x`.
+// Text which could not be lexed is taken to be an operand.
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Ident(_)
+            | Token::Error(_)
+            | Token::Integer(_)
+            | Token::Float(_)
+            | Token::Boolean(_)
+            | Token::Syntax(")" | "]")
+    )
+}
+
+struct ASTContext<'a> {
+    indent_level: usize,
+    indent_str: String,
+    paren_level: usize,
+    bracket_level: usize,
+    brace_level: usize,
+    template_level: usize,
+    // Whether attributes were written since the last line break
+    line_has_attributes: bool,
+    // Used for differentiating between binary and unary operators, ignoring comments
+    prev_token: &'a Token<'a>,
+    // The line break before a comment which was kept on the line of the code before it
+    displaced_delimiter: Option<Delimiter>,
+}
+
+impl ASTContext<'_> {
+    fn new(options: &FormattingOptions) -> Self {
+        let indent_str = if options.insert_spaces {
+            " ".repeat(options.tab_size as usize)
+        } else {
+            "\t".to_string()
+        };
+        Self {
+            indent_level: 0,
+            indent_str,
+            paren_level: 0,
+            bracket_level: 0,
+            brace_level: 0,
+            template_level: 0,
+            line_has_attributes: false,
+            prev_token: &Token::Syntax(""),
+            displaced_delimiter: None,
+        }
+    }
+
+    fn handle_brackets(&mut self, tokens: (&Token<'_>, &Token<'_>)) {
+        match tokens {
+            (Token::Syntax("{"), _) => {
+                self.brace_level = self.brace_level.saturating_add(1);
+            }
+            (Token::Syntax("}"), _) => {
+                self.brace_level = self.brace_level.saturating_sub(1);
+            }
+
+            (Token::Syntax("("), _) => {
+                self.paren_level = self.paren_level.saturating_add(1);
+            }
+            (Token::Syntax(")"), _) => {
+                self.paren_level = self.paren_level.saturating_sub(1);
+            }
+
+            (Token::Syntax("["), _) => {
+                self.bracket_level = self.bracket_level.saturating_add(1);
+            }
+            (Token::Syntax("]"), _) => {
+                self.bracket_level = self.bracket_level.saturating_sub(1);
+            }
+
+            (Token::TemplateArgsStart, _) => {
+                self.template_level = self.template_level.saturating_add(1);
+            }
+            (Token::TemplateArgsEnd, _) => {
+                self.template_level = self.template_level.saturating_sub(1);
+            }
+
+            _ => {}
+        }
+    }
+
+    fn indent(&mut self) {
+        self.indent_level += 1;
+    }
+
+    fn dedent(&mut self) {
+        self.indent_level = self.indent_level.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format_with(source: &str, style: &FormatStyle) -> String {
+        let options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        };
+        pretty_print_ast(source, &options, style).unwrap()
+    }
+
+    fn format(source: &str, max_width: usize) -> String {
+        let style = FormatStyle {
+            max_width,
+            ..Default::default()
+        };
+        format_with(source, &style)
+    }
+
+    #[test]
+    pub fn wraps_long_argument_and_parameter_lists() {
+        let source = "fn f(@builtin(position) position: vec4f, @location(0) uv: vec2f) -> vec4f {
+return textureSample(texture, texture_sampler, uv);
+}";
+
+        assert_eq!(
+            format(source, 48),
+            "fn f(
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f
+) -> vec4f {
+    return textureSample(
+        texture,
+        texture_sampler,
+        uv
+    );
+}"
+        );
+        assert_eq!(format(&format(source, 48), 48), format(source, 48));
+    }
+
+    #[test]
+    pub fn wraps_binary_expressions_by_precedence() {
+        let source = "fn f() {
+let x = first_operand * second_operand + third_operand && is_visible;
+}";
+
+        assert_eq!(
+            format(source, 60),
+            "fn f() {
+    let x = first_operand * second_operand + third_operand
+        && is_visible;
+}"
+        );
+        assert_eq!(
+            format(source, 44),
+            "fn f() {
+    let x = first_operand * second_operand
+        + third_operand
+        && is_visible;
+}"
+        );
+    }
+
+    #[test]
+    pub fn wraps_attribute_lists() {
+        let source = "struct A {
+@builtin(position) @invariant clip_position: vec4<f32>,
+}";
+
+        assert_eq!(
+            format(source, 40),
+            "struct A {
+    @builtin(position)
+    @invariant
+    clip_position: vec4<f32>,
+}"
+        );
+    }
+
+    #[test]
+    pub fn style_options() {
+        let style = FormatStyle {
+            brace_style: BraceStyle::NextLine,
+            attribute_placement: AttributePlacement::SameLine,
+            trailing_commas: style::TrailingCommas::Vertical,
+            template_spacing: true,
+            blank_lines_between_items: 0,
+            align_struct_members: true,
+            ..Default::default()
+        };
+        let source = "struct A {
+position: vec4<f32>,
+uv: vec2<f32>
+}
+@group(0) @binding(0)
+var<uniform> a: A;
+fn f() { g(1, 2,); }
+";
+
+        assert_eq!(
+            format_with(source, &style),
+            "struct A
+{
+    position: vec4< f32 >,
+    uv:       vec2< f32 >,
+}
+@group(0) @binding(0) var< uniform > a: A;
+fn f()
+{
+    g(1, 2);
+}
+"
+        );
+    }
+
+    #[test]
+    pub fn formats_around_lexing_errors() {
+        let options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        };
+        let source = "fn f() {\nlet class=1;\nlet b=a  $$ 2;\n}\n/* unterminated\n";
+        let formatted = super::format(source, &options, &FormatStyle::default()).unwrap();
+
+        assert_eq!(
+            formatted.text,
+            // Whitespace next to the skipped text is kept too
+            "fn f() {\n    let class= 1;\n    let b = a  $$ 2;\n}\n/* unterminated\n"
+        );
+        let skipped: Vec<_> = formatted
+            .skipped
+            .iter()
+            .map(|range| &source[range.clone()])
+            .collect();
+        assert_eq!(skipped, ["class", "$$", "/* unterminated\n"]);
+    }
+
+    #[test]
+    pub fn final_newlines() {
+        let format = |source: &str, insert, trim| {
+            let options = FormattingOptions {
+                tab_size: 4,
+                insert_spaces: true,
+                insert_final_newline: Some(insert),
+                trim_final_newlines: Some(trim),
+                ..Default::default()
+            };
+            super::format(source, &options, &FormatStyle::default())
+                .unwrap()
+                .text
+        };
+
+        assert_eq!(format("const a = 1;", true, false), "const a = 1;\n");
+        assert_eq!(format("const a = 1;\n\n\n", true, false), "const a = 1;\n");
+        assert_eq!(format("const a = 1;\n\n", true, true), "const a = 1;\n");
+        assert_eq!(format("const a = 1;\n\n", false, true), "const a = 1;");
+        assert_eq!(format("const a = 1;\n\n", false, false), "const a = 1;\n\n");
+    }
+
+    #[test]
+    pub fn unary_operators_have_no_space() {
+        let source = "fn f(p: ptr<function, f32>) {\n*p = - *p-1.0;\nreturn - a[0] * * p & b;\n}\n";
+
+        assert_eq!(
+            format(source, 100),
+            "fn f(p: ptr<function, f32>) {\n    *p = -*p - 1.0;\n    return -a[0] * *p & b;\n}\n"
+        );
+    }
+
+    #[test]
+    pub fn keeps_comments_in_place() {
+        let source = "struct A {
+a: f32, // first
+/// doc
+b: u32 // last
+} // end
+fn f() { // open
+let x = g(a, // arg
+b) ;
+let y = a /* c */+b;
+        /* block
+           continued
+      end */
+return x // returned
+;
+}
+";
+
+        assert_eq!(
+            format(source, 100),
+            "struct A {
+    a: f32, // first
+    /// doc
+    b: u32 // last
+} // end
+
+fn f() { // open
+    let x = g(
+        a, // arg
+        b
+    );
+    let y = a /* c */ + b;
+    /* block
+       continued
+    end */
+    return x // returned
+    ;
+}
+"
+        );
+
+        let style = FormatStyle {
+            trailing_commas: style::TrailingCommas::Vertical,
+            align_struct_members: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with("struct A {\na: f32, // a\nlong: u32 // b\n}\n", &style),
+            "struct A {\n    a:    f32, // a\n    long: u32, // b\n}\n"
+        );
+    }
+}
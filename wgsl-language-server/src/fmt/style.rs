@@ -0,0 +1,63 @@
+// Style options of the formatter, which are not part of the editor's `FormattingOptions`. They are
+// read from the `[format]` table of the project configuration, or from the closest `.wgslfmt.toml`.
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormatStyle {
+    // Lines longer than this are wrapped where possible
+    pub max_width: usize,
+    pub brace_style: BraceStyle,
+    // Where the attributes of module scope declarations go, like `@group(0) @binding(0)`
+    pub attribute_placement: AttributePlacement,
+    // Trailing commas of struct members, and of argument and parameter lists
+    pub trailing_commas: TrailingCommas,
+    // Spaces inside template lists, as in `array< f32, 4 >`
+    pub template_spacing: bool,
+    // Blank lines after module scope functions and structs
+    pub blank_lines_between_items: usize,
+    // Aligns the types of struct members to the same column
+    pub align_struct_members: bool,
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        Self {
+            max_width: 100,
+            brace_style: BraceStyle::default(),
+            attribute_placement: AttributePlacement::default(),
+            trailing_commas: TrailingCommas::default(),
+            template_spacing: false,
+            blank_lines_between_items: 1,
+            align_struct_members: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BraceStyle {
+    #[default]
+    SameLine,
+    NextLine,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AttributePlacement {
+    #[default]
+    OwnLine,
+    SameLine,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrailingCommas {
+    // Keeps trailing commas as they are written
+    #[default]
+    Preserve,
+    // Adds trailing commas to lists spanning multiple lines, and removes them from single lines
+    Vertical,
+    Never,
+}
@@ -0,0 +1,78 @@
+// Functions and entry points, which naga stores separately
+
+use std::ops::Range;
+
+use naga::{EntryPoint, Function, Handle, Module};
+
+use crate::{
+    lexer::Keyword,
+    parser::{find_declaration, matching_bracket_index},
+};
+
+#[derive(Clone, Copy)]
+pub enum FunctionItem<'a> {
+    Function(Handle<Function>, &'a Function),
+    EntryPoint(usize, &'a EntryPoint),
+}
+
+impl<'a> FunctionItem<'a> {
+    pub fn function(&self) -> &'a Function {
+        match self {
+            FunctionItem::Function(_, function) => function,
+            FunctionItem::EntryPoint(_, entry_point) => &entry_point.function,
+        }
+    }
+
+    /// The range from the start of the declaration to the end of the body.
+    pub fn range(&self, module: &Module, content: &str) -> Option<Range<usize>> {
+        match self {
+            FunctionItem::Function(handle, _) => {
+                let start = module.functions.get_span(*handle).to_range()?.start;
+                let end = matching_bracket_index(content, start).map_or(start, |close| close + 1);
+                Some(start..end)
+            }
+            // Entry points have no spans
+            FunctionItem::EntryPoint(_, entry_point) => {
+                find_declaration(content, Keyword::Fn, &entry_point.name).map(|(range, _)| range)
+            }
+        }
+    }
+}
+
+pub fn function_items(module: &Module) -> impl Iterator<Item = FunctionItem<'_>> {
+    let functions = module
+        .functions
+        .iter()
+        .map(|(handle, function)| FunctionItem::Function(handle, function));
+
+    let entry_points = module
+        .entry_points
+        .iter()
+        .enumerate()
+        .map(|(index, entry_point)| FunctionItem::EntryPoint(index, entry_point));
+
+    functions.chain(entry_points)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn entry_point_range_includes_signature() {
+        let src = "fn helper() -> f32 { return 1.0; }\n\n@fragment\nfn main() -> @location(0) vec4<f32> {\n    return vec4<f32>(helper());\n}\n";
+        let module = naga::front::wgsl::parse_str(src).unwrap();
+
+        let ranges: Vec<_> = function_items(&module)
+            .map(|item| &src[item.range(&module, src).unwrap()])
+            .collect();
+
+        assert_eq!(
+            ranges,
+            vec![
+                "fn helper() -> f32 { return 1.0; }",
+                "fn main() -> @location(0) vec4<f32> {\n    return vec4<f32>(helper());\n}"
+            ]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// Fuzzy matching used for filtering symbols by a user provided query
+
+/// Matches `query` as a case-insensitive subsequence of `candidate`.
+/// Returns a score, where higher is a better match, or `None` if the query does not match.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+
+    for (index, c) in candidate.chars().enumerate() {
+        let Some(&expected) = query_chars.peek() else {
+            break;
+        };
+
+        if c.to_lowercase().eq(std::iter::once(expected)) {
+            query_chars.next();
+            score += 1;
+
+            if index == 0 {
+                score += 8;
+            } else if is_word_start(prev, c) {
+                score += 4;
+            }
+
+            if prev_matched {
+                score += 2;
+            }
+
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+
+        prev = Some(c);
+    }
+
+    if query_chars.peek().is_some() {
+        return None;
+    }
+
+    if query.chars().count() == candidate.chars().count() {
+        score += 16;
+    }
+
+    Some(score)
+}
+
+fn is_word_start(prev: Option<char>, c: char) -> bool {
+    match prev {
+        Some('_') => true,
+        Some(prev) => prev.is_lowercase() && c.is_uppercase(),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn empty_query_matches_everything() {
+        assert!(fuzzy_match("", "anything").is_some());
+    }
+
+    #[test]
+    pub fn subsequence_matches() {
+        assert!(fuzzy_match("vmn", "vs_main").is_some());
+        assert!(fuzzy_match("VSM", "vs_main").is_some());
+    }
+
+    #[test]
+    pub fn non_subsequence_does_not_match() {
+        assert!(fuzzy_match("nmv", "vs_main").is_none());
+        assert!(fuzzy_match("vs_main_", "vs_main").is_none());
+    }
+
+    #[test]
+    pub fn word_starts_score_higher() {
+        let word_start = fuzzy_match("cl", "computeLighting").unwrap();
+        let inner = fuzzy_match("cl", "cycle").unwrap();
+        assert!(word_start > inner);
+    }
+
+    #[test]
+    pub fn exact_match_scores_highest() {
+        let exact = fuzzy_match("light", "light").unwrap();
+        let prefix = fuzzy_match("light", "lighting").unwrap();
+        assert!(exact > prefix);
+    }
+}
@@ -0,0 +1,163 @@
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+use naga::{Module, TypeInner, proc::Layouter};
+
+use crate::{
+    document_tracker::TrackedDocument,
+    layout::{StructLayout, layouter},
+    parser::{find_struct_members, ident_at},
+    range_tools::{range_to_span, span_to_lsp_range, string_offset},
+};
+
+pub trait HoverProvider {
+    fn get_hover(&self, position: &Position) -> Option<Hover>;
+}
+
+impl HoverProvider for TrackedDocument {
+    fn get_hover(&self, position: &Position) -> Option<Hover> {
+        let Some(Ok((module, _))) = &self.compilation_result else {
+            return None;
+        };
+
+        let offset = string_offset(&self.content, position);
+        let (ident, range) = ident_at(&self.content, offset)?;
+        let layouter = layouter(module)?;
+
+        let structs = module
+            .types
+            .iter()
+            .filter(|(_, ty)| matches!(ty.inner, TypeInner::Struct { .. }));
+
+        for (handle, ty) in structs {
+            let layout = StructLayout::new(module, &layouter, handle)?;
+
+            if ty.name.as_deref() == Some(ident) {
+                let value = struct_layout_markdown(module, &layouter, &layout);
+                return Some(markdown_hover(value, range, &self.content));
+            }
+
+            let Some(body) = module.types.get_span(handle).to_range() else {
+                continue;
+            };
+
+            if !body.contains(&offset) {
+                continue;
+            }
+
+            let is_member_name = find_struct_members(&self.content, body)
+                .iter()
+                .any(|(_, name)| *name == range);
+
+            if let Some(index) = layout.members.iter().position(|it| it.name == ident)
+                && is_member_name
+            {
+                let value = member_layout_markdown(module, &layouter, &layout, index);
+                return Some(markdown_hover(value, range, &self.content));
+            }
+        }
+
+        None
+    }
+}
+
+fn markdown_hover(value: String, range: std::ops::Range<usize>, content: &str) -> Hover {
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(span_to_lsp_range(range_to_span(range), content)),
+    }
+}
+
+fn uniform_summary(
+    module: &Module,
+    layouter: &Layouter,
+    layout: &StructLayout,
+    member: Option<usize>,
+) -> String {
+    let violations: Vec<_> = layout
+        .uniform_violations(module, layouter)
+        .into_iter()
+        .filter(|violation| member.is_none() || violation.member() == member)
+        .collect();
+
+    if violations.is_empty() {
+        return "Compatible with the `uniform` address space".to_owned();
+    }
+
+    violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "⚠ {}  \n{}",
+                violation.message(Some(layout), "uniform"),
+                violation.suggestion(Some(layout))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn struct_layout_markdown(module: &Module, layouter: &Layouter, layout: &StructLayout) -> String {
+    let mut rows = vec![
+        "| Offset | Size | Align | Member |".to_owned(),
+        "| ---: | ---: | ---: | :--- |".to_owned(),
+    ];
+
+    for member in &layout.members {
+        rows.push(format!(
+            "| {} | {} | {} | `{}: {}` |",
+            member.offset, member.size, member.alignment, member.name, member.ty_name
+        ));
+
+        if member.padding > 0 {
+            rows.push(format!(
+                "| {} | {} | | *padding* |",
+                member.offset + member.size,
+                member.padding
+            ));
+        }
+    }
+
+    [
+        format!("```wgsl\nstruct {}\n```", layout.name),
+        format!(
+            "**storage**: size {}, align {}  \n**uniform**: size {}, align {}",
+            layout.size,
+            layout.alignment,
+            layout.size,
+            layout.uniform_alignment()
+        ),
+        rows.join("\n"),
+        uniform_summary(module, layouter, layout, None),
+    ]
+    .join("\n\n")
+}
+
+fn member_layout_markdown(
+    module: &Module,
+    layouter: &Layouter,
+    layout: &StructLayout,
+    index: usize,
+) -> String {
+    let member = &layout.members[index];
+
+    let mut details = format!(
+        "offset {}, size {}, align {}",
+        member.offset, member.size, member.alignment
+    );
+
+    if member.padding > 0 {
+        details += &format!(", followed by {} bytes of padding", member.padding);
+    }
+
+    [
+        format!(
+            "```wgsl\n{}.{}: {}\n```",
+            layout.name, member.name, member.ty_name
+        ),
+        details,
+        uniform_summary(module, layouter, layout, Some(index)),
+    ]
+    .join("\n\n")
+}
@@ -0,0 +1,292 @@
+// The interface of an entry point: the values passed between pipeline stages, and the
+// resources it uses
+
+use std::ops::Range;
+
+use naga::{
+    Binding, BuiltIn, EntryPoint, GlobalVariable, Handle, Interpolation, Module, Sampling,
+    ShaderStage, Type, TypeInner, valid::FunctionInfo,
+};
+
+use crate::{
+    lexer::Keyword,
+    parser::{find_declaration, find_ident, find_struct_members},
+};
+
+pub struct InterfaceVariable {
+    pub name: String,
+    pub ty: Handle<Type>,
+    pub binding: Binding,
+    // The struct declaring this variable as a member, if any
+    pub parent: Option<Handle<Type>>,
+}
+
+impl InterfaceVariable {
+    pub fn label(&self, module: &Module) -> String {
+        format!(
+            "{} {}: {}",
+            binding_str(&self.binding),
+            self.name,
+            self.ty.to_wgsl(&module.to_ctx())
+        )
+    }
+}
+
+// Flattens struct-typed arguments and results into their members
+fn interface_variables(
+    module: &Module,
+    name: Option<&String>,
+    ty: Handle<Type>,
+    binding: Option<&Binding>,
+    parent: Option<Handle<Type>>,
+) -> Vec<InterfaceVariable> {
+    if let Some(binding) = binding {
+        return vec![InterfaceVariable {
+            name: name.cloned().unwrap_or_default(),
+            ty,
+            binding: binding.clone(),
+            parent,
+        }];
+    }
+
+    let TypeInner::Struct { members, .. } = &module.types[ty].inner else {
+        return vec![];
+    };
+
+    members
+        .iter()
+        .flat_map(|member| {
+            interface_variables(
+                module,
+                member.name.as_ref(),
+                member.ty,
+                member.binding.as_ref(),
+                Some(ty),
+            )
+        })
+        .collect()
+}
+
+pub fn entry_point_inputs(module: &Module, entry_point: &EntryPoint) -> Vec<InterfaceVariable> {
+    let mut inputs: Vec<_> = entry_point
+        .function
+        .arguments
+        .iter()
+        .flat_map(|arg| {
+            interface_variables(
+                module,
+                arg.name.as_ref(),
+                arg.ty,
+                arg.binding.as_ref(),
+                None,
+            )
+        })
+        .collect();
+
+    // Naga applies default interpolation to vertex inputs, even though they are not interpolated
+    if entry_point.stage == ShaderStage::Vertex {
+        for input in &mut inputs {
+            if let Binding::Location {
+                interpolation,
+                sampling,
+                ..
+            } = &mut input.binding
+            {
+                *interpolation = None;
+                *sampling = None;
+            }
+        }
+    }
+
+    inputs
+}
+
+pub fn entry_point_outputs(module: &Module, entry_point: &EntryPoint) -> Vec<InterfaceVariable> {
+    let Some(result) = &entry_point.function.result else {
+        return vec![];
+    };
+
+    interface_variables(module, None, result.ty, result.binding.as_ref(), None)
+}
+
+/// The globals with a `@group` and `@binding` used by an entry point, or any function it calls.
+pub fn entry_point_resources(module: &Module, info: &FunctionInfo) -> Vec<Handle<GlobalVariable>> {
+    module
+        .global_variables
+        .iter()
+        .filter(|(handle, global)| global.binding.is_some() && !info[*handle].is_empty())
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+/// Finds the declaration of an interface variable, falling back to the name of the entry point.
+pub fn interface_variable_range(
+    module: &Module,
+    content: &str,
+    entry_point: &EntryPoint,
+    variable: &InterfaceVariable,
+) -> Option<Range<usize>> {
+    if let Some(parent) = variable.parent {
+        let body = module.types.get_span(parent).to_range()?;
+        return find_struct_members(content, body)
+            .into_iter()
+            .find(|(_, name)| content[name.clone()] == variable.name)
+            .map(|(_, name)| name);
+    }
+
+    let (declaration, name) = find_declaration(content, Keyword::Fn, &entry_point.name)?;
+
+    if variable.name.is_empty() {
+        return Some(name);
+    }
+
+    find_ident(content, name.end..declaration.end, &variable.name).or(Some(name))
+}
+
+pub fn shader_stage_str(stage: ShaderStage) -> &'static str {
+    match stage {
+        ShaderStage::Vertex => "vertex",
+        ShaderStage::Fragment => "fragment",
+        ShaderStage::Compute => "compute",
+    }
+}
+
+pub fn binding_str(binding: &Binding) -> String {
+    match *binding {
+        Binding::BuiltIn(builtin) => format!("@builtin({})", builtin_str(builtin)),
+        Binding::Location {
+            location,
+            second_blend_source,
+            interpolation,
+            sampling,
+        } => {
+            let mut res = format!("@location({location})");
+
+            if second_blend_source {
+                res += " @blend_src(1)";
+            }
+
+            if let Some(interpolate) = interpolate_str(interpolation, sampling) {
+                res += " ";
+                res += &interpolate;
+            }
+
+            res
+        }
+    }
+}
+
+pub fn interpolate_str(
+    interpolation: Option<Interpolation>,
+    sampling: Option<Sampling>,
+) -> Option<String> {
+    match (interpolation?, sampling) {
+        (interpolation, Some(sampling)) => Some(format!(
+            "@interpolate({}, {})",
+            interpolation_str(interpolation),
+            sampling_str(sampling)
+        )),
+        (interpolation, None) => Some(format!(
+            "@interpolate({})",
+            interpolation_str(interpolation)
+        )),
+    }
+}
+
+pub fn builtin_str(builtin: BuiltIn) -> &'static str {
+    match builtin {
+        BuiltIn::Position { .. } => "position",
+        BuiltIn::ViewIndex => "view_index",
+        BuiltIn::BaseInstance => "base_instance",
+        BuiltIn::BaseVertex => "base_vertex",
+        BuiltIn::ClipDistance => "clip_distances",
+        BuiltIn::CullDistance => "cull_distance",
+        BuiltIn::InstanceIndex => "instance_index",
+        BuiltIn::PointSize => "point_size",
+        BuiltIn::VertexIndex => "vertex_index",
+        BuiltIn::DrawID => "draw_id",
+        BuiltIn::FragDepth => "frag_depth",
+        BuiltIn::PointCoord => "point_coord",
+        BuiltIn::FrontFacing => "front_facing",
+        BuiltIn::PrimitiveIndex => "primitive_index",
+        BuiltIn::SampleIndex => "sample_index",
+        BuiltIn::SampleMask => "sample_mask",
+        BuiltIn::GlobalInvocationId => "global_invocation_id",
+        BuiltIn::LocalInvocationId => "local_invocation_id",
+        BuiltIn::LocalInvocationIndex => "local_invocation_index",
+        BuiltIn::WorkGroupId => "workgroup_id",
+        BuiltIn::WorkGroupSize => "workgroup_size",
+        BuiltIn::NumWorkGroups => "num_workgroups",
+        BuiltIn::NumSubgroups => "num_subgroups",
+        BuiltIn::SubgroupId => "subgroup_id",
+        BuiltIn::SubgroupSize => "subgroup_size",
+        BuiltIn::SubgroupInvocationId => "subgroup_invocation_id",
+    }
+}
+
+pub fn interpolation_str(interpolation: Interpolation) -> &'static str {
+    match interpolation {
+        Interpolation::Perspective => "perspective",
+        Interpolation::Linear => "linear",
+        Interpolation::Flat => "flat",
+    }
+}
+
+pub fn sampling_str(sampling: Sampling) -> &'static str {
+    match sampling {
+        Sampling::Center => "center",
+        Sampling::Centroid => "centroid",
+        Sampling::Sample => "sample",
+        Sampling::First => "first",
+        Sampling::Either => "either",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn struct_interfaces_are_flattened() {
+        let module = naga::front::wgsl::parse_str(
+            "
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) id: u32,
+}
+
+@vertex fn vs_main(@builtin(vertex_index) index: u32, @location(0) pos: vec3<f32>) -> VertexOutput {
+    return VertexOutput(vec4<f32>(pos, 1.0), vec2<f32>(0.0), index);
+}
+",
+        )
+        .unwrap();
+
+        let entry_point = &module.entry_points[0];
+        let inputs: Vec<_> = entry_point_inputs(&module, entry_point)
+            .iter()
+            .map(|it| it.label(&module))
+            .collect();
+        let outputs: Vec<_> = entry_point_outputs(&module, entry_point)
+            .iter()
+            .map(|it| it.label(&module))
+            .collect();
+
+        assert_eq!(
+            inputs,
+            vec![
+                "@builtin(vertex_index) index: u32",
+                "@location(0) pos: vec3<f32>"
+            ]
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                "@builtin(position) position: vec4<f32>",
+                "@location(0) @interpolate(perspective, center) uv: vec2<f32>",
+                "@location(1) @interpolate(flat) id: u32",
+            ]
+        );
+    }
+}
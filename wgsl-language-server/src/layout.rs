@@ -0,0 +1,445 @@
+// Memory layout of host-shareable structs, following WGSL §14.4 "Memory Layout"
+// https://www.w3.org/TR/WGSL/#memory-layouts
+
+use naga::{ArraySize, Handle, Module, Type, TypeInner, proc::Layouter, valid::Disalignment};
+
+// Minimum alignment of structs and arrays, and required array stride, in the uniform address space
+const UNIFORM_ALIGNMENT: u32 = 16;
+
+pub fn round_up(alignment: u32, value: u32) -> u32 {
+    value.div_ceil(alignment) * alignment
+}
+
+pub fn layouter(module: &Module) -> Option<Layouter> {
+    let mut layouter = Layouter::default();
+    layouter.update(module.to_ctx()).ok()?;
+    Some(layouter)
+}
+
+pub struct MemberLayout {
+    pub name: String,
+    pub ty: Handle<Type>,
+    pub ty_name: String,
+    pub offset: u32,
+    pub size: u32,
+    pub alignment: u32,
+    // Implicit padding between the end of this member and the start of the next member,
+    // or the end of the struct
+    pub padding: u32,
+}
+
+pub struct StructLayout {
+    pub name: String,
+    pub size: u32,
+    pub alignment: u32,
+    pub members: Vec<MemberLayout>,
+}
+
+pub enum LayoutViolation {
+    // The stride of an array is not a multiple of the required alignment
+    ArrayStride {
+        member: Option<usize>,
+        stride: u32,
+        alignment: u32,
+    },
+    // A member's offset is not a multiple of the required alignment of its type
+    MemberOffset {
+        member: usize,
+        offset: u32,
+        alignment: u32,
+    },
+    // A member following a struct-typed member does not leave enough space for it
+    MemberOffsetAfterStruct {
+        member: usize,
+        offset: u32,
+        expected: u32,
+    },
+    // Runtime-sized arrays may only be used in the storage address space
+    UnsizedMember {
+        member: usize,
+    },
+}
+
+impl StructLayout {
+    pub fn new(module: &Module, layouter: &Layouter, handle: Handle<Type>) -> Option<Self> {
+        let ty = &module.types[handle];
+        let TypeInner::Struct { members, span } = &ty.inner else {
+            return None;
+        };
+
+        let members = members
+            .iter()
+            .enumerate()
+            .map(|(index, member)| {
+                let layout = layouter[member.ty];
+                let end = members
+                    .get(index + 1)
+                    .map(|next| next.offset)
+                    .unwrap_or(*span);
+
+                MemberLayout {
+                    name: member.name.clone().unwrap_or_default(),
+                    ty: member.ty,
+                    ty_name: member.ty.to_wgsl(&module.to_ctx()),
+                    offset: member.offset,
+                    size: layout.size,
+                    alignment: layout.alignment.round_up(1),
+                    padding: end.saturating_sub(member.offset + layout.size),
+                }
+            })
+            .collect();
+
+        Some(Self {
+            name: ty.name.clone().unwrap_or_default(),
+            size: *span,
+            alignment: layouter[handle].alignment.round_up(1),
+            members,
+        })
+    }
+
+    pub fn uniform_alignment(&self) -> u32 {
+        self.alignment.max(UNIFORM_ALIGNMENT)
+    }
+
+    // Checks the additional constraints placed on structs used in the uniform address space
+    pub fn uniform_violations(&self, module: &Module, layouter: &Layouter) -> Vec<LayoutViolation> {
+        let mut violations = vec![];
+        let mut previous_struct: Option<&MemberLayout> = None;
+
+        for (index, member) in self.members.iter().enumerate() {
+            match module.types[member.ty].inner {
+                TypeInner::Array {
+                    size: ArraySize::Dynamic,
+                    ..
+                } => violations.push(LayoutViolation::UnsizedMember { member: index }),
+                TypeInner::Array { stride, .. } if stride % UNIFORM_ALIGNMENT != 0 => violations
+                    .push(LayoutViolation::ArrayStride {
+                        member: Some(index),
+                        stride,
+                        alignment: UNIFORM_ALIGNMENT,
+                    }),
+                _ => {}
+            }
+
+            let alignment = uniform_alignment(module, layouter, member.ty);
+            if member.offset % alignment != 0 {
+                violations.push(LayoutViolation::MemberOffset {
+                    member: index,
+                    offset: member.offset,
+                    alignment,
+                });
+            }
+
+            if let Some(previous) = previous_struct {
+                let expected = previous.offset + round_up(UNIFORM_ALIGNMENT, previous.size);
+                if member.offset < expected {
+                    violations.push(LayoutViolation::MemberOffsetAfterStruct {
+                        member: index,
+                        offset: member.offset,
+                        expected,
+                    });
+                }
+            }
+
+            previous_struct =
+                matches!(module.types[member.ty].inner, TypeInner::Struct { .. }).then_some(member);
+        }
+
+        violations
+    }
+}
+
+// The alignment required of a type when used in the uniform address space
+fn uniform_alignment(module: &Module, layouter: &Layouter, ty: Handle<Type>) -> u32 {
+    let alignment = layouter[ty].alignment.round_up(1);
+    match module.types[ty].inner {
+        TypeInner::Struct { .. } | TypeInner::Array { .. } => alignment.max(UNIFORM_ALIGNMENT),
+        _ => alignment,
+    }
+}
+
+// Finds the struct member of type `ty` in the types reachable from `root`
+fn find_member_of_type(
+    module: &Module,
+    root: Handle<Type>,
+    ty: Handle<Type>,
+) -> Option<(Handle<Type>, usize)> {
+    match &module.types[root].inner {
+        TypeInner::Struct { members, .. } => {
+            members
+                .iter()
+                .enumerate()
+                .find_map(|(index, member)| match member.ty == ty {
+                    true => Some((root, index)),
+                    false => find_member_of_type(module, member.ty, ty),
+                })
+        }
+        TypeInner::Array { base, .. } | TypeInner::BindingArray { base, .. } => {
+            find_member_of_type(module, *base, ty)
+        }
+        _ => None,
+    }
+}
+
+impl LayoutViolation {
+    // Converts a layout error reported by the validator for the type `ty`, used by a variable of
+    // type `root`. Returns the struct containing the offending member, if any, along with the
+    // violation.
+    pub fn from_disalignment(
+        module: &Module,
+        root: Handle<Type>,
+        ty: Handle<Type>,
+        disalignment: &Disalignment,
+    ) -> Option<(Option<Handle<Type>>, Self)> {
+        let violation = match *disalignment {
+            Disalignment::ArrayStride { stride, alignment } => {
+                // The validator reports the array type itself, so find the member using it
+                let containing_struct = find_member_of_type(module, root, ty);
+
+                let violation = LayoutViolation::ArrayStride {
+                    member: containing_struct.map(|(_, index)| index),
+                    stride,
+                    alignment: alignment.round_up(1),
+                };
+
+                return Some((containing_struct.map(|(handle, _)| handle), violation));
+            }
+            Disalignment::MemberOffset {
+                index,
+                offset,
+                alignment,
+            } => LayoutViolation::MemberOffset {
+                member: index as usize,
+                offset,
+                alignment: alignment.round_up(1),
+            },
+            Disalignment::MemberOffsetAfterStruct {
+                index,
+                offset,
+                expected,
+            } => LayoutViolation::MemberOffsetAfterStruct {
+                member: index as usize,
+                offset,
+                expected,
+            },
+            Disalignment::UnsizedMember { index } => LayoutViolation::UnsizedMember {
+                member: index as usize,
+            },
+            Disalignment::StructSpan { .. } | Disalignment::NonHostShareable => return None,
+        };
+
+        Some((Some(ty), violation))
+    }
+
+    pub fn member(&self) -> Option<usize> {
+        match *self {
+            LayoutViolation::ArrayStride { member, .. } => member,
+            LayoutViolation::MemberOffset { member, .. }
+            | LayoutViolation::MemberOffsetAfterStruct { member, .. }
+            | LayoutViolation::UnsizedMember { member } => Some(member),
+        }
+    }
+
+    pub fn message(&self, layout: Option<&StructLayout>, space: &str) -> String {
+        let member_name = |index: usize| {
+            layout
+                .and_then(|layout| layout.members.get(index))
+                .map(|member| member.name.as_str())
+                .unwrap_or_default()
+        };
+
+        match *self {
+            LayoutViolation::ArrayStride {
+                member: Some(member),
+                stride,
+                alignment,
+            } => format!(
+                "The array stride of member `{}` is {stride} bytes, but the {space} address space requires a multiple of {alignment}",
+                member_name(member)
+            ),
+            LayoutViolation::ArrayStride {
+                member: None,
+                stride,
+                alignment,
+            } => format!(
+                "The array stride is {stride} bytes, but the {space} address space requires a multiple of {alignment}"
+            ),
+            LayoutViolation::MemberOffset {
+                member,
+                offset,
+                alignment,
+            } => format!(
+                "Member `{}` is at offset {offset}, but the {space} address space requires it to be aligned to {alignment} bytes",
+                member_name(member)
+            ),
+            LayoutViolation::MemberOffsetAfterStruct {
+                member,
+                offset,
+                expected,
+            } => format!(
+                "Member `{}` is at offset {offset}, but the {space} address space requires it to start at offset {expected} or later since it follows the struct member `{}`",
+                member_name(member),
+                member_name(member.saturating_sub(1))
+            ),
+            LayoutViolation::UnsizedMember { member } => format!(
+                "Runtime-sized array `{}` can not be used in the {space} address space",
+                member_name(member)
+            ),
+        }
+    }
+
+    pub fn suggestion(&self, layout: Option<&StructLayout>) -> String {
+        let member_at = |index: usize| layout.and_then(|layout| layout.members.get(index));
+        let member_name = |index: usize| {
+            member_at(index)
+                .map(|it| it.name.as_str())
+                .unwrap_or_default()
+        };
+
+        match *self {
+            LayoutViolation::ArrayStride { alignment, .. } => format!(
+                "Use an element type whose size is a multiple of {alignment} bytes, for example by packing scalars into a `vec4`, or by wrapping the element in a struct with an `@size({alignment})` member"
+            ),
+            LayoutViolation::MemberOffset {
+                member, alignment, ..
+            } => format!(
+                "Add `@align({alignment})` to member `{}`",
+                member_name(member)
+            ),
+            LayoutViolation::MemberOffsetAfterStruct {
+                member, expected, ..
+            } => {
+                let previous = member.saturating_sub(1);
+                let previous_size = expected - member_at(previous).map(|it| it.offset).unwrap_or(0);
+                format!(
+                    "Add `@align({UNIFORM_ALIGNMENT})` to member `{}`, or `@size({previous_size})` to member `{}`",
+                    member_name(member),
+                    member_name(previous)
+                )
+            }
+            LayoutViolation::UnsizedMember { .. } => {
+                "Declare the variable in the storage address space using `var<storage>`".to_owned()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn struct_layout(src: &str, name: &str) -> (Module, Layouter, StructLayout) {
+        let module = naga::front::wgsl::parse_str(src).unwrap();
+        let layouter = layouter(&module).unwrap();
+        let (handle, _) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .unwrap();
+        let layout = StructLayout::new(&module, &layouter, handle).unwrap();
+        (module, layouter, layout)
+    }
+
+    #[test]
+    pub fn member_offsets_and_padding() {
+        let (_, _, layout) = struct_layout(
+            "struct Light { position: vec3<f32>, intensity: f32, color: vec3<f32> }",
+            "Light",
+        );
+
+        let offsets: Vec<_> = layout
+            .members
+            .iter()
+            .map(|it| (it.offset, it.size, it.alignment, it.padding))
+            .collect();
+
+        assert_eq!(
+            offsets,
+            vec![(0, 12, 16, 0), (12, 4, 4, 0), (16, 12, 16, 4)]
+        );
+        assert_eq!((layout.size, layout.alignment), (32, 16));
+    }
+
+    #[test]
+    pub fn uniform_array_stride() {
+        let (module, layouter, layout) =
+            struct_layout("struct Weights { values: array<f32, 8> }", "Weights");
+
+        let violations = layout.uniform_violations(&module, &layouter);
+        assert!(matches!(
+            violations.as_slice(),
+            [LayoutViolation::ArrayStride {
+                member: Some(0),
+                stride: 4,
+                alignment: 16
+            }]
+        ));
+    }
+
+    #[test]
+    pub fn uniform_member_after_struct() {
+        let (module, layouter, layout) = struct_layout(
+            "struct Inner { a: f32 }\nstruct Outer { inner: Inner, b: f32 }",
+            "Outer",
+        );
+
+        let violations = layout.uniform_violations(&module, &layouter);
+        assert!(matches!(
+            violations.as_slice(),
+            [LayoutViolation::MemberOffsetAfterStruct {
+                member: 1,
+                offset: 4,
+                expected: 16
+            }]
+        ));
+    }
+
+    #[test]
+    pub fn valid_uniform_struct() {
+        let (module, layouter, layout) = struct_layout(
+            "struct Camera { view: mat4x4<f32>, position: vec4<f32>, data: array<vec4<f32>, 2> }",
+            "Camera",
+        );
+
+        assert!(layout.uniform_violations(&module, &layouter).is_empty());
+    }
+
+    // The line and message of every diagnostic of `src`
+    fn diagnostics(src: &str) -> Vec<(u32, String)> {
+        let mut tracker = crate::document_tracker::DocumentTracker::new();
+        tracker.insert(lsp_types::TextDocumentItem {
+            uri: "file:///shader.wgsl".parse().unwrap(),
+            language_id: "wgsl".to_owned(),
+            version: 0,
+            text: src.to_owned(),
+        });
+
+        tracker
+            .get_diagnostics()
+            .into_iter()
+            .flat_map(|params| params.diagnostics)
+            .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message))
+            .collect()
+    }
+
+    #[test]
+    pub fn array_stride_in_the_struct_of_the_variable() {
+        let src = "struct Unused { weights: array<f32, 4> }
+struct Params { scale: f32, weights: array<f32, 4> }
+@group(0) @binding(0) var<uniform> params: Params;";
+
+        let found = diagnostics(src);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 1);
+        assert!(found[0].1.contains("member `weights`"));
+
+        // A bare array has no member to point at
+        let src = "struct Unused { weights: array<f32, 4> }
+@group(0) @binding(0) var<uniform> weights: array<f32, 4>;";
+
+        let found = diagnostics(src);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 1);
+        assert!(found[0].1.starts_with("The array stride is 4 bytes"));
+    }
+}
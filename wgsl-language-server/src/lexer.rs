@@ -1,46 +1,47 @@
 use std::ops::Range;
 
 use comment::lex_multiline_comment;
+use literal::{lex_float, lex_integer};
 use logos::Logos;
 mod comment;
+mod diagnostics;
 mod keyword;
+mod literal;
 mod template_disambiguation;
 mod test;
+pub use diagnostics::{LexDiagnostic, lex_diagnostics};
 pub use keyword::Keyword;
 use keyword::{IdentError, parse_ident};
 use template_disambiguation::insert_template_tokens;
 
-use crate::log;
-
-// TODO: Cleanup error filtering
-pub fn lex(src: &str) -> Option<Vec<(Token<'_>, Range<usize>)>> {
-    let tokens = Token::lexer(src).spanned().collect::<Vec<_>>();
-
-    if tokens.iter().any(|it| it.0.is_err()) {
-        let span = tokens.iter().find(|it| it.0.is_err()).unwrap().1.clone();
-
-        log!(
-            "Failed to lex source code: {:?} \n {:?}",
-            tokens.iter().find(|it| it.0.is_err()),
-            &src[span]
-        );
-        return None;
-    };
-
-    let mut tokens = tokens
-        .iter()
-        .map(|(tok, span)| (tok.clone().unwrap(), span.clone()))
-        .collect::<Vec<_>>();
+pub type Spanned<T> = (T, Range<usize>);
+
+/// Lexes `src` without stopping at errors. Text which could not be lexed becomes `Token::Error`,
+/// with consecutive errors, like a run of unknown characters, merged into one token.
+pub fn lex_with_errors(src: &str) -> Vec<Spanned<Token<'_>>> {
+    let mut tokens: Vec<Spanned<Token>> = vec![];
+
+    for (token, span) in Token::lex_spanned(src) {
+        match (token, tokens.last_mut()) {
+            (Ok(token), _) => tokens.push((token, span)),
+            (Err(_), Some((Token::Error(_), last))) if last.end == span.start => {
+                last.end = span.end
+            }
+            (Err(error), _) => tokens.push((Token::Error(error), span)),
+        }
+    }
 
     insert_template_tokens(src, &mut tokens);
 
-    Some(tokens)
+    tokens
 }
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum LexError {
     InvalidIdentifier(IdentError),
     UnterminatedComment,
+    // A number followed by letters or digits which are not part of any literal, like `0x` or `1.0e`
+    InvalidLiteral,
     #[default]
     Other,
 }
@@ -53,7 +54,8 @@ impl From<IdentError> for LexError {
 
 #[derive(Logos, Debug, PartialEq, Eq, Clone)]
 #[logos(error = LexError)]
-#[logos(skip r"\s")]
+// Blankspace, as listed by https://www.w3.org/TR/WGSL/#blankspace-and-line-breaks
+#[logos(skip r"[ \t\n\r\u{000B}\u{000C}\u{0085}\u{200E}\u{200F}\u{2028}\u{2029}]")]
 pub enum Token<'src> {
     #[token("alias", |_| Keyword::Alias)]
     #[token("break", |_| Keyword::Break)]
@@ -136,30 +138,59 @@ pub enum Token<'src> {
     #[regex(r"([_\p{XID_Start}][\p{XID_Continue}]+)|([\p{XID_Start}])|_", |lex| parse_ident(lex.slice()), priority = 2)]
     Ident(&'src str),
 
-    #[regex(r"0[iu]?")] // Zero Values
-    #[regex(r"[1-9][0-9]*[iu]?")] // Decimal Literals
-    #[regex(r"0[xX][0-9a-fA-F]+[iu]?")] // Hex Literals
+    #[regex(r"0[iu]?", lex_integer)] // Zero Values
+    #[regex(r"[1-9][0-9]*[iu]?", lex_integer)] // Decimal Literals
+    #[regex(r"0[xX][0-9a-fA-F]+[iu]?", lex_integer)] // Hex Literals
     Integer(&'src str),
 
     // Decimal Float Literals
-    #[regex(r"0[fh]")]
-    #[regex(r"[1-9][0-9]*[fh]")]
-    #[regex(r"[0-9]*\.[0-9]+([eE][+-]?[0-9]+)?[fh]?")]
-    #[regex(r"[0-9]+\.[0-9]*([eE][+-]?[0-9]+)?[fh]?", priority = 5)]
-    #[regex(r"[0-9]+[eE][+-]?[0-9]+[fh]?")]
+    #[regex(r"0[fh]", lex_float)]
+    #[regex(r"[1-9][0-9]*[fh]", lex_float)]
+    #[regex(r"[0-9]*\.[0-9]+([eE][+-]?[0-9]+)?[fh]?", lex_float)]
+    #[regex(r"[0-9]+\.[0-9]*([eE][+-]?[0-9]+)?[fh]?", lex_float, priority = 5)]
+    #[regex(r"[0-9]+[eE][+-]?[0-9]+[fh]?", lex_float)]
     // Hex Float Literals
     #[regex(
         r"0[xX][0-9a-fA-F]*\.[0-9a-fA-F]+([pP][+-]?[0-9]+[fh]?)?",
+        lex_float,
         priority = 9
     )]
-    #[regex(r"0[xX][0-9a-fA-F]+\.[0-9a-fA-F]*([pP][+-]?[0-9]+[fh]?)?")]
-    #[regex(r"0[xX][0-9a-fA-F]+[pP][+-]?[0-9]+[fh]?")]
+    #[regex(r"0[xX][0-9a-fA-F]+\.[0-9a-fA-F]*([pP][+-]?[0-9]+[fh]?)?", lex_float)]
+    #[regex(r"0[xX][0-9a-fA-F]+[pP][+-]?[0-9]+[fh]?", lex_float)]
     Float(&'src str),
 
     #[regex(r"/\*", lex_multiline_comment)]
-    #[regex(r"\/\/.*\n")]
+    // Line comments end before a line break, or at the end of the source
+    #[regex(r"//[^\n\r\u{000B}\u{000C}\u{0085}\u{2028}\u{2029}]*")]
     Trivia(&'src str),
 
     TemplateArgsStart,
     TemplateArgsEnd,
+
+    // Text which could not be lexed, only produced by `lex_with_errors`
+    Error(LexError),
+}
+
+impl<'src> Token<'src> {
+    /// Lexes `src` into tokens and their spans. Use this rather than `Token::lexer`.
+    pub fn lex_spanned(
+        src: &'src str,
+    ) -> impl Iterator<Item = Spanned<Result<Token<'src>, LexError>>> {
+        // Logos does not backtrack, so a keyword followed by non-ASCII blankspace, like U+0085
+        // which shares its first byte with `µ`, ends up as an identifier. Identifiers followed by
+        // non-ASCII characters are lexed again on their own instead.
+        Token::lexer(src)
+            .spanned()
+            .map(|(token, span)| match token {
+                Ok(Token::Ident(ident))
+                    if src[span.end..].starts_with(|it: char| !it.is_ascii()) =>
+                {
+                    let token = Token::lexer(ident)
+                        .next()
+                        .unwrap_or(Ok(Token::Ident(ident)));
+                    (token, span)
+                }
+                token => (token, span),
+            })
+    }
 }
@@ -0,0 +1,165 @@
+// Errors which are found by lexing alone, like reserved words used as identifiers and literals
+// which do not fit their type. Unlike the parser, every error in the source is reported.
+
+use std::ops::Range;
+
+use super::{IdentError, Keyword, LexError, Token, lex_with_errors};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LexDiagnostic {
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+pub fn lex_diagnostics(src: &str) -> Vec<LexDiagnostic> {
+    let tokens: Vec<_> = lex_with_errors(src)
+        .into_iter()
+        .filter(|(token, _)| !matches!(token, Token::Trivia(_)))
+        .collect();
+    let f16_enabled = is_f16_enabled(&tokens);
+
+    let mut diagnostics = vec![];
+    for (index, (token, span)) in tokens.iter().enumerate() {
+        let text = &src[span.clone()];
+        let prev = index.checked_sub(1).map(|index| &tokens[index].0);
+        let next = tokens.get(index + 1).map(|(token, _)| token);
+
+        let message = match token {
+            // `_` is a valid phony assignment target, as in `_ = f();`, but not a declaration
+            Token::Error(LexError::InvalidIdentifier(IdentError::SingleUnderscore))
+                if next == Some(&Token::Syntax("="))
+                    && !matches!(prev, Some(Token::Keyword(_))) =>
+            {
+                None
+            }
+            Token::Error(error) => Some(error_message(error, text)),
+            Token::Integer(_) => integer_error(text),
+            Token::Float(_) => float_error(text, f16_enabled),
+            _ => None,
+        };
+
+        if let Some(message) = message {
+            diagnostics.push(LexDiagnostic {
+                range: span.clone(),
+                message,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn error_message(error: &LexError, text: &str) -> String {
+    match error {
+        LexError::InvalidIdentifier(IdentError::ReservedKeyword) => {
+            format!("`{text}` is reserved in WGSL, and can not be used as a name")
+        }
+        LexError::InvalidIdentifier(IdentError::DoubleLeadingUnderscore) => {
+            format!("`{text}` starts with `__`, which is reserved in WGSL")
+        }
+        LexError::InvalidIdentifier(IdentError::SingleUnderscore) => {
+            "`_` is not a name, and can only be assigned to, as in `_ = f();`".to_owned()
+        }
+        LexError::InvalidIdentifier(IdentError::Unknown) => format!("Invalid identifier `{text}`"),
+        LexError::UnterminatedComment => "Unterminated block comment".to_owned(),
+        LexError::InvalidLiteral => literal_message(text),
+        LexError::Other => format!("Unexpected `{text}`"),
+    }
+}
+
+// Explains a malformed literal by the valid literal it starts with, and the letters following it
+fn literal_message(text: &str) -> String {
+    let digits = text.trim_end_matches(['i', 'u']);
+    if text.starts_with('0') && digits.len() > 1 && digits.bytes().all(|it| it.is_ascii_digit()) {
+        return format!("Integer literals can not have leading zeros: `{text}`");
+    }
+
+    if matches!(text, "0x" | "0X") {
+        return format!("Hexadecimal literal without digits: `{text}`");
+    }
+
+    let prefix = (1..text.len()).rev().find(|&end| {
+        let mut tokens = Token::lex_spanned(&text[..end]);
+        text[end..].starts_with(|it: char| it.is_ascii_alphabetic())
+            && matches!(
+                tokens.next(),
+                Some((Ok(Token::Integer(_) | Token::Float(_)), _))
+            )
+            && tokens.next().is_none()
+    });
+
+    match prefix {
+        Some(prefix) => format!(
+            "Invalid suffix `{}` on `{}`, literals can only end with `i`, `u`, `f` or `h`",
+            &text[prefix..],
+            &text[..prefix]
+        ),
+        None => format!("Invalid numeric literal `{text}`"),
+    }
+}
+
+fn integer_error(text: &str) -> Option<String> {
+    let (digits, ty, max) = match text.strip_suffix(['i', 'u']) {
+        Some(digits) if text.ends_with('i') => (digits, "i32", i32::MAX as u128),
+        Some(digits) => (digits, "u32", u32::MAX as u128),
+        None => (text, "a 64-bit integer", i64::MAX as u128),
+    };
+
+    let value = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => digits.parse(),
+    };
+
+    match value {
+        Ok(value) if value <= max => None,
+        _ => Some(format!(
+            "`{text}` is out of range for {ty}, whose largest value is {max}"
+        )),
+    }
+}
+
+fn float_error(text: &str, f16_enabled: bool) -> Option<String> {
+    let hex = text.starts_with("0x") || text.starts_with("0X");
+    // `f` is a digit of hexadecimal floats, which only have a suffix after their exponent
+    let suffix = match text.chars().last() {
+        Some(suffix @ ('f' | 'h')) if !hex || text.contains(['p', 'P']) => Some(suffix),
+        _ => None,
+    };
+
+    if suffix == Some('h') && !f16_enabled {
+        return Some(format!(
+            "`{text}` is an `f16` literal, which needs `enable f16;` at the start of the module"
+        ));
+    }
+
+    // The range of hexadecimal floats is left to the parser
+    if hex {
+        return None;
+    }
+
+    let value: f64 = text.trim_end_matches(['f', 'h']).parse().ok()?;
+    let (ty, max) = match suffix {
+        Some('f') => ("f32", f32::MAX as f64),
+        Some('h') => ("f16", 65504.0),
+        _ => ("a 64-bit float", f64::MAX),
+    };
+
+    match value.abs() <= max {
+        true => None,
+        false => Some(format!(
+            "`{text}` is out of range for {ty}, whose largest value is {max:e}"
+        )),
+    }
+}
+
+// Whether an `enable` directive lists `f16`
+fn is_f16_enabled(tokens: &[(Token, Range<usize>)]) -> bool {
+    tokens
+        .split(|(token, _)| *token == Token::Syntax(";"))
+        .any(|directive| match directive {
+            [(Token::Keyword(Keyword::Enable), _), extensions @ ..] => extensions
+                .iter()
+                .any(|(token, _)| *token == Token::Ident("f16")),
+            _ => false,
+        })
+}
@@ -0,0 +1,95 @@
+use logos::Lexer;
+
+use super::{LexError, Token};
+
+// Logos does not backtrack, so text which starts like a literal but does not complete one, like
+// `00` or `1e+`, can be lexed as a literal of the wrong kind or length. Every literal is checked
+// against the grammar again, and the letters, digits and dots after it are part of it.
+fn lex_literal<'a>(
+    lex: &mut Lexer<'a, Token<'a>>,
+    is_valid: fn(&str) -> bool,
+) -> Result<&'a str, LexError> {
+    let rest = lex
+        .remainder()
+        .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_' || char == '.'))
+        .unwrap_or(lex.remainder().len());
+
+    if rest > 0 {
+        lex.bump(rest);
+        return Err(LexError::InvalidLiteral);
+    }
+
+    match is_valid(lex.slice()) {
+        true => Ok(lex.slice()),
+        false => Err(LexError::InvalidLiteral),
+    }
+}
+
+pub fn lex_integer<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Result<&'a str, LexError> {
+    lex_literal(lex, is_integer)
+}
+
+pub fn lex_float<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Result<&'a str, LexError> {
+    lex_literal(lex, is_float)
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn is_hex_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+// Digits with at most one `.`, and at least one digit
+fn is_mantissa(text: &str, is_digits: fn(&str) -> bool) -> bool {
+    match text.split_once('.') {
+        Some((whole, fraction)) => {
+            (whole.is_empty() || is_digits(whole))
+                && (fraction.is_empty() || is_digits(fraction))
+                && whole.len() + fraction.len() > 0
+        }
+        None => is_digits(text),
+    }
+}
+
+// `[+-]?[0-9]+[fh]?`
+fn is_exponent(text: &str) -> bool {
+    let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+    is_digits(text.strip_suffix(['f', 'h']).unwrap_or(text))
+}
+
+// https://www.w3.org/TR/WGSL/#syntax-int_literal
+fn is_integer(text: &str) -> bool {
+    let text = text.strip_suffix(['i', 'u']).unwrap_or(text);
+
+    match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        Some(digits) => is_hex_digits(digits),
+        None => text == "0" || (is_digits(text) && !text.starts_with('0')),
+    }
+}
+
+// https://www.w3.org/TR/WGSL/#syntax-float_literal
+fn is_float(text: &str) -> bool {
+    if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        return match hex.split_once(['p', 'P']) {
+            Some((mantissa, exponent)) => {
+                is_mantissa(mantissa, is_hex_digits) && is_exponent(exponent)
+            }
+            // Without an exponent a suffix would be a hex digit
+            None => hex.contains('.') && is_mantissa(hex, is_hex_digits),
+        };
+    }
+
+    match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => is_mantissa(mantissa, is_digits) && is_exponent(exponent),
+        None => match text.strip_suffix(['f', 'h']) {
+            // `1f`, but not `01f`
+            Some(whole) if !whole.contains('.') => {
+                whole == "0" || (is_digits(whole) && !whole.starts_with('0'))
+            }
+            Some(mantissa) => is_mantissa(mantissa, is_digits),
+            None => text.contains('.') && is_mantissa(text, is_digits),
+        },
+    }
+}
@@ -1,202 +1,157 @@
-use std::{collections::HashSet, ops::Range};
-
-use super::Token;
+use std::ops::Range;
+
+use super::{LexError, Token};
+
+// Replaces the `<` and `>` of template lists by `TemplateArgsStart` and `TemplateArgsEnd`,
+// splitting operators like `>=` and `>>=` whose first characters end a template list
+pub fn insert_template_tokens<'src>(
+    source: &'src str,
+    tokens: &mut Vec<(Token<'src>, Range<usize>)>,
+) {
+    let templates = find_templates(source, tokens);
+    if templates.is_empty() {
+        return;
+    }
 
-pub fn insert_template_tokens(source: &str, tokens: &mut Vec<(Token, Range<usize>)>) {
-    let templates = find_templates(source);
+    let mut starts: Vec<usize> = templates.iter().map(|(start, _)| *start).collect();
+    let mut ends: Vec<usize> = templates.iter().map(|(_, end)| *end).collect();
+    starts.sort_unstable();
+    ends.sort_unstable();
 
-    let starts: HashSet<usize> = templates.iter().map(|(start, _)| *start).collect();
-    let ends: HashSet<usize> = templates.iter().map(|(_, end)| *end).collect();
+    let mut res_tokens = Vec::with_capacity(tokens.len());
 
-    let mut res_tokens: Vec<(Token, Range<usize>)> = vec![];
+    for (token, span) in tokens.drain(..) {
+        if !matches!(token, Token::Syntax(_)) {
+            res_tokens.push((token, span));
+            continue;
+        }
 
-    for (token, span) in tokens.iter() {
-        if matches!(token, Token::Syntax("<<") | Token::Syntax(">>")) {
-            let ident = match token {
-                Token::Syntax("<<") => Token::TemplateArgsStart,
-                Token::Syntax(">>") => Token::TemplateArgsEnd,
-                _ => unreachable!(),
+        // The characters between template brackets stay operators
+        let mut rest = span.start;
+        for position in span.clone() {
+            let bracket = match (
+                starts.binary_search(&position),
+                ends.binary_search(&position),
+            ) {
+                (Ok(_), _) => Token::TemplateArgsStart,
+                (_, Ok(_)) => Token::TemplateArgsEnd,
+                _ => continue,
             };
 
-            let (left, right) = (span.start, span.start + 1);
-            let (left_tok, right_tok) = (Token::Syntax("<"), Token::Syntax(">"));
-            let (left_span, right_span) = (left..(left + 1), (right..(right + 1)));
-
-            match (
-                ends.contains(&left) || starts.contains(&left),
-                ends.contains(&right) || starts.contains(&right),
-            ) {
-                (true, true) => {
-                    res_tokens.push((ident.clone(), left_span));
-                    res_tokens.push((ident.clone(), right_span));
-                    continue;
-                }
-                (true, false) => {
-                    res_tokens.push((ident.clone(), left_span));
-                    res_tokens.push((right_tok.clone(), right_span));
-                    continue;
-                }
-                (false, true) => {
-                    res_tokens.push((left_tok.clone(), left_span));
-                    res_tokens.push((ident.clone(), right_span));
-                    continue;
-                }
-                _ => {}
+            if rest < position {
+                res_tokens.push((Token::Syntax(&source[rest..position]), rest..position));
             }
-        } else if starts.contains(&span.start) {
-            res_tokens.push((Token::TemplateArgsStart, span.clone()));
-            continue;
-        } else if ends.contains(&span.start) {
-            res_tokens.push((Token::TemplateArgsEnd, span.clone()));
-            continue;
+            res_tokens.push((bracket, position..position + 1));
+            rest = position + 1;
         }
 
-        res_tokens.push((token.clone(), span.clone()));
+        if rest < span.end {
+            res_tokens.push((Token::Syntax(&source[rest..span.end]), rest..span.end));
+        }
     }
 
     *tokens = res_tokens;
 }
 
+// Tokens matching the identifier pattern, which includes keywords and reserved words
+fn is_ident_pattern(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Ident(_)
+            | Token::Keyword(_)
+            | Token::Boolean(_)
+            | Token::Error(LexError::InvalidIdentifier(_))
+    )
+}
+
 // https://www.w3.org/TR/WGSL/#template-list-discovery
-// This is required to disambiguate templates from comparison operators
-// TODO: Modify this algorithm to work on tokens
-fn find_templates(src: &str) -> Vec<(usize, usize)> {
+// This is required to disambiguate templates from comparison operators. Comments and literals are
+// skipped by their tokens, and the characters of operators are walked one at a time, like the
+// algorithm does with the source text.
+fn find_templates(source: &str, tokens: &[(Token, Range<usize>)]) -> Vec<(usize, usize)> {
     struct UnclosedCandidate {
         // Position offset in bytes
         position: usize,
         depth: u32,
     }
 
-    let chars: Vec<(usize, char)> = src.char_indices().collect();
     let mut discovered_template_lists = vec![];
     let mut pending: Vec<UnclosedCandidate> = vec![];
-    let mut current_position: usize = 0;
     let mut nesting_depth: u32 = 0;
-
-    let mut in_line_comment = false;
-    let mut in_block_comment = false;
-
-    while current_position < chars.len() {
-        let (byte_offset, current) = chars[current_position];
-        let next = chars.get(current_position + 1).map(|it| it.1);
-
-        if in_line_comment {
-            if current == '\n' {
-                in_line_comment = false;
-            }
-            current_position += 1;
-            continue;
+    let mut after_ident = false;
+    // Where the next character to look at is, as operators can take up the first character of
+    // the token after them, like the `==` of `>==`
+    let mut current_position = 0;
+
+    // Pending candidates at the current depth or deeper can not be template lists
+    let pop_to_depth = |pending: &mut Vec<UnclosedCandidate>, depth: u32| {
+        while pending.last().is_some_and(|it| it.depth >= depth) {
+            pending.pop();
         }
-
-        if in_block_comment {
-            if current == '*' && next == Some('/') {
-                in_block_comment = false;
-                current_position += 2;
+    };
+    let char_at = |position: usize| source.as_bytes().get(position).copied();
+
+    for (token, span) in tokens {
+        match token {
+            Token::Trivia(_) => continue,
+            Token::Syntax(_) => {}
+            _ => {
+                after_ident = is_ident_pattern(token);
+                current_position = span.end;
                 continue;
             }
-            current_position += 1;
-            continue;
         }
 
-        match current {
-            '/' => {
-                current_position += 1;
-                if next == Some('/') || next == Some('*') {
-                    in_line_comment = true;
-                    current_position += 1;
-                    continue;
-                }
-            }
-            '<' => {
-                pending.push(UnclosedCandidate {
-                    position: byte_offset,
-                    depth: nesting_depth,
-                });
-                current_position += 1;
-                if matches!(next, Some('<' | '=')) {
-                    pending.pop();
-                    current_position += 1;
-                    continue;
-                }
-            }
-            '>' => match pending.last() {
-                Some(unclosed) if unclosed.depth == nesting_depth => {
-                    discovered_template_lists.push((unclosed.position, byte_offset));
-                    pending.pop();
-                    current_position += 1;
-                    continue;
-                }
-                _ => {
-                    current_position += 1;
-                    if next == Some('=') {
+        current_position = current_position.max(span.start);
+        while current_position < span.end {
+            let position = current_position;
+            let next = char_at(position + 1);
+            current_position += 1;
+
+            match source.as_bytes()[position] {
+                b'<' if std::mem::take(&mut after_ident) => {
+                    // `<<` and `<=` are operators
+                    if matches!(next, Some(b'<' | b'=')) {
                         current_position += 1;
+                    } else {
+                        pending.push(UnclosedCandidate {
+                            position,
+                            depth: nesting_depth,
+                        });
                     }
-                    continue;
                 }
-            },
-
-            '(' | '[' => {
-                nesting_depth += 1;
-                current_position += 1;
-                continue;
-            }
-
-            ')' | ']' => {
-                loop {
-                    pending.pop();
-                    if pending.is_empty() || pending.last().unwrap().depth < nesting_depth {
-                        break;
+                b'>' => match pending.last() {
+                    Some(unclosed) if unclosed.depth == nesting_depth => {
+                        discovered_template_lists.push((unclosed.position, position));
+                        pending.pop();
                     }
+                    // `>=` is a comparison
+                    _ if next == Some(b'=') => current_position += 1,
+                    _ => {}
+                },
+                b'(' | b'[' => nesting_depth += 1,
+                b')' | b']' => {
+                    pop_to_depth(&mut pending, nesting_depth);
+                    nesting_depth = nesting_depth.saturating_sub(1);
                 }
-                nesting_depth = nesting_depth.saturating_sub(1);
-                current_position += 1;
-                continue;
-            }
-
-            '!' => {
-                current_position += 1;
-                if next == Some('=') {
-                    current_position += 1
-                }
-                continue;
-            }
-
-            '=' => {
-                current_position += 2;
-                if next != Some('=') {
+                // `!=` and `<=` are comparisons
+                b'!' | b'<' if next == Some(b'=') => current_position += 1,
+                b'=' if next == Some(b'=') => current_position += 1,
+                // An assignment ends any expression
+                b'=' | b';' | b'{' | b':' => {
                     nesting_depth = 0;
                     pending.clear();
                 }
-                continue;
-            }
-
-            ';' | '{' | ':' => {
-                nesting_depth = 0;
-                pending.clear();
-                current_position += 1;
-            }
-
-            '&' if next == Some('&') => {
-                loop {
-                    pending.pop();
-                    if pending.is_empty() || pending.last().unwrap().depth < nesting_depth {
-                        break;
-                    }
+                // `&&` and `||` have a lower precedence than comparisons
+                char @ (b'&' | b'|') if next == Some(char) => {
+                    pop_to_depth(&mut pending, nesting_depth);
+                    current_position += 1;
                 }
-                current_position += 2;
+                _ => {}
             }
 
-            '|' if next == Some('|') => {
-                loop {
-                    pending.pop();
-                    if pending.is_empty() || pending.last().unwrap().depth < nesting_depth {
-                        break;
-                    }
-                }
-                current_position += 2;
-            }
-            _ => current_position += 1,
-        };
+            after_ident = false;
+        }
     }
 
     discovered_template_lists
@@ -1,6 +1,15 @@
 #[cfg(test)]
 mod comment;
 
+#[cfg(test)]
+mod diagnostics;
+
+#[cfg(test)]
+mod error;
+
+#[cfg(test)]
+mod grammar;
+
 #[cfg(test)]
 mod ident;
 
@@ -15,3 +24,6 @@ mod misc;
 
 #[cfg(test)]
 mod operator;
+
+#[cfg(test)]
+mod template;
@@ -0,0 +1,85 @@
+use super::super::*;
+
+fn messages(source: &str) -> Vec<(&str, String)> {
+    lex_diagnostics(source)
+        .into_iter()
+        .map(|diagnostic| (&source[diagnostic.range], diagnostic.message))
+        .collect()
+}
+
+#[test]
+pub fn invalid_identifiers_are_explained() {
+    assert_eq!(
+        messages("fn f() { let class = 1; let __a = 2; _ = 3; let _ = 4; }"),
+        [
+            (
+                "class",
+                "`class` is reserved in WGSL, and can not be used as a name".to_owned()
+            ),
+            (
+                "__a",
+                "`__a` starts with `__`, which is reserved in WGSL".to_owned()
+            ),
+            (
+                "_",
+                "`_` is not a name, and can only be assigned to, as in `_ = f();`".to_owned()
+            ),
+        ]
+    );
+}
+
+#[test]
+pub fn every_error_is_reported() {
+    let source = "const a = 1u8; const b = 0123; const c = 0x; const d = $; /* open";
+    let ranges: Vec<_> = messages(source).into_iter().map(|(text, _)| text).collect();
+
+    assert_eq!(ranges, ["1u8", "0123", "0x", "$", "/* open"]);
+    assert_eq!(
+        messages("const a = 1u8;")[0].1,
+        "Invalid suffix `u8` on `1`, literals can only end with `i`, `u`, `f` or `h`"
+    );
+}
+
+#[test]
+pub fn out_of_range_integers() {
+    let source = "const a = 2147483647i; const b = 2147483648i; const c = 4294967296u;
+const d = 0xffffffffu; const e = 9223372036854775808; const f = 0x100000000u;";
+    let ranges: Vec<_> = messages(source).into_iter().map(|(text, _)| text).collect();
+
+    assert_eq!(
+        ranges,
+        [
+            "2147483648i",
+            "4294967296u",
+            "9223372036854775808",
+            "0x100000000u"
+        ]
+    );
+    assert_eq!(
+        messages("const a = 4294967296u;")[0].1,
+        "`4294967296u` is out of range for u32, whose largest value is 4294967295"
+    );
+}
+
+#[test]
+pub fn out_of_range_floats() {
+    let source =
+        "enable f16; const a = 1e39f; const b = 65504.0h; const c = 70000.0h; const d = 1e39;";
+    let ranges: Vec<_> = messages(source).into_iter().map(|(text, _)| text).collect();
+
+    assert_eq!(ranges, ["1e39f", "70000.0h"]);
+}
+
+#[test]
+pub fn f16_literals_need_the_extension() {
+    let literals = "const a = 1.0h; const b = 0x1p2h; const c = 0x1.f; const d = 2h;";
+
+    let ranges: Vec<_> = messages(literals)
+        .into_iter()
+        .map(|(text, _)| text)
+        .collect();
+    assert_eq!(ranges, ["1.0h", "0x1p2h", "2h"]);
+
+    assert_eq!(messages(&format!("enable f16;\n{literals}")), []);
+    assert_eq!(messages("enable clip_distances, f16; const a = 1.0h;"), []);
+}
@@ -0,0 +1,85 @@
+use super::super::*;
+
+fn errors(source: &str) -> Vec<(LexError, &str)> {
+    lex_with_errors(source)
+        .into_iter()
+        .filter_map(|(token, span)| match token {
+            Token::Error(error) => Some((error, &source[span])),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+pub fn errors_become_tokens() {
+    let source = "let class = 1;";
+    let tokens: Vec<_> = lex_with_errors(source)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect();
+
+    assert_eq!(
+        tokens,
+        [
+            Token::Keyword(Keyword::Let),
+            Token::Error(LexError::InvalidIdentifier(IdentError::ReservedKeyword)),
+            Token::Syntax("="),
+            Token::Integer("1"),
+            Token::Syntax(";"),
+        ]
+    );
+}
+
+#[test]
+pub fn invalid_identifiers() {
+    assert_eq!(
+        errors("let _ = __a + class;"),
+        [
+            (
+                LexError::InvalidIdentifier(IdentError::SingleUnderscore),
+                "_"
+            ),
+            (
+                LexError::InvalidIdentifier(IdentError::DoubleLeadingUnderscore),
+                "__a"
+            ),
+            (
+                LexError::InvalidIdentifier(IdentError::ReservedKeyword),
+                "class"
+            ),
+        ]
+    );
+}
+
+#[test]
+pub fn unterminated_comment_reaches_the_end() {
+    assert_eq!(
+        errors("const a = 1; /* comment\nconst b = 2;"),
+        [(LexError::UnterminatedComment, "/* comment\nconst b = 2;")]
+    );
+}
+
+#[test]
+pub fn invalid_literals() {
+    let literals = ["0x", "1.0e", "0123", "12abc", "1u8", "0x1.0q"];
+
+    for literal in literals {
+        assert_eq!(
+            errors(literal),
+            [(LexError::InvalidLiteral, literal)],
+            "{literal:?} should be an invalid literal"
+        );
+    }
+
+    for literal in ["1e-5", "0x1p4", "1.0f", "0123.5", "1.", "0x1.8p-2h"] {
+        assert_eq!(errors(literal), [], "{literal:?} should be a valid literal");
+    }
+}
+
+#[test]
+pub fn unknown_characters_are_merged() {
+    assert_eq!(
+        errors("a $$ b $ c"),
+        [(LexError::Other, "$$"), (LexError::Other, "$")]
+    );
+}
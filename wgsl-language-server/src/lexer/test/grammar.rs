@@ -0,0 +1,218 @@
+// Tests generated from the token rules of the WGSL grammar, https://www.w3.org/TR/WGSL/#grammar
+use super::super::*;
+
+// Every string made of one of the options of each part, in order
+fn combine(parts: &[&[&str]]) -> Vec<String> {
+    parts.iter().fold(vec![String::new()], |prefixes, options| {
+        prefixes
+            .iter()
+            .flat_map(|prefix| {
+                options
+                    .iter()
+                    .map(move |option| format!("{prefix}{option}"))
+            })
+            .collect()
+    })
+}
+
+fn lex_all(source: &str) -> Vec<Result<Token<'_>, LexError>> {
+    Token::lex_spanned(source).map(|(token, _)| token).collect()
+}
+
+const DIGITS: &[&str] = &["0", "7", "09", "123"];
+const NONZERO: &[&str] = &["1", "9", "10", "123"];
+const HEX_PREFIX: &[&str] = &["0x", "0X"];
+const HEX_DIGITS: &[&str] = &["0", "a", "F3", "1f", "dEaD"];
+const INT_SUFFIX: &[&str] = &["", "i", "u"];
+const FLOAT_SUFFIX: &[&str] = &["", "f", "h"];
+const EXPONENT: &[&str] = &["e5", "E5", "e+10", "e-3", "E-07"];
+const HEX_EXPONENT: &[&str] = &["p5", "P5", "p+10", "p-3", "P-07"];
+
+#[test]
+pub fn int_literals() {
+    let literals = [
+        // decimal_int_literal: /0[iu]?/ | /[1-9][0-9]*[iu]?/
+        combine(&[&["0"], INT_SUFFIX]),
+        combine(&[NONZERO, INT_SUFFIX]),
+        // hex_int_literal: /0[xX][0-9a-fA-F]+[iu]?/
+        combine(&[HEX_PREFIX, HEX_DIGITS, INT_SUFFIX]),
+    ];
+
+    for literal in literals.iter().flatten() {
+        assert_eq!(
+            lex_all(literal),
+            [Ok(Token::Integer(literal))],
+            "{literal:?} should be an integer literal"
+        );
+    }
+}
+
+#[test]
+pub fn float_literals() {
+    let literals = [
+        // decimal_float_literal
+        // /0[fh]/
+        combine(&[&["0"], &["f", "h"]]),
+        // /[1-9][0-9]*[fh]/
+        combine(&[NONZERO, &["f", "h"]]),
+        // /[0-9]*\.[0-9]+([eE][+-]?[0-9]+)?[fh]?/
+        combine(&[&["", "0", "12"], &["."], DIGITS, &[""], FLOAT_SUFFIX]),
+        combine(&[&["", "12"], &["."], DIGITS, EXPONENT, FLOAT_SUFFIX]),
+        // /[0-9]+\.[0-9]*([eE][+-]?[0-9]+)?[fh]?/
+        combine(&[DIGITS, &["."], &["", "5"], &["", "e5", "E-3"], FLOAT_SUFFIX]),
+        // /[0-9]+[eE][+-]?[0-9]+[fh]?/
+        combine(&[DIGITS, EXPONENT, FLOAT_SUFFIX]),
+        // hex_float_literal
+        // /0[xX][0-9a-fA-F]*\.[0-9a-fA-F]+([pP][+-]?[0-9]+[fh]?)?/
+        combine(&[HEX_PREFIX, &["", "1f"], &["."], HEX_DIGITS]),
+        combine(&[
+            HEX_PREFIX,
+            &["", "1f"],
+            &["."],
+            HEX_DIGITS,
+            HEX_EXPONENT,
+            FLOAT_SUFFIX,
+        ]),
+        // /0[xX][0-9a-fA-F]+\.[0-9a-fA-F]*([pP][+-]?[0-9]+[fh]?)?/
+        combine(&[HEX_PREFIX, HEX_DIGITS, &["."], &["", "8"]]),
+        combine(&[
+            HEX_PREFIX,
+            HEX_DIGITS,
+            &["."],
+            &["", "8"],
+            HEX_EXPONENT,
+            FLOAT_SUFFIX,
+        ]),
+        // /0[xX][0-9a-fA-F]+[pP][+-]?[0-9]+[fh]?/
+        combine(&[HEX_PREFIX, HEX_DIGITS, HEX_EXPONENT, FLOAT_SUFFIX]),
+    ];
+
+    for literal in literals.iter().flatten() {
+        assert_eq!(
+            lex_all(literal),
+            [Ok(Token::Float(literal))],
+            "{literal:?} should be a float literal"
+        );
+    }
+}
+
+#[test]
+pub fn malformed_literals() {
+    let literals = [
+        "00", "01u", "0x", "0xg", "1i32", "1.0e", "1e", "0x1p", "0x.p1", "1.0d", "1fh", "0x1.0q",
+    ];
+
+    for literal in literals {
+        assert_eq!(
+            lex_all(literal),
+            [Err(LexError::InvalidLiteral)],
+            "{literal:?} should not be a literal"
+        );
+    }
+
+    // Signs are only part of an exponent followed by digits
+    assert_eq!(
+        lex_all("1e+"),
+        [Err(LexError::InvalidLiteral), Ok(Token::Syntax("+"))]
+    );
+}
+
+// Blankspace separates tokens, and line breaks end line comments
+#[test]
+pub fn blankspace() {
+    let blankspace = [
+        '\u{0020}', '\u{0009}', '\u{000A}', '\u{000B}', '\u{000C}', '\u{000D}', '\u{0085}',
+        '\u{200E}', '\u{200F}', '\u{2028}', '\u{2029}',
+    ];
+    let line_breaks = [
+        '\u{000A}', '\u{000B}', '\u{000C}', '\u{000D}', '\u{0085}', '\u{2028}', '\u{2029}',
+    ];
+
+    for char in blankspace {
+        let source = format!("a{char}b");
+        assert_eq!(
+            lex_all(&source),
+            [Ok(Token::Ident("a")), Ok(Token::Ident("b"))],
+            "{char:?} should be blankspace"
+        );
+
+        let source = format!("const{char}a{char}true{char}let");
+        assert_eq!(
+            lex_all(&source),
+            [
+                Ok(Token::Keyword(Keyword::Const)),
+                Ok(Token::Ident("a")),
+                Ok(Token::Boolean(true)),
+                Ok(Token::Keyword(Keyword::Let))
+            ],
+            "{char:?} should be blankspace after keywords"
+        );
+    }
+
+    for char in line_breaks {
+        let source = format!("// comment{char}const");
+        assert_eq!(
+            lex_all(&source),
+            [
+                Ok(Token::Trivia("// comment")),
+                Ok(Token::Keyword(Keyword::Const))
+            ],
+            "{char:?} should end a line comment"
+        );
+    }
+
+    // Other white space is not blankspace
+    for char in ['\u{00A0}', '\u{2003}', '\u{3000}', '\u{FEFF}'] {
+        let source = format!("a{char}b");
+        assert_eq!(
+            lex_all(&source),
+            [
+                Ok(Token::Ident("a")),
+                Err(LexError::Other),
+                Ok(Token::Ident("b"))
+            ],
+            "{char:?} should not be blankspace"
+        );
+    }
+}
+
+#[test]
+pub fn line_comment_at_end_of_source() {
+    assert_eq!(
+        lex_all("const a = 1; // comment"),
+        [
+            Ok(Token::Keyword(Keyword::Const)),
+            Ok(Token::Ident("a")),
+            Ok(Token::Syntax("=")),
+            Ok(Token::Integer("1")),
+            Ok(Token::Syntax(";")),
+            Ok(Token::Trivia("// comment")),
+        ]
+    );
+    assert_eq!(lex_all("//"), [Ok(Token::Trivia("//"))]);
+}
+
+#[test]
+pub fn nested_block_comments() {
+    let comments = [
+        "/**/",
+        "/* a /* b */ c */",
+        "/* /* /* deep */ */ */",
+        "/*/ still open */",
+        "/* ** / // */",
+        "/* a */",
+    ];
+
+    for comment in comments {
+        assert_eq!(
+            lex_all(&format!("{comment}a")),
+            [Ok(Token::Trivia(comment)), Ok(Token::Ident("a"))],
+            "{comment:?} should be one comment"
+        );
+    }
+
+    assert_eq!(
+        lex_all("/* a /* b */"),
+        [Err(LexError::UnterminatedComment)]
+    );
+}
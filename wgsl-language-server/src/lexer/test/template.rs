@@ -0,0 +1,91 @@
+use super::super::*;
+
+// The tokens of `source` separated by spaces, with template brackets written as `⟨` and `⟩`
+fn templates(source: &str) -> String {
+    lex_with_errors(source)
+        .into_iter()
+        .filter(|(token, _)| !matches!(token, Token::Trivia(_)))
+        .map(|(token, span)| match token {
+            Token::TemplateArgsStart => "⟨",
+            Token::TemplateArgsEnd => "⟩",
+            _ => &source[span],
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The examples of https://www.w3.org/TR/WGSL/#template-list-discovery
+#[test]
+pub fn spec_examples() {
+    let examples = [
+        ("A ( B < C, D > ( E ) )", "A ( B ⟨ C , D ⟩ ( E ) )"),
+        (
+            "array<i32,select(2,3,a>b)>",
+            "array ⟨ i32 , select ( 2 , 3 , a > b ) ⟩",
+        ),
+        ("A<B<=C>", "A ⟨ B <= C ⟩"),
+        ("A<(B>=C)>", "A ⟨ ( B >= C ) ⟩"),
+        ("A<(B!=C)>", "A ⟨ ( B != C ) ⟩"),
+        ("A<(B==C)>", "A ⟨ ( B == C ) ⟩"),
+        ("a<b || c>d", "a < b || c > d"),
+        ("a<b && c>d", "a < b && c > d"),
+    ];
+
+    for (source, expected) in examples {
+        assert_eq!(templates(source), expected, "{source}");
+    }
+}
+
+#[test]
+pub fn nested_templates() {
+    let examples = [
+        ("array<vec2<f32>, 4>", "array ⟨ vec2 ⟨ f32 ⟩ , 4 ⟩"),
+        (
+            "ptr<function, array<vec4<f32>, 2>>",
+            "ptr ⟨ function , array ⟨ vec4 ⟨ f32 ⟩ , 2 ⟩ ⟩",
+        ),
+        (
+            "var<storage, read_write> a: array<atomic<u32>>;",
+            "var ⟨ storage , read_write ⟩ a : array ⟨ atomic ⟨ u32 ⟩ ⟩ ;",
+        ),
+        ("array</* f16 */ f32>", "array ⟨ f32 ⟩"),
+        ("vec3<f32>(1.0)", "vec3 ⟨ f32 ⟩ ( 1.0 )"),
+    ];
+
+    for (source, expected) in examples {
+        assert_eq!(templates(source), expected, "{source}");
+    }
+}
+
+#[test]
+pub fn comparisons_and_shifts() {
+    let examples = [
+        ("a < b || c > d", "a < b || c > d"),
+        ("x = a << 2u;", "x = a << 2u ;"),
+        ("x >>= 1u;", "x >>= 1u ;"),
+        ("x <<= 1u;", "x <<= 1u ;"),
+        ("if a <= b && c >= d {}", "if a <= b && c >= d { }"),
+        ("f(a < b, c)", "f ( a < b , c )"),
+        ("let x = 1 < 2;", "let x = 1 < 2 ;"),
+        ("a[i] < b[j]", "a [ i ] < b [ j ]"),
+    ];
+
+    for (source, expected) in examples {
+        assert_eq!(templates(source), expected, "{source}");
+    }
+}
+
+// Operators starting with `>` are split when the `>` ends a template list
+#[test]
+pub fn split_operators() {
+    let examples = [
+        ("a<b>=c", "a ⟨ b ⟩ = c"),
+        ("a<b<c>>=d", "a ⟨ b ⟨ c ⟩ ⟩ = d"),
+        ("a<b<c>>d", "a ⟨ b ⟨ c ⟩ ⟩ d"),
+        ("a<b>>=c", "a ⟨ b ⟩ >= c"),
+    ];
+
+    for (source, expected) in examples {
+        assert_eq!(templates(source), expected, "{source}");
+    }
+}
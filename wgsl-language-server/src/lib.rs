@@ -1,11 +1,28 @@
+mod backends;
 mod block_ext;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+mod code_actions;
+mod code_lens;
+mod commands;
 mod completions;
+mod config;
+mod document_links;
 mod document_tracker;
 mod fmt;
+mod functions;
+mod fuzzy;
+mod hover;
+mod interface;
+mod layout;
 mod lexer;
 mod parser;
+mod preprocessor;
 mod pretty_error;
 mod range_tools;
+mod reflection;
+mod rust_structs;
+mod stage_interface;
 mod symbol_provider;
 mod wgsl_error;
 
@@ -21,41 +38,67 @@ mod macros {
 
 pub(crate) use macros::log;
 
+use config::ProjectConfig;
 use document_tracker::DocumentTracker;
 
 use lsp_types::{
-    CompletionItem, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, DocumentFormattingParams, Position, PublishDiagnosticsParams,
-    TextDocumentIdentifier, TextDocumentPositionParams,
+    CodeActionParams, CodeLensParams, CompletionItem, DidChangeConfigurationParams,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, DocumentLinkParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, DocumentSymbolParams, ExecuteCommandParams, HoverParams,
+    Position, PublishDiagnosticsParams, ShowMessageParams, TextDocumentIdentifier,
+    TextDocumentPositionParams, WorkspaceSymbolParams,
 };
 
+use serde::Deserialize;
 use serde_wasm_bindgen::{from_value, to_value};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console, js_name = error)]
-    fn console_log(s: &str);
+    fn console_error(s: &str);
 
     #[wasm_bindgen(js_namespace = performance, js_name = now)]
     fn performance_now() -> usize;
 }
 
+#[cfg(target_arch = "wasm32")]
+fn console_log(s: &str) {
+    console_error(s);
+}
+
+// There is no console to log to when running natively, e.g. from the CLI
+#[cfg(not(target_arch = "wasm32"))]
+fn console_log(_s: &str) {}
+
+#[derive(Deserialize)]
+struct DidChangeConfigFileParams {
+    uri: lsp_types::Uri,
+    // `None` when the file was deleted
+    text: Option<String>,
+}
+
 #[wasm_bindgen]
 pub struct WGSLLanguageServer {
     documents: DocumentTracker,
     send_diagnostics_callback: js_sys::Function,
+    show_message_callback: js_sys::Function,
 }
 
 #[wasm_bindgen]
 impl WGSLLanguageServer {
     #[wasm_bindgen(constructor)]
-    pub fn new(send_diagnostics_callback: &js_sys::Function) -> Self {
+    pub fn new(
+        send_diagnostics_callback: &js_sys::Function,
+        show_message_callback: &js_sys::Function,
+    ) -> Self {
         console_error_panic_hook::set_once();
         log!("WGSL Language Server Created");
         Self {
             documents: DocumentTracker::new(),
             send_diagnostics_callback: send_diagnostics_callback.clone(),
+            show_message_callback: show_message_callback.clone(),
         }
     }
 
@@ -71,13 +114,83 @@ impl WGSLLanguageServer {
         serde_json::to_string(&res).unwrap()
     }
 
+    #[wasm_bindgen(js_name = onHover)]
+    pub fn on_hover(&mut self, params: JsValue) -> String {
+        log!("Request for hover");
+        let HoverParams {
+            text_document_position_params:
+                TextDocumentPositionParams {
+                    text_document,
+                    position,
+                },
+            ..
+        } = from_value(params).unwrap();
+
+        let res = self.documents.get_hover(&text_document.uri, &position);
+        serde_json::to_string(&res).unwrap()
+    }
+
     #[wasm_bindgen(js_name = onDocumentSymbol)]
-    pub fn on_document_symbol(&mut self, _params: JsValue) -> String {
+    pub fn on_document_symbol(&mut self, params: JsValue) -> String {
         log!("Request for document symbol");
-        let res = self.documents.get_symbols();
+        let DocumentSymbolParams { text_document, .. } = from_value(params).unwrap();
+
+        let res = self.documents.get_document_symbols(&text_document.uri);
+        serde_json::to_string(&res).unwrap()
+    }
+
+    #[wasm_bindgen(js_name = onWorkspaceSymbol)]
+    pub fn on_workspace_symbol(&mut self, params: JsValue) -> String {
+        log!("Request for workspace symbol");
+        let WorkspaceSymbolParams { query, .. } = from_value(params).unwrap();
+
+        let res = self.documents.get_workspace_symbols(&query);
+        serde_json::to_string(&res).unwrap()
+    }
+
+    #[wasm_bindgen(js_name = onCodeLens)]
+    pub fn on_code_lens(&mut self, params: JsValue) -> String {
+        log!("Request for code lens");
+        let CodeLensParams { text_document, .. } = from_value(params).unwrap();
+
+        let res = self.documents.get_code_lenses(&text_document.uri);
+        serde_json::to_string(&res).unwrap()
+    }
+
+    #[wasm_bindgen(js_name = onDocumentLinks)]
+    pub fn on_document_links(&mut self, params: JsValue) -> String {
+        log!("Request for document links");
+        let DocumentLinkParams { text_document, .. } = from_value(params).unwrap();
+
+        let res = self.documents.get_document_links(&text_document.uri);
+        serde_json::to_string(&res).unwrap()
+    }
+
+    #[wasm_bindgen(js_name = onCodeAction)]
+    pub fn on_code_action(&mut self, params: JsValue) -> String {
+        log!("Request for code action");
+        let CodeActionParams {
+            text_document,
+            range,
+            ..
+        } = from_value(params).unwrap();
+
+        let res = self.documents.get_code_actions(&text_document.uri, &range);
         serde_json::to_string(&res).unwrap()
     }
 
+    #[wasm_bindgen(js_name = onExecuteCommand)]
+    pub fn on_execute_command(&mut self, params: JsValue) -> Result<String, JsValue> {
+        log!("Request for execute command");
+        let params: ExecuteCommandParams = from_value(params)?;
+
+        let output = self
+            .documents
+            .execute_command(params)
+            .map_err(|err| JsValue::from_str(&err))?;
+        Ok(serde_json::to_string(&output).unwrap())
+    }
+
     #[wasm_bindgen(js_name = onDocumentFormatting)]
     pub fn on_document_formatting(&mut self, params_json: String) -> Option<String> {
         log!("Request for document formatting");
@@ -87,7 +200,37 @@ impl WGSLLanguageServer {
             return None;
         };
 
-        let edits = self.documents.format_document(params)?;
+        let (edits, message) = self.documents.format_document(params)?;
+        if let Some(message) = message {
+            self.show_message(message);
+        }
+        serde_json::to_string(&edits).ok()
+    }
+
+    #[wasm_bindgen(js_name = onDocumentRangeFormatting)]
+    pub fn on_document_range_formatting(&mut self, params_json: String) -> Option<String> {
+        log!("Request for range formatting");
+
+        let Ok(params) = serde_json::from_str::<DocumentRangeFormattingParams>(&params_json) else {
+            log!("Failed to parse params: {}", params_json);
+            return None;
+        };
+
+        let edits = self.documents.format_range(params)?;
+        serde_json::to_string(&edits).ok()
+    }
+
+    #[wasm_bindgen(js_name = onDocumentOnTypeFormatting)]
+    pub fn on_document_on_type_formatting(&mut self, params_json: String) -> Option<String> {
+        log!("Request for on type formatting");
+
+        let Ok(params) = serde_json::from_str::<DocumentOnTypeFormattingParams>(&params_json)
+        else {
+            log!("Failed to parse params: {}", params_json);
+            return None;
+        };
+
+        let edits = self.documents.format_on_type(params)?;
         serde_json::to_string(&edits).ok()
     }
 
@@ -110,6 +253,29 @@ impl WGSLLanguageServer {
                 self.update_diagnostics();
             }
             "textDocument/didSave" => {}
+            "workspace/didChangeConfiguration" => {
+                let params: DidChangeConfigurationParams = from_value(params).unwrap();
+                if let Some(message) = self.documents.set_settings(params.settings) {
+                    self.show_message(message);
+                }
+                self.update_diagnostics();
+            }
+            // Sent by the client when `wgsl-analyzer.toml` is created, changed or deleted
+            "wgsl-analyzer/didChangeProjectConfig" => {
+                let params: DidChangeConfigFileParams = from_value(params).unwrap();
+                let project_config = params.text.map(|text| ProjectConfig {
+                    uri: params.uri,
+                    text,
+                });
+                self.documents.set_project_config(project_config);
+                self.update_diagnostics();
+            }
+            // Sent by the client for every `.wgslfmt.toml` in the workspace, and when one changes
+            "wgsl-analyzer/didChangeFormatConfig" => {
+                let params: DidChangeConfigFileParams = from_value(params).unwrap();
+                self.documents.set_format_config(params.uri, params.text);
+                self.update_diagnostics();
+            }
             "initialized" => {}
             _ => log!("on_notification {} {:?}", method, params),
         }
@@ -136,6 +302,13 @@ impl WGSLLanguageServer {
         }
     }
 
+    fn show_message(&self, params: ShowMessageParams) {
+        let params = &to_value(&params).unwrap();
+        if let Err(e) = self.show_message_callback.call1(&JsValue::null(), params) {
+            log!("show_message params:\n\t{:?}\n\tJS error: {:?}", params, e);
+        }
+    }
+
     fn get_auto_complete(
         &self,
         text_document: TextDocumentIdentifier,
@@ -1,3 +1,7 @@
+use std::ops::Range;
+
+use crate::lexer::{Keyword, Token};
+
 pub fn matching_bracket_index(source: &str, open: usize) -> Option<usize> {
     let chars = source[open..].char_indices();
     let mut depth = 0;
@@ -17,6 +21,114 @@ pub fn matching_bracket_index(source: &str, open: usize) -> Option<usize> {
     None
 }
 
+/// Finds the byte range of the first identifier matching `name` within `range`.
+/// Tokens which fail to lex are skipped, so this works on partially invalid source.
+pub fn find_ident(source: &str, range: Range<usize>, name: &str) -> Option<Range<usize>> {
+    let offset = range.start;
+    Token::lex_spanned(source.get(range)?)
+        .find(|(token, _)| matches!(token, Ok(Token::Ident(ident)) if *ident == name))
+        .map(|(_, span)| (span.start + offset)..(span.end + offset))
+}
+
+/// Finds the identifier containing the byte `offset`, returning it along with its range.
+pub fn ident_at(source: &str, offset: usize) -> Option<(&str, Range<usize>)> {
+    Token::lex_spanned(source)
+        .take_while(|(_, span)| span.start <= offset)
+        .find_map(|(token, span)| match token {
+            Ok(Token::Ident(ident)) if span.contains(&offset) || span.end == offset => {
+                Some((ident, span))
+            }
+            _ => None,
+        })
+}
+
+/// Finds the declaration introduced by `keyword` with the given `name`.
+/// Returns the range from the keyword up to and including either the closing brace of its body,
+/// or the terminating semicolon, along with the range of its name.
+pub fn find_declaration(
+    source: &str,
+    keyword: Keyword,
+    name: &str,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let tokens: Vec<_> = Token::lex_spanned(source)
+        .filter_map(|(token, span)| Some((token.ok()?, span)))
+        .collect();
+
+    tokens.windows(2).find_map(|window| match window {
+        [
+            (Token::Keyword(kw), keyword_span),
+            (Token::Ident(ident), ident_span),
+        ] if *kw == keyword && *ident == name => {
+            let rest = &source[ident_span.end..];
+            let end = match (rest.find('{'), rest.find(';')) {
+                (Some(open), Some(semicolon)) if semicolon < open => ident_span.end + semicolon + 1,
+                (Some(open), _) => matching_bracket_index(source, ident_span.end + open)
+                    .map(|close| close + 1)
+                    .unwrap_or(ident_span.end),
+                (None, Some(semicolon)) => ident_span.end + semicolon + 1,
+                (None, None) => ident_span.end,
+            };
+            Some((keyword_span.start..end, ident_span.clone()))
+        }
+        _ => None,
+    })
+}
+
+/// Finds each `name: type` member declaration within the body of a struct.
+/// Returns the range of each member declaration along with the range of its name.
+pub fn find_struct_members(source: &str, range: Range<usize>) -> Vec<(Range<usize>, Range<usize>)> {
+    let offset = range.start;
+    let Some(body) = source.get(range) else {
+        return vec![];
+    };
+
+    let tokens: Vec<_> = Token::lex_spanned(body)
+        .filter_map(|(token, span)| Some((token.ok()?, (span.start + offset)..(span.end + offset))))
+        .collect();
+
+    let mut members = vec![];
+    let mut current: Option<Range<usize>> = None;
+    let mut last_end = 0;
+    let mut brace_depth = 0;
+    let mut nesting = 0;
+
+    for (index, (token, span)) in tokens.iter().enumerate() {
+        let member_end = brace_depth == 1
+            && nesting == 0
+            && matches!(token, Token::Syntax(",") | Token::Syntax("}"));
+
+        if member_end && let Some(name) = current.take() {
+            members.push((name.start..last_end, name));
+        }
+
+        match token {
+            Token::Syntax("{") => brace_depth += 1,
+            Token::Syntax("}") => brace_depth -= 1,
+            Token::Syntax("(" | "[" | "<") => nesting += 1,
+            Token::Syntax(")" | "]" | ">") => nesting -= 1,
+            Token::Syntax(">>") => nesting -= 2,
+            Token::Ident(_)
+                if brace_depth == 1
+                    && nesting == 0
+                    && current.is_none()
+                    && matches!(tokens.get(index + 1), Some((Token::Syntax(":"), _))) =>
+            {
+                current = Some(span.clone());
+            }
+            _ => {}
+        }
+
+        last_end = span.end;
+    }
+
+    // Naga's struct spans end before the closing brace
+    if let Some(name) = current {
+        members.push((name.start..last_end, name));
+    }
+
+    members
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -40,4 +152,48 @@ mod test {
         let src = "pub fn function_name(param:Type) { if (condition) { expr } }";
         assert_eq!(matching_bracket_index(src, 0), Some(src.len() - 1));
     }
+
+    #[test]
+    pub fn ident_in_range() {
+        let src = "const a = 1;\nconst b = a;";
+        assert_eq!(find_ident(src, 13..src.len(), "a"), Some(23..24));
+    }
+
+    #[test]
+    pub fn ident_at_offset() {
+        let src = "var<uniform> light: Light;";
+        assert_eq!(ident_at(src, 15), Some(("light", 13..18)));
+        assert_eq!(ident_at(src, 18), Some(("light", 13..18)));
+        assert_eq!(ident_at(src, 12), None);
+    }
+
+    #[test]
+    pub fn function_declaration() {
+        let src = "@vertex\nfn main() -> @builtin(position) vec4<f32> { return vec4(0.0); }";
+        let (decl, name) = find_declaration(src, Keyword::Fn, "main").unwrap();
+        assert_eq!(&src[decl], &src[8..]);
+        assert_eq!(&src[name], "main");
+    }
+
+    #[test]
+    pub fn alias_declaration() {
+        let src = "alias Vec = vec3<f32>;\nfn f() {}";
+        let (decl, name) = find_declaration(src, Keyword::Alias, "Vec").unwrap();
+        assert_eq!(&src[decl], "alias Vec = vec3<f32>;");
+        assert_eq!(&src[name], "Vec");
+    }
+
+    #[test]
+    pub fn struct_members() {
+        let src = "struct S {\n    a: array<f32, 4>,\n    @align(16) b: vec2<f32>,\n}";
+        let members: Vec<_> = find_struct_members(src, 0..src.len())
+            .into_iter()
+            .map(|(decl, name)| (&src[decl], &src[name]))
+            .collect();
+
+        assert_eq!(
+            members,
+            vec![("a: array<f32, 4>", "a"), ("b: vec2<f32>", "b")]
+        );
+    }
 }
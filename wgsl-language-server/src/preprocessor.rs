@@ -0,0 +1,201 @@
+// Conditional compilation with shader defs, as supported by naga_oil and Bevy:
+// `#ifdef NAME`, `#ifndef NAME`, `#if NAME`, `#if NAME == value`, `#else` and `#endif`.
+// Directives and inactive lines are replaced by spaces instead of being removed, so spans in the
+// output are the same as in the source. `#import` and `#define_import_path` are blanked as well,
+// since naga does not know about them.
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::config::ShaderDef;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PreprocessError {
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+struct Branch {
+    // Whether the lines of the current branch are kept
+    active: bool,
+    // Whether the enclosing branch is kept
+    parent_active: bool,
+    has_else: bool,
+    start: Range<usize>,
+}
+
+fn evaluate(condition: &str, defs: &HashMap<String, ShaderDef>) -> Result<bool, String> {
+    let parts: Vec<_> = condition.split_whitespace().collect();
+
+    let (name, op, expected) = match parts.as_slice() {
+        [name] => return Ok(matches!(defs.get(*name), Some(ShaderDef::Bool(true)))),
+        [name, op, expected] => (*name, *op, *expected),
+        _ => return Err(format!("Invalid condition `{condition}`")),
+    };
+
+    let Some(value) = defs.get(name) else {
+        return Err(format!("Shader def `{name}` is not defined"));
+    };
+
+    let expected = match value {
+        ShaderDef::Bool(_) => expected.parse().map(ShaderDef::Bool).ok(),
+        ShaderDef::Int(_) => expected.parse().map(ShaderDef::Int).ok(),
+    }
+    .ok_or_else(|| format!("`{expected}` can not be compared with `{name}`"))?;
+
+    match op {
+        "==" => Ok(*value == expected),
+        "!=" => Ok(*value != expected),
+        _ => Err(format!("Unknown operator `{op}`")),
+    }
+}
+
+// Keeps line breaks, and replaces everything else by as many spaces as it has bytes
+fn blank(line: &str) -> String {
+    line.chars()
+        .map(|char| match char {
+            '\n' | '\r' => char.to_string(),
+            _ => " ".repeat(char.len_utf8()),
+        })
+        .collect()
+}
+
+/// Evaluates the directives in `source`, returning code with the same spans for naga to parse.
+pub fn preprocess(
+    source: &str,
+    defs: &HashMap<String, ShaderDef>,
+) -> (String, Vec<PreprocessError>) {
+    // Nothing to do for plain WGSL, which never uses `#`
+    if !source.contains('#') {
+        return (source.to_owned(), vec![]);
+    }
+
+    let mut output = String::with_capacity(source.len());
+    let mut errors = vec![];
+    let mut branches: Vec<Branch> = vec![];
+    let mut offset = 0;
+
+    for line in source.split_inclusive('\n') {
+        let range = offset..offset + line.trim_end().len();
+        offset += line.len();
+
+        let active = branches.last().is_none_or(|it| it.active);
+        let trimmed = line.trim();
+
+        let Some(directive) = trimmed.strip_prefix('#') else {
+            output += &match active {
+                true => line.to_owned(),
+                false => blank(line),
+            };
+            continue;
+        };
+
+        let (name, argument) = directive
+            .split_once(char::is_whitespace)
+            .map_or((directive, ""), |(name, argument)| (name, argument.trim()));
+
+        let mut error = |message: String| {
+            errors.push(PreprocessError {
+                range: range.clone(),
+                message,
+            })
+        };
+
+        match name {
+            "ifdef" | "ifndef" | "if" => {
+                let condition = match name {
+                    "ifdef" => Ok(defs.contains_key(argument)),
+                    "ifndef" => Ok(!defs.contains_key(argument)),
+                    _ => evaluate(argument, defs),
+                };
+
+                let condition = condition.unwrap_or_else(|message| {
+                    error(message);
+                    false
+                });
+
+                branches.push(Branch {
+                    active: active && condition,
+                    parent_active: active,
+                    has_else: false,
+                    start: range.clone(),
+                });
+            }
+            "else" => match branches.last_mut() {
+                Some(branch) if !branch.has_else => {
+                    branch.has_else = true;
+                    branch.active = branch.parent_active && !branch.active;
+                }
+                Some(_) => error("Multiple `#else` directives for the same `#if`".to_owned()),
+                None => error("`#else` without a matching `#if`".to_owned()),
+            },
+            "endif" => {
+                if branches.pop().is_none() {
+                    error("`#endif` without a matching `#if`".to_owned());
+                }
+            }
+            "import" | "define_import_path" => {}
+            _ => error(format!("Unknown directive `#{name}`")),
+        }
+
+        output += &blank(line);
+    }
+
+    for branch in branches {
+        errors.push(PreprocessError {
+            range: branch.start,
+            message: "Missing `#endif`".to_owned(),
+        });
+    }
+
+    (output, errors)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn defs() -> HashMap<String, ShaderDef> {
+        HashMap::from([
+            ("SHADOWS".to_owned(), ShaderDef::Bool(true)),
+            ("MAX_LIGHTS".to_owned(), ShaderDef::Int(4)),
+        ])
+    }
+
+    #[test]
+    pub fn conditional_lines_are_blanked() {
+        let source = "#ifdef SHADOWS\na\n#else\n// é\n#endif\n#if MAX_LIGHTS == 2\nc\n#endif\nd";
+        let (output, errors) = preprocess(source, &defs());
+
+        assert!(errors.is_empty());
+        assert_eq!(output.len(), source.len());
+        assert_eq!(
+            output.split('\n').map(str::trim).collect::<Vec<_>>(),
+            vec!["", "a", "", "", "", "", "", "", "d"]
+        );
+    }
+
+    #[test]
+    pub fn nested_inactive_branches() {
+        let source = "#ifndef SHADOWS\n#ifdef MAX_LIGHTS\na\n#else\nb\n#endif\n#endif\nc";
+        let (output, _) = preprocess(source, &defs());
+
+        assert_eq!(output.trim(), "c");
+    }
+
+    #[test]
+    pub fn directive_errors() {
+        let (_, errors) = preprocess("#if\n#else\n#else\n#endif\n#endif\n#ifdef A", &defs());
+        let messages: Vec<_> = errors.iter().map(|it| it.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid condition ``",
+                "Multiple `#else` directives for the same `#if`",
+                "`#endif` without a matching `#if`",
+                "Missing `#endif`",
+            ]
+        );
+        assert_eq!(errors[3].range, 30..38);
+    }
+}
@@ -3,12 +3,20 @@ mod ident_query;
 pub mod index_impl;
 pub mod type_print;
 
-use crate::block_ext::BlockExt;
+use crate::{
+    block_ext::BlockExt,
+    layout::{LayoutViolation, StructLayout, layouter},
+    parser::find_struct_members,
+    range_tools::range_to_span,
+};
 use as_type::AsType;
 use codespan_reporting::diagnostic::Diagnostic;
 use naga::{
-    Expression, Function, Handle, Module, Statement, WithSpan,
-    valid::{CallError, ExpressionError, FunctionError, ValidationError},
+    AddressSpace, Expression, Function, GlobalVariable, Handle, Module, Statement, Type, WithSpan,
+    valid::{
+        CallError, Disalignment, ExpressionError, FunctionError, GlobalVariableError,
+        ValidationError,
+    },
 };
 use type_print::TypePrintable;
 
@@ -43,12 +51,74 @@ impl<'a> ModuleContext<'a> {
                 name: _,
                 source,
             } => self.function_error_diagnostic(Diagnostic::error(), *handle, source),
+            ValidationError::GlobalVariable {
+                handle,
+                name: _,
+                source: GlobalVariableError::Alignment(space, ty, disalignment),
+            } => self
+                .alignment_error_diagnostic(*handle, *space, *ty, disalignment)
+                .unwrap_or_else(|| Diagnostic::error().with_message(error.to_string())),
             other => {
                 Diagnostic::error().with_message("UNIMPLEMENTED: ".to_string() + &other.to_string())
             }
         }
     }
 
+    fn alignment_error_diagnostic(
+        &self,
+        global: Handle<GlobalVariable>,
+        space: AddressSpace,
+        ty: Handle<Type>,
+        disalignment: &Disalignment,
+    ) -> Option<Diagnostic<()>> {
+        let space = match space {
+            AddressSpace::Uniform => "uniform",
+            AddressSpace::Storage { .. } => "storage",
+            _ => return None,
+        };
+
+        let root = self.module.global_variables[global].ty;
+        let (struct_handle, violation) =
+            LayoutViolation::from_disalignment(self.module, root, ty, disalignment)?;
+
+        let layouter = layouter(self.module)?;
+        let layout =
+            struct_handle.and_then(|handle| StructLayout::new(self.module, &layouter, handle));
+
+        let global_span = self.module.global_variables.get_span(global);
+
+        // Point at the offending struct member if we can find it, otherwise at the variable
+        let member_span = struct_handle
+            .zip(violation.member())
+            .zip(layout.as_ref())
+            .and_then(|((handle, index), layout)| {
+                let body = self.module.types.get_span(handle).to_range()?;
+                let member = &layout.members[index];
+                find_struct_members(self.code, body)
+                    .into_iter()
+                    .find(|(_, name)| self.code[name.clone()] == member.name)
+                    .map(|(declaration, _)| range_to_span(declaration))
+            });
+
+        let message = violation.message(layout.as_ref(), space);
+
+        let diagnostic = match member_span {
+            Some(member_span) => Diagnostic::error()
+                .with_label(label_primary!(&member_span, "{message}"))
+                .with_label(label!(
+                    &global_span,
+                    "Required by this variable in the {space} address space"
+                )),
+            None => Diagnostic::error().with_label(label_primary!(&global_span, "{message}")),
+        };
+
+        Some(
+            diagnostic
+                .with_message(message.clone())
+                .with_notes(vec![violation.suggestion(layout.as_ref())]),
+        )
+    }
+
     pub fn function_ctx(&'a self, function: &'a Function) -> FunctionContext<'a> {
         FunctionContext {
             error_ctx: self,
@@ -56,7 +126,10 @@ impl<'a> ModuleContext<'a> {
         }
     }
 
-    pub fn function_ctx_from_handle(&self, function_handle: Handle<Function>) -> FunctionContext {
+    pub fn function_ctx_from_handle(
+        &self,
+        function_handle: Handle<Function>,
+    ) -> FunctionContext<'_> {
         self.function_ctx(&self.module.functions[function_handle])
     }
 
@@ -15,36 +15,51 @@ pub fn string_range(string: &str, range: lsp_types::Range) -> std::ops::Range<us
     string_offset(string, &range.start)..string_offset(string, &range.end)
 }
 
+/// The byte offset of a position, whose character is counted in UTF-16 code units as LSP expects.
+/// Positions past the end of a line or of the string are clamped to it, and positions inside a
+/// character to the end of it.
 pub fn string_offset(string: &str, position: &Position) -> usize {
-    let mut res = 0;
+    let mut line_start = 0;
 
-    for (index, line) in string.lines().enumerate() {
-        if index == position.line as usize {
-            return res + position.character as usize;
+    for _ in 0..position.line {
+        match string[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return string.len(),
         }
-        res += line.len() + 1;
     }
 
-    0
+    let line = string[line_start..].split('\n').next().unwrap_or_default();
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut character = 0;
+
+    for (index, char) in line.char_indices() {
+        if character >= position.character as usize {
+            return line_start + index;
+        }
+        character += char.len_utf16();
+    }
+
+    line_start + line.len()
 }
 
+/// The position of a byte offset, with the character counted in UTF-16 code units as LSP expects.
 pub fn position_at_byte_offset(source: &str, byte_offset: usize) -> Position {
     let mut abs_offset = 0;
     let mut character = 0;
     let mut line = 0;
 
     for char in source.chars() {
+        if abs_offset >= byte_offset {
+            break;
+        }
+
         abs_offset += char.len_utf8();
 
         if char == '\n' {
             line += 1;
             character = 0;
         } else {
-            character += char.len_utf8();
-        }
-
-        if abs_offset >= byte_offset {
-            break;
+            character += char.len_utf16();
         }
     }
 
@@ -86,3 +101,43 @@ pub fn new_location(range: std::ops::Range<usize>, source: &str, uri: lsp_types:
         range: lsp_types::Range { start, end },
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn offsets_of_positions() {
+        let source = "é😀a\r\nb";
+
+        assert_eq!(string_offset(source, &Position::new(0, 1)), 2);
+        assert_eq!(string_offset(source, &Position::new(0, 3)), 6);
+        assert_eq!(string_offset(source, &Position::new(0, 2)), 6);
+        assert_eq!(string_offset(source, &Position::new(0, 9)), 7);
+        assert_eq!(string_offset(source, &Position::new(1, 1)), 10);
+        assert_eq!(string_offset(source, &Position::new(1, 9)), 10);
+        assert_eq!(string_offset(source, &Position::new(5, 0)), 10);
+        assert_eq!(string_offset("a\n", &Position::new(1, 0)), 2);
+    }
+
+    #[test]
+    pub fn positions_of_byte_offsets() {
+        let source = "ab\ncd";
+
+        assert_eq!(position_at_byte_offset(source, 0), Position::new(0, 0));
+        assert_eq!(position_at_byte_offset(source, 1), Position::new(0, 1));
+        assert_eq!(position_at_byte_offset(source, 2), Position::new(0, 2));
+        assert_eq!(position_at_byte_offset(source, 3), Position::new(1, 0));
+        assert_eq!(position_at_byte_offset(source, 5), Position::new(1, 2));
+    }
+
+    #[test]
+    pub fn positions_count_utf16_code_units() {
+        let source = "é😀a\nb";
+
+        assert_eq!(position_at_byte_offset(source, 2), Position::new(0, 1));
+        assert_eq!(position_at_byte_offset(source, 6), Position::new(0, 3));
+        assert_eq!(position_at_byte_offset(source, 7), Position::new(0, 4));
+        assert_eq!(position_at_byte_offset(source, 9), Position::new(1, 1));
+    }
+}
@@ -0,0 +1,369 @@
+// Reflection of the resources bound by a module, used to validate pipeline layouts ahead of time.
+// Names follow the WebGPU `GPUBindGroupLayoutEntry` terminology where possible.
+
+use naga::{
+    AddressSpace, ArraySize, ImageClass, ImageDimension, Module, ScalarKind, StorageAccess,
+    StorageFormat, TypeInner, valid::ModuleInfo,
+};
+use serde::Serialize;
+
+use crate::{interface::shader_stage_str, layout::layouter};
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BindGroupReflection {
+    pub group: u32,
+    pub bindings: Vec<BindingReflection>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingReflection {
+    pub binding: u32,
+    pub name: String,
+    pub type_name: String,
+    pub resource: Resource,
+    // Number of bindings in a `binding_array`, `None` for a single binding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    pub visibility: Vec<&'static str>,
+    pub entry_points: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Resource {
+    #[serde(rename_all = "camelCase")]
+    Uniform {
+        min_binding_size: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    Storage {
+        access: &'static str,
+        min_binding_size: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    Texture {
+        sample_type: &'static str,
+        view_dimension: &'static str,
+        multisampled: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    StorageTexture {
+        format: &'static str,
+        access: &'static str,
+        view_dimension: &'static str,
+    },
+    Sampler {
+        comparison: bool,
+    },
+    AccelerationStructure,
+}
+
+/// Reflects every global with a `@group` and `@binding`, grouped and sorted by group and binding.
+pub fn reflect_bind_groups(module: &Module, info: &ModuleInfo) -> Vec<BindGroupReflection> {
+    let Some(layouter) = layouter(module) else {
+        return vec![];
+    };
+
+    let mut groups: Vec<BindGroupReflection> = vec![];
+
+    for (handle, global) in module.global_variables.iter() {
+        let Some(binding) = &global.binding else {
+            continue;
+        };
+
+        let (ty, count) = match module.types[global.ty].inner {
+            TypeInner::BindingArray { base, size } => (
+                base,
+                Some(match size {
+                    ArraySize::Constant(size) => size.get(),
+                    _ => 0,
+                }),
+            ),
+            _ => (global.ty, None),
+        };
+
+        let resource = match (global.space, &module.types[ty].inner) {
+            (AddressSpace::Uniform, _) => Resource::Uniform {
+                min_binding_size: layouter[ty].size,
+            },
+            (AddressSpace::Storage { access }, _) => Resource::Storage {
+                access: storage_access_str(access),
+                min_binding_size: layouter[ty].size,
+            },
+            (
+                _,
+                TypeInner::Image {
+                    dim,
+                    arrayed,
+                    class,
+                },
+            ) => match *class {
+                ImageClass::Sampled { kind, multi } => Resource::Texture {
+                    sample_type: match kind {
+                        ScalarKind::Sint => "sint",
+                        ScalarKind::Uint => "uint",
+                        _ => "float",
+                    },
+                    view_dimension: view_dimension_str(*dim, *arrayed),
+                    multisampled: multi,
+                },
+                ImageClass::Depth { multi } => Resource::Texture {
+                    sample_type: "depth",
+                    view_dimension: view_dimension_str(*dim, *arrayed),
+                    multisampled: multi,
+                },
+                ImageClass::Storage { format, access } => Resource::StorageTexture {
+                    format: storage_format_str(format),
+                    access: storage_access_str(access),
+                    view_dimension: view_dimension_str(*dim, *arrayed),
+                },
+            },
+            (_, TypeInner::Sampler { comparison }) => Resource::Sampler {
+                comparison: *comparison,
+            },
+            (_, TypeInner::AccelerationStructure) => Resource::AccelerationStructure,
+            _ => continue,
+        };
+
+        let mut visibility = vec![];
+        let mut entry_points = vec![];
+        for (index, entry_point) in module.entry_points.iter().enumerate() {
+            if info.get_entry_point(index)[handle].is_empty() {
+                continue;
+            }
+
+            let stage = shader_stage_str(entry_point.stage);
+            if !visibility.contains(&stage) {
+                visibility.push(stage);
+            }
+            entry_points.push(entry_point.name.clone());
+        }
+
+        let reflection = BindingReflection {
+            binding: binding.binding,
+            name: global.name.clone().unwrap_or_default(),
+            type_name: global.ty.to_wgsl(&module.to_ctx()),
+            resource,
+            count,
+            visibility,
+            entry_points,
+        };
+
+        match groups.iter_mut().find(|it| it.group == binding.group) {
+            Some(group) => group.bindings.push(reflection),
+            None => groups.push(BindGroupReflection {
+                group: binding.group,
+                bindings: vec![reflection],
+            }),
+        }
+    }
+
+    groups.sort_by_key(|group| group.group);
+    for group in &mut groups {
+        group.bindings.sort_by_key(|binding| binding.binding);
+    }
+
+    groups
+}
+
+fn storage_access_str(access: StorageAccess) -> &'static str {
+    if access.contains(StorageAccess::STORE) && access.contains(StorageAccess::LOAD) {
+        "read_write"
+    } else if access.contains(StorageAccess::STORE) {
+        "write"
+    } else {
+        "read"
+    }
+}
+
+fn view_dimension_str(dim: ImageDimension, arrayed: bool) -> &'static str {
+    match (dim, arrayed) {
+        (ImageDimension::D1, _) => "1d",
+        (ImageDimension::D2, false) => "2d",
+        (ImageDimension::D2, true) => "2d-array",
+        (ImageDimension::D3, _) => "3d",
+        (ImageDimension::Cube, false) => "cube",
+        (ImageDimension::Cube, true) => "cube-array",
+    }
+}
+
+fn storage_format_str(format: StorageFormat) -> &'static str {
+    match format {
+        StorageFormat::R8Unorm => "r8unorm",
+        StorageFormat::R8Snorm => "r8snorm",
+        StorageFormat::R8Uint => "r8uint",
+        StorageFormat::R8Sint => "r8sint",
+        StorageFormat::R16Uint => "r16uint",
+        StorageFormat::R16Sint => "r16sint",
+        StorageFormat::R16Float => "r16float",
+        StorageFormat::Rg8Unorm => "rg8unorm",
+        StorageFormat::Rg8Snorm => "rg8snorm",
+        StorageFormat::Rg8Uint => "rg8uint",
+        StorageFormat::Rg8Sint => "rg8sint",
+        StorageFormat::R32Uint => "r32uint",
+        StorageFormat::R32Sint => "r32sint",
+        StorageFormat::R32Float => "r32float",
+        StorageFormat::Rg16Uint => "rg16uint",
+        StorageFormat::Rg16Sint => "rg16sint",
+        StorageFormat::Rg16Float => "rg16float",
+        StorageFormat::Rgba8Unorm => "rgba8unorm",
+        StorageFormat::Rgba8Snorm => "rgba8snorm",
+        StorageFormat::Rgba8Uint => "rgba8uint",
+        StorageFormat::Rgba8Sint => "rgba8sint",
+        StorageFormat::Bgra8Unorm => "bgra8unorm",
+        StorageFormat::Rgb10a2Uint => "rgb10a2uint",
+        StorageFormat::Rgb10a2Unorm => "rgb10a2unorm",
+        StorageFormat::Rg11b10Ufloat => "rg11b10ufloat",
+        StorageFormat::R64Uint => "r64uint",
+        StorageFormat::Rg32Uint => "rg32uint",
+        StorageFormat::Rg32Sint => "rg32sint",
+        StorageFormat::Rg32Float => "rg32float",
+        StorageFormat::Rgba16Uint => "rgba16uint",
+        StorageFormat::Rgba16Sint => "rgba16sint",
+        StorageFormat::Rgba16Float => "rgba16float",
+        StorageFormat::Rgba32Uint => "rgba32uint",
+        StorageFormat::Rgba32Sint => "rgba32sint",
+        StorageFormat::Rgba32Float => "rgba32float",
+        StorageFormat::R16Unorm => "r16unorm",
+        StorageFormat::R16Snorm => "r16snorm",
+        StorageFormat::Rg16Unorm => "rg16unorm",
+        StorageFormat::Rg16Snorm => "rg16snorm",
+        StorageFormat::Rgba16Unorm => "rgba16unorm",
+        StorageFormat::Rgba16Snorm => "rgba16snorm",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+    use super::*;
+
+    fn reflect(src: &str) -> Vec<BindGroupReflection> {
+        let module = naga::front::wgsl::parse_str(src).unwrap();
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .unwrap();
+        reflect_bind_groups(&module, &info)
+    }
+
+    const SHADER: &str = "
+struct Camera { view_proj: mat4x4<f32>, position: vec3<f32> }
+struct Particles { count: u32, positions: array<vec4<f32>> }
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(1) var color_sampler: sampler;
+@group(1) @binding(0) var color: texture_2d<f32>;
+@group(1) @binding(2) var shadow: texture_depth_2d_array;
+@group(1) @binding(3) var shadow_sampler: sampler_comparison;
+@group(2) @binding(0) var<storage, read_write> particles: Particles;
+@group(2) @binding(1) var output: texture_storage_2d<rgba8unorm, write>;
+@group(2) @binding(2) var<storage> unused: array<u32>;
+
+fn project(p: vec4<f32>) -> vec4<f32> { return camera.view_proj * p; }
+
+@vertex fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    return project(vec4<f32>(f32(i)));
+}
+
+@fragment fn fs_main() -> @location(0) vec4<f32> {
+    let depth = textureSampleCompare(shadow, shadow_sampler, vec2<f32>(0.0), 0, 0.5);
+    return textureSample(color, color_sampler, vec2<f32>(0.0)) * depth + vec4<f32>(camera.position, 1.0);
+}
+
+@compute @workgroup_size(64) fn update(@builtin(global_invocation_id) id: vec3<u32>) {
+    particles.positions[id.x] += vec4<f32>(1.0);
+    textureStore(output, vec2<u32>(id.xy), vec4<f32>(0.0));
+}
+";
+
+    #[test]
+    pub fn groups_are_sorted() {
+        let groups = reflect(SHADER);
+        let bindings: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                let bindings: Vec<_> = group.bindings.iter().map(|it| it.binding).collect();
+                (group.group, bindings)
+            })
+            .collect();
+
+        assert_eq!(
+            bindings,
+            vec![(0, vec![0]), (1, vec![0, 1, 2, 3]), (2, vec![0, 1, 2])]
+        );
+    }
+
+    #[test]
+    pub fn buffers() {
+        let groups = reflect(SHADER);
+
+        assert_eq!(
+            groups[0].bindings[0].resource,
+            Resource::Uniform {
+                min_binding_size: 80
+            }
+        );
+        assert_eq!(
+            groups[2].bindings[0].resource,
+            Resource::Storage {
+                access: "read_write",
+                min_binding_size: 32
+            }
+        );
+        assert_eq!(
+            groups[2].bindings[2].resource,
+            Resource::Storage {
+                access: "read",
+                min_binding_size: 4
+            }
+        );
+    }
+
+    #[test]
+    pub fn textures_and_samplers() {
+        let groups = reflect(SHADER);
+        let resources: Vec<_> = groups[1].bindings.iter().map(|it| &it.resource).collect();
+
+        assert_eq!(
+            resources,
+            vec![
+                &Resource::Texture {
+                    sample_type: "float",
+                    view_dimension: "2d",
+                    multisampled: false
+                },
+                &Resource::Sampler { comparison: false },
+                &Resource::Texture {
+                    sample_type: "depth",
+                    view_dimension: "2d-array",
+                    multisampled: false
+                },
+                &Resource::Sampler { comparison: true },
+            ]
+        );
+
+        assert_eq!(
+            groups[2].bindings[1].resource,
+            Resource::StorageTexture {
+                format: "rgba8unorm",
+                access: "write",
+                view_dimension: "2d"
+            }
+        );
+    }
+
+    #[test]
+    pub fn visibility_includes_called_functions() {
+        let groups = reflect(SHADER);
+
+        let camera = &groups[0].bindings[0];
+        assert_eq!(camera.visibility, vec!["vertex", "fragment"]);
+        assert_eq!(camera.entry_points, vec!["vs_main", "fs_main"]);
+
+        assert_eq!(groups[2].bindings[0].visibility, vec!["compute"]);
+        assert!(groups[2].bindings[2].visibility.is_empty());
+    }
+}
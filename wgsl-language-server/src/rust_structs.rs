@@ -0,0 +1,501 @@
+// Generation of Rust struct definitions matching the memory layout of WGSL structs
+
+use std::fmt::Write;
+
+use naga::{
+    ArraySize, Handle, Module, Scalar, ScalarKind, Type, TypeInner, VectorSize, proc::Layouter,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{StructLayout, layouter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RustStructStyle {
+    // `#[repr(C)]` structs with explicit padding fields deriving `bytemuck::Pod`
+    Bytemuck,
+    // Structs deriving `encase::ShaderType`, which computes the layout itself
+    Encase,
+}
+
+impl std::str::FromStr for RustStructStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytemuck" => Ok(RustStructStyle::Bytemuck),
+            "encase" => Ok(RustStructStyle::Encase),
+            other => Err(format!(
+                "Unknown struct style `{other}`, expected `bytemuck` or `encase`"
+            )),
+        }
+    }
+}
+
+// Without this feature, bytemuck only implements `Pod` for arrays of some lengths
+const BYTEMUCK_HEADER: &str = "\
+// Padding and arrays of any length need the `min_const_generics` feature of bytemuck
+";
+
+// Naga applies `@align` and `@size` to member offsets without keeping the attributes
+const ENCASE_ALIGN_HEADER: &str = "\
+// `@align` is not carried over, as it can not be told apart from `@size` once parsed. Gaps
+// between members are kept with `#[size]`, but members declared with `@align(N)` need
+// `#[align(N)]` for the struct to have the same alignment
+";
+
+/// Generates Rust definitions for the struct `name`, or every host-shareable struct in the module.
+/// Structs referenced by the selected struct are included, so the output is self-contained.
+pub fn generate_rust_structs(
+    module: &Module,
+    name: Option<&str>,
+    style: RustStructStyle,
+) -> Result<String, String> {
+    let layouter = layouter(module).ok_or("Failed to compute type layouts")?;
+
+    let mut selected = vec![];
+    match name {
+        Some(name) => {
+            let (handle, _) = module
+                .types
+                .iter()
+                .find(|(_, ty)| {
+                    ty.name.as_deref() == Some(name) && matches!(ty.inner, TypeInner::Struct { .. })
+                })
+                .ok_or_else(|| format!("No struct named `{name}` found"))?;
+
+            if !is_host_shareable(module, handle) {
+                return Err(format!(
+                    "Struct `{name}` is not host-shareable, so it can not be shared with the host"
+                ));
+            }
+
+            collect_dependencies(module, handle, &mut selected);
+        }
+        None => {
+            for (handle, ty) in module.types.iter() {
+                if matches!(ty.inner, TypeInner::Struct { .. }) && is_host_shareable(module, handle)
+                {
+                    collect_dependencies(module, handle, &mut selected);
+                }
+            }
+        }
+    }
+
+    // Types are always declared after their dependencies within the arena
+    selected.sort_by_key(|handle| handle.index());
+
+    // Whether a member is placed after its natural offset by an explicit `@size` or `@align`
+    let mut explicit_layout = false;
+    let structs = selected
+        .into_iter()
+        .map(|handle| {
+            let layout = StructLayout::new(module, &layouter, handle)
+                .ok_or("Failed to compute struct layout")?;
+            match style {
+                RustStructStyle::Bytemuck => bytemuck_struct(module, &layouter, &layout),
+                RustStructStyle::Encase => encase_struct(module, &layout, &mut explicit_layout),
+            }
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let header = match style {
+        RustStructStyle::Bytemuck => BYTEMUCK_HEADER,
+        RustStructStyle::Encase if explicit_layout => ENCASE_ALIGN_HEADER,
+        RustStructStyle::Encase => "",
+    };
+
+    if header.is_empty() {
+        return Ok(structs.join("\n"));
+    }
+    Ok(format!("{header}\n{}", structs.join("\n")))
+}
+
+pub fn is_host_shareable(module: &Module, ty: Handle<Type>) -> bool {
+    match &module.types[ty].inner {
+        TypeInner::Scalar(scalar) | TypeInner::Vector { scalar, .. } => {
+            scalar.kind != ScalarKind::Bool
+        }
+        TypeInner::Matrix { .. } | TypeInner::Atomic(_) => true,
+        TypeInner::Array { base, .. } => is_host_shareable(module, *base),
+        TypeInner::Struct { members, .. } => members
+            .iter()
+            .all(|member| is_host_shareable(module, member.ty)),
+        _ => false,
+    }
+}
+
+fn collect_dependencies(module: &Module, ty: Handle<Type>, structs: &mut Vec<Handle<Type>>) {
+    match &module.types[ty].inner {
+        TypeInner::Array { base, .. } => collect_dependencies(module, *base, structs),
+        TypeInner::Struct { members, .. } if !structs.contains(&ty) => {
+            for member in members {
+                collect_dependencies(module, member.ty, structs);
+            }
+            structs.push(ty);
+        }
+        _ => {}
+    }
+}
+
+fn scalar_type(scalar: Scalar) -> Result<&'static str, String> {
+    match (scalar.kind, scalar.width) {
+        (ScalarKind::Float, 2) => Ok("half::f16"),
+        (ScalarKind::Float, 4) => Ok("f32"),
+        (ScalarKind::Float, 8) => Ok("f64"),
+        (ScalarKind::Sint, 4) => Ok("i32"),
+        (ScalarKind::Sint, 8) => Ok("i64"),
+        (ScalarKind::Uint, 4) => Ok("u32"),
+        (ScalarKind::Uint, 8) => Ok("u64"),
+        (kind, width) => Err(format!(
+            "Scalar {kind:?} of width {width} can not be shared with the host"
+        )),
+    }
+}
+
+// Plain Rust arrays are used since they have no alignment requirements beyond their scalars,
+// which allows the WGSL layout to be reproduced exactly using explicit padding
+fn bytemuck_type(module: &Module, layouter: &Layouter, ty: Handle<Type>) -> Result<String, String> {
+    let ty_info = &module.types[ty];
+    Ok(match &ty_info.inner {
+        TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => scalar_type(*scalar)?.to_owned(),
+        TypeInner::Vector { size, scalar } => {
+            format!("[{}; {}]", scalar_type(*scalar)?, *size as u8)
+        }
+        // Columns are padded to the alignment of the column vector, so `vec3` columns take up four scalars
+        TypeInner::Matrix {
+            columns,
+            rows,
+            scalar,
+        } => {
+            let rows = match rows {
+                VectorSize::Tri => 4,
+                rows => *rows as u8,
+            };
+            format!("[[{}; {rows}]; {}]", scalar_type(*scalar)?, *columns as u8)
+        }
+        TypeInner::Array {
+            base,
+            size: ArraySize::Constant(count),
+            stride,
+        } => {
+            let element = match module.types[*base].inner {
+                // The stride of a `vec3` array includes a scalar of padding
+                TypeInner::Vector {
+                    size: VectorSize::Tri,
+                    scalar,
+                } if *stride != layouter[*base].size => format!("[{}; 4]", scalar_type(scalar)?),
+                _ => bytemuck_type(module, layouter, *base)?,
+            };
+            format!("[{element}; {count}]")
+        }
+        TypeInner::Array { .. } => {
+            return Err("Runtime-sized arrays can not be represented in a bytemuck struct".into());
+        }
+        TypeInner::Struct { .. } => ty_info.name.clone().unwrap_or_default(),
+        other => return Err(format!("Type {other:?} can not be shared with the host")),
+    })
+}
+
+fn bytemuck_struct(
+    module: &Module,
+    layouter: &Layouter,
+    layout: &StructLayout,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut offset = 0;
+    let mut runtime_sized = false;
+    let mut padding_fields = 0;
+    let mut padding = |out: &mut String, bytes: u32| {
+        let _ = writeln!(out, "    pub _pad{padding_fields}: [u8; {bytes}],");
+        padding_fields += 1;
+    };
+
+    let _ = writeln!(out, "#[repr(C)]");
+    let _ = writeln!(
+        out,
+        "#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]"
+    );
+    let _ = writeln!(out, "pub struct {} {{", layout.name);
+
+    for member in &layout.members {
+        if let TypeInner::Array {
+            size: ArraySize::Dynamic,
+            ..
+        } = module.types[member.ty].inner
+        {
+            if member.offset > offset {
+                padding(&mut out, member.offset - offset);
+            }
+            let _ = writeln!(
+                out,
+                "    // `{}: {}` is runtime-sized and follows at offset {}",
+                member.name, member.ty_name, member.offset
+            );
+            offset = member.offset;
+            runtime_sized = true;
+            break;
+        }
+
+        if member.offset > offset {
+            padding(&mut out, member.offset - offset);
+        }
+
+        let _ = writeln!(
+            out,
+            "    pub {}: {},",
+            member.name,
+            bytemuck_type(module, layouter, member.ty)?
+        );
+        offset = member.offset + member.size;
+    }
+
+    // Only the sized part of a struct ending in a runtime-sized array can be represented
+    let size = if runtime_sized { offset } else { layout.size };
+    if size > offset {
+        padding(&mut out, size - offset);
+    }
+
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(
+        out,
+        "\nconst _: () = assert!(std::mem::size_of::<{}>() == {size});",
+        layout.name
+    );
+
+    Ok(out)
+}
+
+fn encase_type(module: &Module, ty: Handle<Type>) -> Result<String, String> {
+    let ty_info = &module.types[ty];
+    let glam_prefix = |scalar: Scalar| match (scalar.kind, scalar.width) {
+        (ScalarKind::Float, 4) => Ok(""),
+        (ScalarKind::Sint, 4) => Ok("I"),
+        (ScalarKind::Uint, 4) => Ok("U"),
+        (ScalarKind::Float, 8) => Ok("D"),
+        _ => Err(format!("Vectors of {scalar:?} are not supported by glam")),
+    };
+
+    Ok(match &ty_info.inner {
+        TypeInner::Scalar(scalar) => scalar_type(*scalar)?.to_owned(),
+        TypeInner::Atomic(scalar) => format!("encase::Atomic<{}>", scalar_type(*scalar)?),
+        TypeInner::Vector { size, scalar } => {
+            format!("glam::{}Vec{}", glam_prefix(*scalar)?, *size as u8)
+        }
+        TypeInner::Matrix {
+            columns,
+            rows,
+            scalar,
+        } if columns == rows && glam_prefix(*scalar)?.is_empty() => {
+            format!("glam::Mat{}", *columns as u8)
+        }
+        TypeInner::Array {
+            base,
+            size: ArraySize::Constant(count),
+            ..
+        } => format!("[{}; {count}]", encase_type(module, *base)?),
+        TypeInner::Array { base, .. } => format!("Vec<{}>", encase_type(module, *base)?),
+        TypeInner::Struct { .. } => ty_info.name.clone().unwrap_or_default(),
+        _ => {
+            return Err(format!(
+                "`{}` has no equivalent encase type",
+                ty.to_wgsl(&module.to_ctx())
+            ));
+        }
+    })
+}
+
+fn encase_struct(
+    module: &Module,
+    layout: &StructLayout,
+    explicit_layout: &mut bool,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let _ = writeln!(out, "#[derive(Debug, Clone, encase::ShaderType)]");
+    let _ = writeln!(out, "pub struct {} {{", layout.name);
+
+    for (index, member) in layout.members.iter().enumerate() {
+        let next_offset = layout
+            .members
+            .get(index + 1)
+            .map(|next| next.offset)
+            .unwrap_or(layout.size);
+
+        let is_runtime_sized = matches!(
+            module.types[member.ty].inner,
+            TypeInner::Array {
+                size: ArraySize::Dynamic,
+                ..
+            }
+        );
+
+        // Explicit `@size` and `@align` attributes show up as padding, which encase needs to be told about
+        if is_runtime_sized {
+            let _ = writeln!(out, "    #[size(runtime)]");
+        } else if member.padding > 0 && index + 1 < layout.members.len() {
+            let next = &layout.members[index + 1];
+            if next.offset != crate::layout::round_up(next.alignment, member.offset + member.size) {
+                let _ = writeln!(out, "    #[size({})]", next_offset - member.offset);
+                *explicit_layout = true;
+            }
+        } else if member.padding > 0 {
+            let natural_size =
+                crate::layout::round_up(layout.alignment, member.offset + member.size);
+            if natural_size != layout.size {
+                let _ = writeln!(out, "    #[size({})]", next_offset - member.offset);
+                *explicit_layout = true;
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "    pub {}: {},",
+            member.name,
+            encase_type(module, member.ty)?
+        );
+    }
+
+    let _ = writeln!(out, "}}");
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn generate(src: &str, name: Option<&str>, style: RustStructStyle) -> Result<String, String> {
+        let module = naga::front::wgsl::parse_str(src).unwrap();
+        generate_rust_structs(&module, name, style)
+    }
+
+    #[test]
+    pub fn bytemuck_padding() {
+        let res = generate(
+            "struct Light { position: vec3<f32>, color: vec3<f32>, intensity: f32 }",
+            Some("Light"),
+            RustStructStyle::Bytemuck,
+        )
+        .unwrap();
+
+        assert_eq!(
+            res,
+            "// Padding and arrays of any length need the `min_const_generics` feature of bytemuck
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Light {
+    pub position: [f32; 3],
+    pub _pad0: [u8; 4],
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+const _: () = assert!(std::mem::size_of::<Light>() == 32);
+"
+        );
+    }
+
+    #[test]
+    pub fn bytemuck_runtime_sized_array() {
+        let res = generate(
+            "struct Particles { count: u32, data: array<vec4<f32>> }",
+            Some("Particles"),
+            RustStructStyle::Bytemuck,
+        )
+        .unwrap();
+
+        assert_eq!(
+            res,
+            "// Padding and arrays of any length need the `min_const_generics` feature of bytemuck
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Particles {
+    pub count: u32,
+    pub _pad0: [u8; 12],
+    // `data: array<vec4<f32>>` is runtime-sized and follows at offset 16
+}
+
+const _: () = assert!(std::mem::size_of::<Particles>() == 16);
+"
+        );
+    }
+
+    #[test]
+    pub fn bytemuck_matrix_and_array() {
+        let res = generate(
+            "struct Bones { normal: mat3x3<f32>, offsets: array<vec3<f32>, 2> }",
+            Some("Bones"),
+            RustStructStyle::Bytemuck,
+        )
+        .unwrap();
+
+        assert!(res.contains("pub normal: [[f32; 4]; 3],"));
+        assert!(res.contains("pub offsets: [[f32; 4]; 2],"));
+        assert!(res.contains("size_of::<Bones>() == 80"));
+    }
+
+    #[test]
+    pub fn dependencies_are_included() {
+        let res = generate(
+            "struct Inner { a: f32 }\nstruct Outer { inner: Inner }\nstruct Unrelated { b: u32 }",
+            Some("Outer"),
+            RustStructStyle::Bytemuck,
+        )
+        .unwrap();
+
+        assert!(res.contains("pub struct Inner"));
+        assert!(res.contains("pub struct Outer"));
+        assert!(!res.contains("Unrelated"));
+        assert!(res.find("pub struct Inner") < res.find("pub struct Outer"));
+    }
+
+    #[test]
+    pub fn non_host_shareable_structs_are_skipped() {
+        let src = "struct Flags { enabled: bool }\nstruct Data { value: f32 }";
+
+        let all = generate(src, None, RustStructStyle::Bytemuck).unwrap();
+        assert!(!all.contains("Flags"));
+        assert!(all.contains("pub struct Data"));
+
+        assert!(generate(src, Some("Flags"), RustStructStyle::Bytemuck).is_err());
+    }
+
+    #[test]
+    pub fn encase_explicit_size() {
+        let res = generate(
+            "struct Particle { @size(32) mass: f32, velocity: vec3<f32>, particles: array<f32> }",
+            Some("Particle"),
+            RustStructStyle::Encase,
+        )
+        .unwrap();
+
+        assert!(res.starts_with(ENCASE_ALIGN_HEADER));
+        assert_eq!(
+            &res[ENCASE_ALIGN_HEADER.len()..],
+            "
+#[derive(Debug, Clone, encase::ShaderType)]
+pub struct Particle {
+    #[size(32)]
+    pub mass: f32,
+    pub velocity: glam::Vec3,
+    #[size(runtime)]
+    pub particles: Vec<f32>,
+}
+"
+        );
+    }
+
+    #[test]
+    pub fn encase_natural_layout() {
+        let res = generate(
+            "struct Light { position: vec3<f32>, intensity: f32 }",
+            Some("Light"),
+            RustStructStyle::Encase,
+        )
+        .unwrap();
+
+        assert!(res.starts_with("#[derive(Debug, Clone, encase::ShaderType)]"));
+    }
+}
@@ -0,0 +1,426 @@
+// Checks that the outputs of vertex entry points match the inputs of the fragment entry points
+// they are paired with. Naga validates each entry point on its own, so these mismatches would
+// otherwise only be reported when creating a pipeline.
+
+use lsp_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Uri};
+use naga::{Binding, EntryPoint, Interpolation, Module, Sampling, ShaderStage};
+
+use crate::{
+    config::{InterfaceCheckingConfig, InterfacePairing, Lint},
+    document_tracker::{DocumentTracker, TrackedDocument},
+    interface::{
+        InterfaceVariable, entry_point_inputs, entry_point_outputs, interface_variable_range,
+        interpolate_str,
+    },
+    lexer::Keyword,
+    parser::find_declaration,
+    range_tools::new_location,
+};
+
+struct StageEntryPoint<'a> {
+    document: &'a TrackedDocument,
+    module: &'a Module,
+    entry_point: &'a EntryPoint,
+}
+
+impl StageEntryPoint<'_> {
+    fn location(&self, range: std::ops::Range<usize>) -> lsp_types::Location {
+        new_location(range, &self.document.content, self.document.uri.clone())
+    }
+
+    fn variable_location(&self, variable: &InterfaceVariable) -> Option<lsp_types::Location> {
+        let content = &self.document.content;
+        interface_variable_range(self.module, content, self.entry_point, variable)
+            .map(|range| self.location(range))
+    }
+
+    fn name_location(&self) -> Option<lsp_types::Location> {
+        find_declaration(&self.document.content, Keyword::Fn, &self.entry_point.name)
+            .map(|(_, name)| self.location(name))
+    }
+}
+
+fn stage_entry_points(
+    document: &TrackedDocument,
+    stage: ShaderStage,
+) -> impl Iterator<Item = StageEntryPoint<'_>> {
+    let module = match &document.compilation_result {
+        Some(Ok((module, _))) => Some(module),
+        _ => None,
+    };
+
+    module.into_iter().flat_map(move |module| {
+        module
+            .entry_points
+            .iter()
+            .filter(move |entry_point| entry_point.stage == stage)
+            .map(move |entry_point| StageEntryPoint {
+                document,
+                module,
+                entry_point,
+            })
+    })
+}
+
+// Matches a reference of the form `name` or `path/to/file.wgsl:name`, where the path is made of
+// the last components of the document's path
+fn entry_point_matches(reference: &str, uri: &Uri, name: &str) -> bool {
+    match reference.rsplit_once(':') {
+        Some((path, entry_point)) => {
+            let path = path.trim_start_matches("./");
+            // Spaces and non-ASCII characters are percent-encoded in the URI, but not in the reference
+            let document_path = uri.path().as_estr().decode().into_string_lossy();
+            entry_point == name
+                && document_path
+                    .strip_suffix(path)
+                    .is_some_and(|rest| rest.ends_with('/'))
+        }
+        None => reference == name,
+    }
+}
+
+fn location_of(variable: &InterfaceVariable) -> Option<u32> {
+    match variable.binding {
+        Binding::Location { location, .. } => Some(location),
+        Binding::BuiltIn(_) => None,
+    }
+}
+
+// The interpolation of a variable, with the sampling defaulted as described by the WGSL spec
+fn interpolation_of(variable: &InterfaceVariable) -> Option<String> {
+    let Binding::Location {
+        interpolation,
+        sampling,
+        ..
+    } = variable.binding
+    else {
+        return None;
+    };
+
+    let sampling = sampling.or(match interpolation? {
+        Interpolation::Flat => Some(Sampling::First),
+        Interpolation::Perspective | Interpolation::Linear => Some(Sampling::Center),
+    });
+
+    interpolate_str(interpolation, sampling)
+}
+
+// Compares the inputs of `fragment` against the outputs of `vertex`
+fn check_pair(vertex: &StageEntryPoint, fragment: &StageEntryPoint) -> Vec<Diagnostic> {
+    let outputs = entry_point_outputs(vertex.module, vertex.entry_point);
+    let mut diagnostics = vec![];
+
+    for input in entry_point_inputs(fragment.module, fragment.entry_point) {
+        let Some(location) = location_of(&input) else {
+            continue;
+        };
+
+        let output = outputs
+            .iter()
+            .find(|output| location_of(output) == Some(location));
+
+        let input_ty = input.ty.to_wgsl(&fragment.module.to_ctx());
+        let vertex_name = &vertex.entry_point.name;
+
+        let (message, related_message) = match output {
+            None => (
+                format!(
+                    "Fragment input `{}` at @location({location}) is not written by vertex entry point `{vertex_name}`",
+                    input.name
+                ),
+                format!("Vertex entry point `{vertex_name}` declared here"),
+            ),
+            Some(output) => {
+                let output_ty = output.ty.to_wgsl(&vertex.module.to_ctx());
+                let input_interpolation = interpolation_of(&input);
+                let output_interpolation = interpolation_of(output);
+
+                if output_ty != input_ty {
+                    (
+                        format!(
+                            "Fragment input `{}` at @location({location}) has type `{input_ty}`, but vertex entry point `{vertex_name}` outputs `{output_ty}`",
+                            input.name
+                        ),
+                        format!("Vertex output `{}` declared here", output.name),
+                    )
+                } else if output_interpolation != input_interpolation {
+                    (
+                        format!(
+                            "Fragment input `{}` at @location({location}) uses {}, but vertex entry point `{vertex_name}` outputs {}",
+                            input.name,
+                            input_interpolation.unwrap_or_default(),
+                            output_interpolation.unwrap_or_default()
+                        ),
+                        format!("Vertex output `{}` declared here", output.name),
+                    )
+                } else {
+                    continue;
+                }
+            }
+        };
+
+        let Some(input_location) = fragment.variable_location(&input) else {
+            continue;
+        };
+
+        let related_location = match output {
+            Some(output) => vertex.variable_location(output),
+            None => vertex.name_location(),
+        };
+
+        diagnostics.push(Diagnostic {
+            range: input_location.range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: Lint::InterfaceMismatch.code(),
+            message,
+            related_information: related_location.map(|location| {
+                vec![DiagnosticRelatedInformation {
+                    location,
+                    message: related_message,
+                }]
+            }),
+            source: Some("wgsl-language-support".to_owned()),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+impl DocumentTracker {
+    /// Interface mismatches between the fragment entry points of `document`,
+    /// and the vertex entry points they are paired with.
+    pub fn stage_interface_diagnostics(
+        &self,
+        document: &TrackedDocument,
+        config: &InterfaceCheckingConfig,
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for fragment in stage_entry_points(document, ShaderStage::Fragment) {
+            let name = &fragment.entry_point.name;
+
+            let explicit_pairs: Vec<_> = config
+                .pairs
+                .iter()
+                .filter(|pair| entry_point_matches(&pair.fragment, &document.uri, name))
+                .collect();
+
+            let vertices: Vec<_> = if !explicit_pairs.is_empty() {
+                self.iter()
+                    .flat_map(|doc| stage_entry_points(doc, ShaderStage::Vertex))
+                    .filter(|vertex| {
+                        explicit_pairs.iter().any(|pair| {
+                            entry_point_matches(
+                                &pair.vertex,
+                                &vertex.document.uri,
+                                &vertex.entry_point.name,
+                            )
+                        })
+                    })
+                    .collect()
+            } else {
+                let vertices: Vec<_> = match config.pairing {
+                    InterfacePairing::Document => {
+                        stage_entry_points(document, ShaderStage::Vertex).collect()
+                    }
+                    InterfacePairing::Workspace => self
+                        .iter()
+                        .flat_map(|doc| stage_entry_points(doc, ShaderStage::Vertex))
+                        .collect(),
+                    InterfacePairing::Off => vec![],
+                };
+
+                // Files with several passes, like a depth only pass next to the main one, can not
+                // be paired without knowing the pipelines, so they need explicit pairs
+                match vertices.len() {
+                    1 => vertices,
+                    _ => vec![],
+                }
+            };
+
+            for vertex in &vertices {
+                diagnostics.extend(check_pair(vertex, &fragment));
+            }
+        }
+
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::TextDocumentItem;
+
+    use super::*;
+    use crate::config::EntryPointPair;
+
+    fn tracker(documents: &[(&str, &str)]) -> DocumentTracker {
+        let mut tracker = DocumentTracker::new();
+        for (path, text) in documents {
+            tracker.insert(TextDocumentItem {
+                uri: format!("file:///shaders/{path}").parse().unwrap(),
+                language_id: "wgsl".to_owned(),
+                version: 0,
+                text: text.to_string(),
+            });
+        }
+        tracker
+    }
+
+    fn messages(
+        tracker: &DocumentTracker,
+        path: &str,
+        config: &InterfaceCheckingConfig,
+    ) -> Vec<String> {
+        let uri = format!("file:///shaders/{path}").parse().unwrap();
+        let document = tracker.get(&uri).unwrap();
+        tracker
+            .stage_interface_diagnostics(document, config)
+            .into_iter()
+            .map(|it| it.message)
+            .collect()
+    }
+
+    const VERTEX: &str = "
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) id: u32,
+}
+
+@vertex fn vs_main() -> VertexOutput {
+    return VertexOutput(vec4<f32>(0.0), vec2<f32>(0.0), 0u);
+}
+";
+
+    const FRAGMENT: &str = "
+@fragment fn fs_main(
+    @location(0) uv: vec3<f32>,
+    @location(1) @interpolate(flat) id: u32,
+    @location(2) @interpolate(linear) color: vec4<f32>,
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(uv, 1.0) + color;
+}
+";
+
+    #[test]
+    pub fn matching_interfaces() {
+        let source = format!(
+            "{VERTEX}\n@fragment fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {{ return vec4<f32>(in.uv, 0.0, 1.0); }}"
+        );
+        let tracker = tracker(&[("mesh.wgsl", &source)]);
+
+        assert!(messages(&tracker, "mesh.wgsl", &Default::default()).is_empty());
+    }
+
+    #[test]
+    pub fn mismatched_interfaces() {
+        let source = format!(
+            "{VERTEX}\n{}",
+            FRAGMENT.replace("@interpolate(flat) id", "id")
+        );
+        let tracker = tracker(&[("mesh.wgsl", &source)]);
+
+        assert_eq!(
+            messages(&tracker, "mesh.wgsl", &Default::default()),
+            vec![
+                "Fragment input `uv` at @location(0) has type `vec3<f32>`, but vertex entry point `vs_main` outputs `vec2<f32>`",
+                "Fragment input `color` at @location(2) is not written by vertex entry point `vs_main`",
+            ]
+        );
+    }
+
+    #[test]
+    pub fn interpolation_mismatch() {
+        let source = format!(
+            "{VERTEX}\n@fragment fn fs_main(@location(0) @interpolate(linear) uv: vec2<f32>) -> @location(0) vec4<f32> {{ return vec4<f32>(uv, 0.0, 1.0); }}"
+        );
+        let tracker = tracker(&[("mesh.wgsl", &source)]);
+
+        assert_eq!(
+            messages(&tracker, "mesh.wgsl", &Default::default()),
+            vec![
+                "Fragment input `uv` at @location(0) uses @interpolate(linear, center), but vertex entry point `vs_main` outputs @interpolate(perspective, center)"
+            ]
+        );
+    }
+
+    #[test]
+    pub fn ambiguous_pairing() {
+        let shadow =
+            "@vertex fn vs_shadow() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }";
+        let source = format!("{VERTEX}\n{shadow}\n{FRAGMENT}");
+        let tracker = tracker(&[("mesh.wgsl", &source)]);
+
+        assert!(messages(&tracker, "mesh.wgsl", &Default::default()).is_empty());
+
+        let explicit = InterfaceCheckingConfig {
+            pairing: InterfacePairing::Document,
+            pairs: vec![EntryPointPair {
+                vertex: "vs_main".to_owned(),
+                fragment: "fs_main".to_owned(),
+            }],
+        };
+        assert_eq!(messages(&tracker, "mesh.wgsl", &explicit).len(), 2);
+    }
+
+    #[test]
+    pub fn paths_match_whole_components() {
+        let uri = "file:///shaders/othermesh.wgsl".parse().unwrap();
+        assert!(!entry_point_matches("mesh.wgsl:vs_main", &uri, "vs_main"));
+        assert!(entry_point_matches(
+            "othermesh.wgsl:vs_main",
+            &uri,
+            "vs_main"
+        ));
+        assert!(entry_point_matches(
+            "./shaders/othermesh.wgsl:vs_main",
+            &uri,
+            "vs_main"
+        ));
+        assert!(!entry_point_matches(
+            "othermesh.wgsl:fs_main",
+            &uri,
+            "vs_main"
+        ));
+    }
+
+    #[test]
+    pub fn paths_are_percent_decoded() {
+        let uri = "file:///my%20shaders/caf%C3%A9.wgsl".parse().unwrap();
+        assert!(entry_point_matches("café.wgsl:vs_main", &uri, "vs_main"));
+        assert!(entry_point_matches(
+            "my shaders/café.wgsl:vs_main",
+            &uri,
+            "vs_main"
+        ));
+        assert!(!entry_point_matches(
+            "caf%C3%A9.wgsl:vs_main",
+            &uri,
+            "vs_main"
+        ));
+    }
+
+    #[test]
+    pub fn pairing_across_documents() {
+        let tracker = tracker(&[("vertex.wgsl", VERTEX), ("fragment.wgsl", FRAGMENT)]);
+
+        assert!(messages(&tracker, "fragment.wgsl", &Default::default()).is_empty());
+
+        let workspace = InterfaceCheckingConfig {
+            pairing: InterfacePairing::Workspace,
+            pairs: vec![],
+        };
+        assert_eq!(messages(&tracker, "fragment.wgsl", &workspace).len(), 2);
+
+        let explicit = InterfaceCheckingConfig {
+            pairing: InterfacePairing::Off,
+            pairs: vec![EntryPointPair {
+                vertex: "vertex.wgsl:vs_main".to_owned(),
+                fragment: "fs_main".to_owned(),
+            }],
+        };
+        assert_eq!(messages(&tracker, "fragment.wgsl", &explicit).len(), 2);
+    }
+}
@@ -1,23 +1,55 @@
-use lsp_types::{DocumentSymbol, SymbolKind};
-use naga::Handle;
+use std::ops::Range;
+
+use lsp_types::{DocumentSymbol, Location, OneOf, SymbolKind, WorkspaceSymbol};
+use naga::{AddressSpace, Handle, Span, StorageAccess};
 use regex::Regex;
 
-use crate::{document_tracker::TrackedDocument, range_tools::span_to_lsp_range};
+use crate::{
+    document_tracker::TrackedDocument,
+    fuzzy::fuzzy_match,
+    lexer::Keyword,
+    parser::{find_declaration, find_ident, find_struct_members},
+    range_tools::{range_to_span, span_to_lsp_range},
+};
 
 pub enum SymbolItem {
     Constant(Handle<naga::Constant>),
+    Override(Handle<naga::Override>),
+    GlobalVariable(Handle<naga::GlobalVariable>),
     Function(Handle<naga::Function>),
+    // Index into `Module::entry_points`
+    EntryPoint(usize),
     Struct(Handle<naga::Type>),
+    Alias(Handle<naga::Type>),
+}
+// Splits a function declaration into its arguments and return type.
+// Arguments may contain attributes with parentheses, so the closing parenthesis must be matched.
+fn split_function_signature(signature: &str) -> Option<(&str, &str)> {
+    let fn_start = signature.find("fn")?;
+    let open = fn_start + signature[fn_start..].find('(')?;
+
+    let mut depth = 0;
+    let close = signature[open..].char_indices().find_map(|(index, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        (depth == 0).then_some(open + index)
+    })?;
+
+    let rest = &signature[close + 1..];
+    let result = rest[..rest.find('{').unwrap_or(rest.len())].trim();
+
+    Some((
+        &signature[open + 1..close],
+        result.strip_prefix("->").unwrap_or(result),
+    ))
 }
 
 fn parse_function_signature(signature: &str) -> String {
     let signature = signature.replace('\n', " ");
-    if let Some((_, [args, _, result])) = Regex::new(r"fn +.+? *\((.*?)\)\s*(->|)\s*(.*?) *?\{")
-        .unwrap()
-        .captures_iter(&signature)
-        .map(|c| c.extract())
-        .next()
-    {
+    if let Some((args, result)) = split_function_signature(&signature) {
         let args: Vec<&str> = args
             .split_ascii_whitespace()
             .map(|s| s.trim())
@@ -36,90 +68,372 @@ fn parse_function_signature(signature: &str) -> String {
     }
 }
 
-impl<'a> SymbolItem {
+// Extracts the declared type from a declaration of the form `name: type = initializer;`
+fn parse_declared_type(declaration: &str) -> Option<String> {
+    let declaration = declaration.replace('\n', " ");
+    Regex::new(r":\s*(.+?)\s*(=|;|$)")
+        .unwrap()
+        .captures(&declaration)
+        .map(|c| c[1].to_owned())
+}
+
+fn address_space_str(space: AddressSpace) -> Option<&'static str> {
+    match space {
+        AddressSpace::Function => Some("function"),
+        AddressSpace::Private => Some("private"),
+        AddressSpace::WorkGroup => Some("workgroup"),
+        AddressSpace::Uniform => Some("uniform"),
+        AddressSpace::Storage { access } if access.contains(StorageAccess::STORE) => {
+            Some("storage, read_write")
+        }
+        AddressSpace::Storage { .. } => Some("storage, read"),
+        AddressSpace::PushConstant => Some("push_constant"),
+        // Textures and samplers are declared without an address space
+        AddressSpace::Handle => None,
+    }
+}
+
+impl SymbolItem {
     fn symbol_kind(&self) -> SymbolKind {
         match self {
-            SymbolItem::Constant(_) => SymbolKind::CONSTANT,
-            SymbolItem::Function(_) => SymbolKind::FUNCTION,
+            SymbolItem::Constant(_) | SymbolItem::Override(_) => SymbolKind::CONSTANT,
+            SymbolItem::GlobalVariable(_) => SymbolKind::VARIABLE,
+            SymbolItem::Function(_) | SymbolItem::EntryPoint(_) => SymbolKind::FUNCTION,
             SymbolItem::Struct(_) => SymbolKind::STRUCT,
+            SymbolItem::Alias(_) => SymbolKind::TYPE_PARAMETER,
         }
     }
 
-    fn parse_detail(&'a self, content: &'a str) -> String {
-        match self {
-            SymbolItem::Constant(_) => content.to_owned(),
-            SymbolItem::Function(_) => parse_function_signature(content),
-            SymbolItem::Struct(_) => content.to_owned(),
+    fn detail(&self, module: &naga::Module, content: &str) -> Option<String> {
+        match *self {
+            SymbolItem::Constant(_) | SymbolItem::Override(_) | SymbolItem::Alias(_) => {
+                Some(content.to_owned())
+            }
+            SymbolItem::GlobalVariable(handle) => {
+                let global = &module.global_variables[handle];
+                let mut detail = String::new();
+
+                if let Some(binding) = &global.binding {
+                    detail += &format!("@group({}) @binding({}) ", binding.group, binding.binding);
+                }
+
+                match address_space_str(global.space) {
+                    Some(space) => detail += &format!("var<{space}>"),
+                    None => detail += "var",
+                }
+
+                if let Some(ty) = parse_declared_type(content) {
+                    detail += &format!(": {ty}");
+                }
+
+                Some(detail)
+            }
+            SymbolItem::Function(_) => Some(parse_function_signature(content)),
+            SymbolItem::EntryPoint(index) => {
+                let entry_point = &module.entry_points[index];
+                let signature = parse_function_signature(content);
+
+                Some(match entry_point.stage {
+                    naga::ShaderStage::Vertex => format!("@vertex {signature}"),
+                    naga::ShaderStage::Fragment => format!("@fragment {signature}"),
+                    naga::ShaderStage::Compute => {
+                        let [x, y, z] = entry_point.workgroup_size;
+                        format!("@compute @workgroup_size({x}, {y}, {z}) {signature}")
+                    }
+                })
+            }
+            // Struct members are listed as children instead
+            SymbolItem::Struct(_) => None,
         }
     }
+
+    fn name(&self, module: &naga::Module) -> String {
+        match *self {
+            SymbolItem::Constant(handle) => module.constants[handle].name.clone(),
+            SymbolItem::Override(handle) => module.overrides[handle].name.clone(),
+            SymbolItem::GlobalVariable(handle) => module.global_variables[handle].name.clone(),
+            SymbolItem::Function(handle) => module.functions[handle].name.clone(),
+            SymbolItem::EntryPoint(index) => Some(module.entry_points[index].name.clone()),
+            SymbolItem::Struct(handle) | SymbolItem::Alias(handle) => {
+                module.types[handle].name.clone()
+            }
+        }
+        .unwrap_or_default()
+    }
+
+    // Returns the span of the whole declaration along with the span of its name
+    fn spans(&self, module: &naga::Module, content: &str) -> (Span, Span) {
+        let span = match *self {
+            SymbolItem::Constant(handle) => module.constants.get_span(handle),
+            SymbolItem::Override(handle) => module.overrides.get_span(handle),
+            SymbolItem::GlobalVariable(handle) => module.global_variables.get_span(handle),
+            SymbolItem::Function(handle) => module.functions.get_span(handle),
+            SymbolItem::Struct(handle) => module.types.get_span(handle),
+            // Naga does not keep track of entry point or alias spans, so we have to find them ourselves
+            SymbolItem::EntryPoint(_) | SymbolItem::Alias(_) => {
+                let keyword = match self {
+                    SymbolItem::EntryPoint(_) => Keyword::Fn,
+                    _ => Keyword::Alias,
+                };
+
+                return find_declaration(content, keyword, &self.name(module))
+                    .map(|(declaration, name)| (range_to_span(declaration), range_to_span(name)))
+                    .unwrap_or_default();
+            }
+        };
+
+        let name_span = span
+            .to_range()
+            .and_then(|range| find_ident(content, range, &self.name(module)))
+            .map(range_to_span)
+            .unwrap_or(span);
+
+        (span, name_span)
+    }
+}
+
+/// Every top-level item declared in a module
+fn symbol_items(module: &naga::Module) -> impl Iterator<Item = SymbolItem> + '_ {
+    let constants = module
+        .constants
+        .iter()
+        .map(|(handle, _)| SymbolItem::Constant(handle));
+
+    let overrides = module
+        .overrides
+        .iter()
+        .map(|(handle, _)| SymbolItem::Override(handle));
+
+    let globals = module
+        .global_variables
+        .iter()
+        .map(|(handle, _)| SymbolItem::GlobalVariable(handle));
+
+    let functions = module
+        .functions
+        .iter()
+        .map(|(handle, _)| SymbolItem::Function(handle));
+
+    let entry_points = (0..module.entry_points.len()).map(SymbolItem::EntryPoint);
+
+    let types = module.types.iter().flat_map(|(handle, ty)| match ty.inner {
+        naga::TypeInner::Struct { .. } => Some(SymbolItem::Struct(handle)),
+        _ if ty.name.is_some() => Some(SymbolItem::Alias(handle)),
+        _ => None,
+    });
+
+    constants
+        .chain(overrides)
+        .chain(globals)
+        .chain(functions)
+        .chain(entry_points)
+        .chain(types)
 }
 
 pub trait SymbolProvider {
     fn get_symbols(&self) -> Vec<DocumentSymbol>;
+    fn get_workspace_symbols(&self, query: &str) -> Vec<(u32, WorkspaceSymbol)>;
     fn document_symbol(&self, module: &naga::Module, item: SymbolItem) -> DocumentSymbol;
 }
 
-impl SymbolProvider for TrackedDocument {
-    fn document_symbol(&self, module: &naga::Module, item: SymbolItem) -> DocumentSymbol {
-        let name = match item {
-            SymbolItem::Constant(handle) => &module.constants[handle].name,
-            SymbolItem::Function(handle) => &module.functions[handle].name,
-            SymbolItem::Struct(handle) => &module.types[handle].name,
-        }
-        .clone()
-        .unwrap_or_default();
+impl TrackedDocument {
+    fn struct_member_symbols(
+        &self,
+        module: &naga::Module,
+        item: &SymbolItem,
+    ) -> Vec<DocumentSymbol> {
+        let SymbolItem::Struct(handle) = *item else {
+            return vec![];
+        };
 
-        let span = match item {
-            SymbolItem::Constant(handle) => module.constants.get_span(handle),
-            SymbolItem::Function(handle) => module.functions.get_span(handle),
-            SymbolItem::Struct(handle) => module.types.get_span(handle),
+        let naga::TypeInner::Struct { members, .. } = &module.types[handle].inner else {
+            return vec![];
         };
 
-        let span_content = &self.content[span.to_range().unwrap_or_default()];
+        let body = module.types.get_span(handle).to_range().unwrap_or_default();
+        let declarations = find_struct_members(&self.content, body);
 
-        let detail = Some(item.parse_detail(span_content).to_owned());
+        members
+            .iter()
+            .filter_map(|member| {
+                let name = member.name.clone()?;
+                let (range, name_range) = declarations
+                    .iter()
+                    .find(|(_, name_range)| self.content[name_range.clone()] == name)?
+                    .clone();
 
+                Some(self.new_document_symbol(
+                    name,
+                    SymbolKind::FIELD,
+                    parse_declared_type(&self.content[range.clone()]),
+                    range,
+                    name_range,
+                    None,
+                ))
+            })
+            .collect()
+    }
+
+    fn new_document_symbol(
+        &self,
+        name: String,
+        kind: SymbolKind,
+        detail: Option<String>,
+        range: Range<usize>,
+        selection_range: Range<usize>,
+        children: Option<Vec<DocumentSymbol>>,
+    ) -> DocumentSymbol {
         #[allow(deprecated)]
         // https://github.com/gluon-lang/lsp-types/issues/226
         DocumentSymbol {
             name,
-            kind: item.symbol_kind(),
+            kind,
             detail,
-            range: span_to_lsp_range(span, &self.content),
-            selection_range: span_to_lsp_range(span, &self.content),
-            children: None,
+            range: span_to_lsp_range(range_to_span(range), &self.content),
+            selection_range: span_to_lsp_range(range_to_span(selection_range), &self.content),
+            children,
             deprecated: None,
             tags: None,
         }
     }
+}
+
+impl SymbolProvider for TrackedDocument {
+    fn document_symbol(&self, module: &naga::Module, item: SymbolItem) -> DocumentSymbol {
+        let (span, name_span) = item.spans(module, &self.content);
+        let range = span.to_range().unwrap_or_default();
+        let detail = item.detail(module, &self.content[range.clone()]);
+
+        let children = match item {
+            SymbolItem::Struct(_) => Some(self.struct_member_symbols(module, &item)),
+            _ => None,
+        };
+
+        self.new_document_symbol(
+            item.name(module),
+            item.symbol_kind(),
+            detail,
+            range,
+            name_span.to_range().unwrap_or_default(),
+            children,
+        )
+    }
 
     fn get_symbols(&self) -> Vec<DocumentSymbol> {
         let Some(Ok((module, _))) = &self.compilation_result else {
             return vec![];
         };
 
-        let constants = module
-            .constants
-            .iter()
-            .map(|(handle, _)| SymbolItem::Constant(handle));
+        let mut symbols: Vec<_> = symbol_items(module)
+            .map(|item| self.document_symbol(module, item))
+            .collect();
 
-        let functions = module
-            .functions
-            .iter()
-            .map(|(handle, _)| SymbolItem::Function(handle));
+        symbols.sort_by_key(|symbol| symbol.range.start);
+        symbols
+    }
 
-        let structs = module.types.iter().flat_map(|(handle, ty)| {
-            if ty.name.is_some() {
-                Some(SymbolItem::Struct(handle))
-            } else {
-                None
-            }
-        });
+    // Symbols are returned along with their match score so results can be ranked across documents
+    fn get_workspace_symbols(&self, query: &str) -> Vec<(u32, WorkspaceSymbol)> {
+        let Some(module) = &self.last_valid_module else {
+            return vec![];
+        };
 
-        constants
-            .chain(functions)
-            .chain(structs)
-            .map(|item| self.document_symbol(module, item))
+        symbol_items(module)
+            .filter_map(|item| {
+                let name = item.name(module);
+                let score = fuzzy_match(query, &name)?;
+                let (span, name_span) = item.spans(module, &self.content);
+
+                // The last valid module can be older than the content, so symbols which were
+                // moved or removed by later edits are left out
+                if self.content.get(name_span.to_range()?) != Some(name.as_str()) {
+                    return None;
+                }
+
+                let symbol = WorkspaceSymbol {
+                    name,
+                    kind: item.symbol_kind(),
+                    tags: None,
+                    container_name: None,
+                    location: OneOf::Left(Location::new(
+                        self.uri.clone(),
+                        span_to_lsp_range(span, &self.content),
+                    )),
+                    data: None,
+                };
+
+                Some((score, symbol))
+            })
             .collect()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn function_signature() {
+        let signature = parse_function_signature("fn add(a: f32,\n b: f32) -> f32 {");
+        assert_eq!(signature, "fn (a: f32, b: f32) -> f32");
+    }
+
+    #[test]
+    pub fn function_signature_with_attributes() {
+        let signature = parse_function_signature(
+            "fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {",
+        );
+        assert_eq!(
+            signature,
+            "fn (@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32>"
+        );
+    }
+
+    #[test]
+    pub fn declared_type() {
+        assert_eq!(
+            parse_declared_type("var<private> x: vec2<f32> = vec2(0.0);"),
+            Some("vec2<f32>".to_owned())
+        );
+        assert_eq!(
+            parse_declared_type("a: array<f32, 4>"),
+            Some("array<f32, 4>".to_owned())
+        );
+    }
+
+    #[test]
+    pub fn workspace_symbols_follow_edits() {
+        use lsp_types::{
+            DidChangeTextDocumentParams, TextDocumentContentChangeEvent, TextDocumentItem,
+            VersionedTextDocumentIdentifier,
+        };
+
+        use crate::document_tracker::DocumentTracker;
+
+        let uri: lsp_types::Uri = "file:///shader.wgsl".parse().unwrap();
+        let mut tracker = DocumentTracker::new();
+        tracker.insert(TextDocumentItem {
+            uri: uri.clone(),
+            language_id: "wgsl".to_owned(),
+            version: 0,
+            text: "fn first() {}\nfn second() {}\n".to_owned(),
+        });
+
+        // Removing `second` leaves the document unparsable, so the last valid module still has it
+        tracker.update(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri, 1),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "fn first() {}\nfn".to_owned(),
+            }],
+        });
+
+        let names: Vec<_> = tracker
+            .get_workspace_symbols("")
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert_eq!(names, ["first"]);
+    }
+}